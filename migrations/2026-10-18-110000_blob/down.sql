@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS blob;
+ALTER TABLE image DROP COLUMN hash;
+ALTER TABLE image ADD CONSTRAINT image_path_key UNIQUE (path);
\ No newline at end of file
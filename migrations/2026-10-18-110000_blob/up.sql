@@ -0,0 +1,16 @@
+CREATE TABLE blob (
+  path UUID PRIMARY KEY,
+  hash TEXT,
+  user_id UUID,
+  size BIGINT,
+  ref_count INTEGER NOT NULL DEFAULT 1,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX blob_hash_idx ON blob (hash);
+
+ALTER TABLE image DROP CONSTRAINT image_path_key;
+ALTER TABLE image ADD COLUMN hash TEXT;
+
+-- blobs stored before deduplication are referenced only by their own image
+INSERT INTO blob (path, user_id) SELECT path, user_id FROM image;
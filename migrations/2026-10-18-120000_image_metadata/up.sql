@@ -0,0 +1,18 @@
+CREATE TABLE image_metadata (
+  image_id UUID PRIMARY KEY,
+  width INTEGER,
+  height INTEGER,
+  mime_type TEXT,
+  byte_size BIGINT NOT NULL,
+  captured_at TIMESTAMPTZ,
+  camera_model TEXT,
+  orientation INTEGER,
+  gps_latitude DOUBLE PRECISION,
+  gps_longitude DOUBLE PRECISION,
+  gps_altitude DOUBLE PRECISION,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  FOREIGN KEY (image_id) REFERENCES image (id) ON DELETE CASCADE
+);
+
+CREATE INDEX image_metadata_captured_at_idx ON image_metadata (captured_at);
+CREATE INDEX image_metadata_camera_model_idx ON image_metadata (camera_model);
@@ -0,0 +1,3 @@
+ALTER TABLE image_metadata DROP COLUMN exif_policy;
+ALTER TABLE account DROP COLUMN exif_policy, DROP COLUMN exif_retain_private;
+ALTER TABLE sys_config DROP COLUMN exif_policy, DROP COLUMN exif_retain_private;
\ No newline at end of file
@@ -0,0 +1,11 @@
+ALTER TABLE sys_config
+  ADD COLUMN exif_policy VARCHAR(255) NOT NULL DEFAULT 'keep',
+  ADD COLUMN exif_retain_private BOOLEAN NOT NULL DEFAULT TRUE;
+
+-- NULL means sys_config default is used
+ALTER TABLE account
+  ADD COLUMN exif_policy VARCHAR(255),
+  ADD COLUMN exif_retain_private BOOLEAN;
+
+-- policy applied on upload, fields it strips are visible only to the owner
+ALTER TABLE image_metadata ADD COLUMN exif_policy VARCHAR(255) NOT NULL DEFAULT 'keep';
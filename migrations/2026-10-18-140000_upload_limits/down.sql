@@ -0,0 +1,5 @@
+DROP TABLE upload_limit;
+ALTER TABLE sys_config
+  DROP COLUMN upload_mime_types,
+  DROP COLUMN upload_max_width,
+  DROP COLUMN upload_max_height;
\ No newline at end of file
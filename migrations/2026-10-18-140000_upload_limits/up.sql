@@ -0,0 +1,15 @@
+ALTER TABLE sys_config
+  ADD COLUMN upload_mime_types TEXT[] NOT NULL DEFAULT '{image/jpeg,image/png,image/webp,image/gif}',
+  ADD COLUMN upload_max_width INTEGER NOT NULL DEFAULT 16384,
+  ADD COLUMN upload_max_height INTEGER NOT NULL DEFAULT 16384;
+
+-- max original size by account kind, kinds without limit use UPLOAD_MAX_SIZE
+CREATE TABLE upload_limit (
+  account_kind VARCHAR(255) PRIMARY KEY,
+  max_size BIGINT NOT NULL
+);
+
+INSERT INTO
+    upload_limit (account_kind, max_size)
+VALUES
+    ('creator', 52428800);
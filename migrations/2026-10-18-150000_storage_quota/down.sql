@@ -0,0 +1,6 @@
+ALTER TABLE account
+  DROP COLUMN quota_bytes,
+  DROP COLUMN quota_images,
+  DROP COLUMN used_bytes,
+  DROP COLUMN used_images;
+ALTER TABLE sys_config DROP COLUMN quota_bytes, DROP COLUMN quota_images;
\ No newline at end of file
@@ -0,0 +1,28 @@
+-- NULL means unlimited
+ALTER TABLE sys_config
+  ADD COLUMN quota_bytes BIGINT,
+  ADD COLUMN quota_images INTEGER;
+
+-- NULL quota means sys_config default is used
+ALTER TABLE account
+  ADD COLUMN quota_bytes BIGINT,
+  ADD COLUMN quota_images INTEGER,
+  ADD COLUMN used_bytes BIGINT NOT NULL DEFAULT 0,
+  ADD COLUMN used_images INTEGER NOT NULL DEFAULT 0;
+
+UPDATE account
+SET
+    used_bytes = usage.bytes,
+    used_images = usage.images
+FROM
+    (
+        SELECT
+            users.account_id,
+            COALESCE(SUM(blob.size), 0) AS bytes,
+            COUNT(image.id) AS images
+        FROM image
+            JOIN users ON users.id = image.user_id
+            LEFT JOIN blob ON blob.path = image.path
+        GROUP BY users.account_id
+    ) AS usage
+WHERE account.id = usage.account_id;
@@ -0,0 +1,2 @@
+ALTER TABLE image DROP COLUMN preview;
+ALTER TABLE image DROP COLUMN blurhash;
\ No newline at end of file
@@ -0,0 +1,2 @@
+ALTER TABLE image ADD COLUMN blurhash TEXT;
+ALTER TABLE image ADD COLUMN preview TEXT;
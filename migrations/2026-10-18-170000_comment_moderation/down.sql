@@ -0,0 +1 @@
+ALTER TABLE comment DROP COLUMN hidden_at;
\ No newline at end of file
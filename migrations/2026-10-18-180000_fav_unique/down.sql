@@ -0,0 +1 @@
+DROP INDEX fav_user_id_post_id;
\ No newline at end of file
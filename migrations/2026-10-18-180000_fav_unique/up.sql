@@ -0,0 +1,7 @@
+-- keep the oldest of duplicated favorites
+DELETE FROM fav a USING fav b
+WHERE a.user_id = b.user_id
+  AND a.post_id = b.post_id
+  AND (a.created_at, a.id) > (b.created_at, b.id);
+
+CREATE UNIQUE INDEX fav_user_id_post_id ON fav (user_id, post_id);
@@ -0,0 +1,2 @@
+DROP TABLE invite;
+ALTER TABLE referral DROP COLUMN public_lvl;
\ No newline at end of file
@@ -0,0 +1,15 @@
+-- lowest public_lvl of referrer content the referral opens, 1 subscriber or 2 public
+ALTER TABLE referral ADD COLUMN public_lvl INT NOT NULL DEFAULT 1;
+
+CREATE TABLE invite (
+  id UUID PRIMARY KEY,
+  code VARCHAR(64) NOT NULL UNIQUE,
+  referrer_id UUID NOT NULL,
+  public_lvl INT NOT NULL DEFAULT 1,
+  max_uses INT NOT NULL DEFAULT 1,
+  uses INT NOT NULL DEFAULT 0,
+  expires_at TIMESTAMPTZ,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  FOREIGN KEY (referrer_id) REFERENCES account (id) ON DELETE CASCADE
+);
@@ -0,0 +1 @@
+ALTER TABLE referral DROP COLUMN granted_by;
\ No newline at end of file
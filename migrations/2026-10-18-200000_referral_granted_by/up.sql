@@ -0,0 +1,2 @@
+-- account who granted referral, admin or creator by invite
+ALTER TABLE referral ADD COLUMN granted_by UUID REFERENCES account (id) ON DELETE SET NULL;
@@ -0,0 +1,16 @@
+-- login session, id is `id` claim of access tokens issued for it
+CREATE TABLE session (
+  id UUID PRIMARY KEY,
+  user_id UUID NOT NULL,
+  -- hash of current refresh token, rotated on every refresh
+  refresh_hash VARCHAR(128) NOT NULL,
+  user_agent VARCHAR(512),
+  expires_at TIMESTAMPTZ NOT NULL,
+  revoked_at TIMESTAMPTZ,
+  last_used_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+);
+
+CREATE INDEX session_user_id ON session (user_id);
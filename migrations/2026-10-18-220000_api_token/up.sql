@@ -0,0 +1,14 @@
+-- personal API token of user, `imt_{id}.{secret}` with HMAC of secret stored
+CREATE TABLE api_token (
+  id UUID PRIMARY KEY,
+  user_id UUID NOT NULL,
+  name VARCHAR(128) NOT NULL,
+  token_hash VARCHAR(128) NOT NULL,
+  scopes TEXT[] NOT NULL DEFAULT '{}',
+  expires_at TIMESTAMPTZ,
+  last_used_at TIMESTAMPTZ,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+);
+
+CREATE INDEX api_token_user_id ON api_token (user_id);
@@ -0,0 +1,2 @@
+-- hash of refresh token replaced by the last refresh, presenting it again means token was stolen
+ALTER TABLE session ADD COLUMN previous_refresh_hash VARCHAR(128);
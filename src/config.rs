@@ -1,5 +1,8 @@
 use std::{str::FromStr, sync::OnceLock};
 
+use crate::services::mail::MailKind;
+use crate::services::processor::ImageFormat;
+use crate::services::transform::{Crop, Operation};
 use crate::{crypt::b64u_decode, Error, Result};
 
 pub fn config() -> &'static Config {
@@ -10,27 +13,159 @@ pub fn config() -> &'static Config {
     })
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StoreKind {
+    Lust,
+    Local,
+}
+
+impl FromStr for StoreKind {
+    type Err = ();
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "lust" => Ok(StoreKind::Lust),
+            "local" => Ok(StoreKind::Local),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Who uploads of identical bytes share stored blob with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DedupScope {
+    User,
+    Global,
+}
+
+impl FromStr for DedupScope {
+    type Err = ();
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "user" => Ok(DedupScope::User),
+            "global" => Ok(DedupScope::Global),
+            _ => Err(()),
+        }
+    }
+}
+
 #[allow(non_snake_case)]
 pub struct Config {
     pub DB_URL: String,
-    pub LUST_URL: String,
+    pub IMAGE_STORE: StoreKind,
+    pub LOCAL_STORE_DIR: String,
+    pub PROCESSOR_CONFIG: String,
+    pub UPLOAD_DIR: String,
+    pub UPLOAD_MAX_SIZE: u64,
+    pub UPLOAD_DURATION: i64,
+    pub DEDUP_SCOPE: DedupScope,
+    pub LUST_URL: Option<String>,
     pub LUST_PROFILE_BUCKET: String,
     pub LUST_IMAGE_BUCKET: String,
     pub TOKEN_SECRET: Vec<u8>,
     pub PWD_KEY: Vec<u8>,
+    /// lifetime of access token in seconds
     pub TOKEN_DURATION: i64,
+    /// lifetime of session refresh token in seconds, extended on every refresh
+    pub REFRESH_TOKEN_DURATION: i64,
+    pub IMAGE_URL_BASE: String,
+    pub IMAGE_URL_DURATION: i64,
+    /// formats images are negotiated to, in server preference order
+    pub IMAGE_FORMATS: Vec<ImageFormat>,
+    /// allow-list of on-the-fly transformations
+    pub TRANSFORM_OPERATIONS: Vec<Operation>,
+    pub TRANSFORM_SIZES: Vec<u32>,
+    pub TRANSFORM_BLUR: Vec<u32>,
+    pub TRANSFORM_CROPS: Vec<Crop>,
+    /// when set, self-service registration requires this invite code
+    pub REGISTRATION_INVITE_CODE: Option<String>,
+    pub MAIL_TRANSPORT: MailKind,
+    /// directory of `file` mail transport
+    pub MAIL_DIR: String,
+    pub MAIL_FROM: String,
+    /// link of password reset mail, reset token is appended
+    pub PASSWORD_RESET_URL: String,
+    /// lifetime of password reset token in seconds
+    pub PASSWORD_RESET_DURATION: i64,
 }
 
 impl Config {
     fn load_from_env() -> Result<Config> {
+        let image_store = get_env_parse_or("IMAGE_STORE", StoreKind::Lust)?;
+        let lust_url = match image_store {
+            StoreKind::Lust => Some(get_env("LUST_URL")?),
+            StoreKind::Local => get_env("LUST_URL").ok(),
+        };
+
         Ok(Config {
             DB_URL: get_env("DATABASE_URL")?,
-            LUST_URL: get_env("LUST_URL")?,
+            IMAGE_STORE: image_store,
+            LOCAL_STORE_DIR: get_env("LOCAL_STORE_DIR")
+                .unwrap_or_else(|_| "./imagery_storage".to_string()),
+            PROCESSOR_CONFIG: get_env("PROCESSOR_CONFIG")
+                .unwrap_or_else(|_| "./lust_config/config.yaml".to_string()),
+            UPLOAD_DIR: get_env("UPLOAD_DIR").unwrap_or_else(|_| "./uploads".to_string()),
+            UPLOAD_MAX_SIZE: get_env_parse_or("UPLOAD_MAX_SIZE", 1024 * 1024 * 1024)?,
+            UPLOAD_DURATION: get_env_parse_or("UPLOAD_DURATION", 60 * 60 * 24)?,
+            DEDUP_SCOPE: get_env_parse_or("DEDUP_SCOPE", DedupScope::User)?,
+            LUST_URL: lust_url,
             LUST_PROFILE_BUCKET: "profile".to_string(),
             LUST_IMAGE_BUCKET: "imagery".to_string(),
             TOKEN_SECRET: get_env_b64u_as_u8s("TOKEN_SECRET")?,
             PWD_KEY: get_env_b64u_as_u8s("PWD_KEY")?,
             TOKEN_DURATION: get_env_parse("TOKEN_DURATION")?,
+            REFRESH_TOKEN_DURATION: get_env_parse_or("REFRESH_TOKEN_DURATION", 60 * 60 * 24 * 30)?,
+            IMAGE_URL_BASE: get_env("IMAGE_URL_BASE").unwrap_or_default(),
+            IMAGE_URL_DURATION: get_env_parse_or("IMAGE_URL_DURATION", 60 * 60)?,
+            IMAGE_FORMATS: get_env_list_parse_or(
+                "IMAGE_FORMATS",
+                vec![ImageFormat::Webp, ImageFormat::Jpeg],
+            )?,
+            TRANSFORM_OPERATIONS: get_env_list_parse_or(
+                "TRANSFORM_OPERATIONS",
+                vec![
+                    Operation::Resize,
+                    Operation::Crop,
+                    Operation::Rotate,
+                    Operation::Blur,
+                ],
+            )?,
+            TRANSFORM_SIZES: get_env_list_parse_or(
+                "TRANSFORM_SIZES",
+                vec![160, 320, 640, 1024, 1600],
+            )?,
+            TRANSFORM_BLUR: get_env_list_parse_or("TRANSFORM_BLUR", vec![10, 40])?,
+            TRANSFORM_CROPS: get_env_list_parse_or(
+                "TRANSFORM_CROPS",
+                vec![
+                    Crop {
+                        width: 1,
+                        height: 1,
+                    },
+                    Crop {
+                        width: 4,
+                        height: 3,
+                    },
+                    Crop {
+                        width: 3,
+                        height: 4,
+                    },
+                    Crop {
+                        width: 16,
+                        height: 9,
+                    },
+                ],
+            )?,
+            REGISTRATION_INVITE_CODE: get_env("REGISTRATION_INVITE_CODE")
+                .ok()
+                .filter(|code| !code.is_empty()),
+            MAIL_TRANSPORT: get_env_parse_or("MAIL_TRANSPORT", MailKind::Log)?,
+            MAIL_DIR: get_env("MAIL_DIR").unwrap_or_else(|_| "./mails".to_string()),
+            MAIL_FROM: get_env("MAIL_FROM").unwrap_or_else(|_| "imagery@localhost".to_string()),
+            PASSWORD_RESET_URL: get_env("PASSWORD_RESET_URL")
+                .unwrap_or_else(|_| "http://localhost:8080/reset-password?token=".to_string()),
+            PASSWORD_RESET_DURATION: get_env_parse_or("PASSWORD_RESET_DURATION", 60 * 60)?,
         })
     }
 }
@@ -45,6 +180,27 @@ fn get_env_parse<T: FromStr>(name: &'static str) -> Result<T> {
         .map_err(|_| Error::ConfigWrongFormat(name.to_string()))
 }
 
+fn get_env_parse_or<T: FromStr>(name: &'static str, default: T) -> Result<T> {
+    match std::env::var(name) {
+        Ok(val) => val
+            .parse::<T>()
+            .map_err(|_| Error::ConfigWrongFormat(name.to_string())),
+        Err(_) => Ok(default),
+    }
+}
+
+/// comma separated list, e.g. `avif,webp,jpeg`
+fn get_env_list_parse_or<T: FromStr>(name: &'static str, default: Vec<T>) -> Result<Vec<T>> {
+    match std::env::var(name) {
+        Ok(val) => val
+            .split(',')
+            .map(|item| item.trim().parse::<T>())
+            .collect::<std::result::Result<Vec<T>, _>>()
+            .map_err(|_| Error::ConfigWrongFormat(name.to_string())),
+        Err(_) => Ok(default),
+    }
+}
+
 fn get_env_b64u_as_u8s(name: &'static str) -> Result<Vec<u8>> {
     b64u_decode(&get_env(name)?).map_err(|_| Error::ConfigWrongFormat(name.to_string()))
 }
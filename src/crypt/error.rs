@@ -8,6 +8,9 @@ pub enum Error {
     TokenParseFailed,
     TokenExpired,
 
+    SignatureInvalid,
+    SignatureExpired,
+
     FailToB64uDecode,
 
     FailToCreateArgonEncoder,
@@ -2,11 +2,11 @@ use base64::{engine::general_purpose, Engine};
 
 mod error;
 pub mod pass;
+pub mod signed_url;
 pub mod token;
 
 pub use error::{Error, Result};
 
-#[allow(dead_code)]
 pub fn b64u_encode(content: impl AsRef<[u8]>) -> String {
     general_purpose::URL_SAFE_NO_PAD.encode(content)
 }
@@ -5,8 +5,6 @@ use argon2::{
     Argon2, Params, PasswordHash, PasswordHasher,
 };
 
-// TODO: encrypt for new user
-#[allow(dead_code)]
 pub fn encrypt_pwd(pwd: &str) -> Result<String> {
     let salt = SaltString::generate(&mut OsRng);
     let argon2 = Argon2::new_with_secret(
@@ -0,0 +1,85 @@
+use hmac::{Hmac, Mac};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use sha2::Sha512;
+use uuid::Uuid;
+
+use crate::config;
+
+use super::error::{Error, Result};
+use super::{b64u_decode, b64u_encode};
+
+/// ### SignedImage
+/// Image request authorized by signature instead of auth cookie,
+/// so image can be embedded on other origins and cached by CDN.\
+/// Signature covers stored path, size preset and expiry (unix seconds).
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignedImage {
+    pub path: Uuid,
+    pub size: Option<String>,
+    pub exp: i64,
+}
+
+impl SignedImage {
+    /// Expiry is rounded up to whole `IMAGE_URL_DURATION` windows,
+    /// so url stays the same within a window and can be cached.
+    pub fn new(path: Uuid, size: Option<String>) -> Self {
+        let duration = config().IMAGE_URL_DURATION.max(1);
+        let now = chrono::Utc::now().timestamp();
+        Self {
+            path,
+            size,
+            exp: (now / duration + 2) * duration,
+        }
+    }
+
+    pub fn sign(&self) -> Result<String> {
+        let mut mac = self.mac()?;
+        mac.update(self.message().as_bytes());
+        Ok(b64u_encode(mac.finalize().into_bytes()))
+    }
+
+    pub fn verify(&self, signature: &str) -> Result<()> {
+        let signature = b64u_decode(signature)?;
+        let mut mac = self.mac()?;
+        mac.update(self.message().as_bytes());
+        mac.verify_slice(&signature)
+            .map_err(|_| Error::SignatureInvalid)?;
+
+        match chrono::Utc::now().timestamp() < self.exp {
+            true => Ok(()),
+            false => Err(Error::SignatureExpired),
+        }
+    }
+
+    /// public url of the image, relative unless `IMAGE_URL_BASE` is set
+    pub fn url(&self) -> Result<String> {
+        let mut url = format!(
+            "{}/public/image/{}?exp={}&sig={}",
+            config().IMAGE_URL_BASE,
+            self.path,
+            self.exp,
+            self.sign()?
+        );
+        if let Some(size) = &self.size {
+            url.push_str(&format!(
+                "&size={}",
+                utf8_percent_encode(size, NON_ALPHANUMERIC)
+            ));
+        }
+        Ok(url)
+    }
+
+    fn mac(&self) -> Result<Hmac<Sha512>> {
+        Hmac::new_from_slice(&config().TOKEN_SECRET).map_err(|_| Error::TokenInvalidSecret)
+    }
+
+    // prefixed so image signature can't be mistaken for other use of the key
+    fn message(&self) -> String {
+        format!(
+            "image:{}:{}:{}",
+            self.path,
+            self.size.as_deref().unwrap_or_default(),
+            self.exp
+        )
+    }
+}
@@ -1,6 +1,7 @@
 use chrono::Local;
 use hmac::{Hmac, Mac};
 use jwt::{Error as JWTError, Header, SignWithKey, Token as JWTToken, VerifyWithKey};
+use rand::RngCore;
 use sha2::Sha512;
 use std::{collections::BTreeMap, str::FromStr};
 use uuid::Uuid;
@@ -8,6 +9,10 @@ use uuid::Uuid;
 use crate::config;
 
 use super::error::{Error, Result};
+use super::{b64u_decode, b64u_encode};
+
+/// random bytes of refresh, personal and reset token secret
+const SECRET_BYTES: usize = 32;
 
 pub struct Token(String);
 
@@ -19,11 +24,12 @@ pub struct TokenClaims {
 }
 
 impl Token {
-    pub fn new(user_id: &Uuid, nick: &str) -> Result<Token> {
+    /// short-lived access token of session `id`
+    pub fn new(id: &Uuid, user_id: &Uuid, nick: &str) -> Result<Token> {
         let key: Hmac<Sha512> = Hmac::new_from_slice(&config::config().TOKEN_SECRET)
             .map_err(|_| Error::TokenInvalidSecret)?;
         let mut claims = BTreeMap::new();
-        claims.insert("id", Uuid::new_v4().to_string());
+        claims.insert("id", id.to_string());
         claims.insert("user_id", user_id.to_string());
         claims.insert("nick", nick.to_string());
         let exp = Local::now() + chrono::Duration::seconds(config::config().TOKEN_DURATION);
@@ -63,30 +69,6 @@ impl Token {
         })
     }
 
-    #[allow(dead_code)]
-    fn update_exp(old_token: Token) -> Result<Token> {
-        let key: Hmac<Sha512> = Hmac::new_from_slice(&config::config().TOKEN_SECRET)
-            .map_err(|_| Error::TokenInvalidSecret)?;
-        let mut claims: BTreeMap<String, String> = old_token
-            .0
-            .verify_with_key(&key)
-            .map_err(|_| Error::TokenParseFailed)?;
-        let exp = claims
-            .get("exp")
-            .ok_or(Error::TokenParseFailed)?
-            .to_string();
-        let exp_time =
-            chrono::DateTime::parse_from_rfc3339(&exp).map_err(|_| Error::TokenParseFailed)?;
-        let time = exp_time + chrono::Duration::seconds(config::config().TOKEN_DURATION);
-        claims.insert("exp".to_string(), time.to_rfc3339());
-
-        let token_str = claims
-            .sign_with_key(&key)
-            .map_err(|_| Error::TokenInvalidSecret)?;
-
-        Ok(Token(token_str))
-    }
-
     pub fn validate(&self) -> Result<()> {
         let key: Hmac<Sha512> = Hmac::new_from_slice(&config::config().TOKEN_SECRET)
             .map_err(|_| Error::TokenInvalidSecret)?;
@@ -122,3 +104,160 @@ impl FromStr for Token {
         Ok(Token(s.to_string()))
     }
 }
+
+/// ### RefreshToken
+/// Opaque `{session_id}.{secret}` token, only HMAC of the secret is stored with session.\
+/// Every refresh replaces it, so refresh token can be used just once.
+pub struct RefreshToken {
+    pub session_id: Uuid,
+    secret: String,
+}
+
+impl RefreshToken {
+    pub fn new(session_id: &Uuid) -> Self {
+        Self {
+            session_id: *session_id,
+            secret: new_secret(),
+        }
+    }
+
+    pub fn hash(&self) -> Result<String> {
+        hash_secret(&self.secret)
+    }
+
+    /// compares secret with stored hash of session in constant time
+    pub fn verify(&self, refresh_hash: &str) -> Result<()> {
+        verify_secret(&self.secret, refresh_hash)
+    }
+}
+
+impl std::fmt::Display for RefreshToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.session_id, self.secret)
+    }
+}
+
+impl FromStr for RefreshToken {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (session_id, secret) = parse_secret_token(s)?;
+        Ok(Self { session_id, secret })
+    }
+}
+
+/// ### PersonalToken
+/// Long-lived API token `imt_{id}.{secret}` sent as `Authorization: Bearer`,
+/// only HMAC of the secret is stored.
+pub struct PersonalToken {
+    pub id: Uuid,
+    secret: String,
+}
+
+impl PersonalToken {
+    pub const PREFIX: &'static str = "imt_";
+
+    pub fn new(id: &Uuid) -> Self {
+        Self {
+            id: *id,
+            secret: new_secret(),
+        }
+    }
+
+    pub fn hash(&self) -> Result<String> {
+        hash_secret(&self.secret)
+    }
+
+    pub fn verify(&self, token_hash: &str) -> Result<()> {
+        verify_secret(&self.secret, token_hash)
+    }
+}
+
+impl std::fmt::Display for PersonalToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}.{}", Self::PREFIX, self.id, self.secret)
+    }
+}
+
+impl FromStr for PersonalToken {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let token = s
+            .strip_prefix(Self::PREFIX)
+            .ok_or(Error::TokenParseFailed)?;
+        let (id, secret) = parse_secret_token(token)?;
+        Ok(Self { id, secret })
+    }
+}
+
+/// ### ResetToken
+/// Single-use `{id}.{secret}` token of password reset mail, only HMAC of the secret is stored.
+pub struct ResetToken {
+    pub id: Uuid,
+    secret: String,
+}
+
+impl ResetToken {
+    pub fn new(id: &Uuid) -> Self {
+        Self {
+            id: *id,
+            secret: new_secret(),
+        }
+    }
+
+    pub fn hash(&self) -> Result<String> {
+        hash_secret(&self.secret)
+    }
+
+    pub fn verify(&self, token_hash: &str) -> Result<()> {
+        verify_secret(&self.secret, token_hash)
+    }
+}
+
+impl std::fmt::Display for ResetToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.id, self.secret)
+    }
+}
+
+impl FromStr for ResetToken {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (id, secret) = parse_secret_token(s)?;
+        Ok(Self { id, secret })
+    }
+}
+
+fn new_secret() -> String {
+    let mut secret = [0u8; SECRET_BYTES];
+    rand::thread_rng().fill_bytes(&mut secret);
+    b64u_encode(secret)
+}
+
+fn hash_secret(secret: &str) -> Result<String> {
+    let mut mac: Hmac<Sha512> = Hmac::new_from_slice(&config::config().TOKEN_SECRET)
+        .map_err(|_| Error::TokenInvalidSecret)?;
+    mac.update(secret.as_bytes());
+    Ok(b64u_encode(mac.finalize().into_bytes()))
+}
+
+fn verify_secret(secret: &str, hash: &str) -> Result<()> {
+    let hash = b64u_decode(hash)?;
+    let mut mac: Hmac<Sha512> = Hmac::new_from_slice(&config::config().TOKEN_SECRET)
+        .map_err(|_| Error::TokenInvalidSecret)?;
+    mac.update(secret.as_bytes());
+    mac.verify_slice(&hash).map_err(|_| Error::TokenParseFailed)
+}
+
+/// splits `{id}.{secret}`
+fn parse_secret_token(s: &str) -> Result<(Uuid, String)> {
+    let (id, secret) = s.split_once('.').ok_or(Error::TokenParseFailed)?;
+    let id = Uuid::parse_str(id).map_err(|_| Error::TokenParseFailed)?;
+    if secret.is_empty() {
+        return Err(Error::TokenParseFailed);
+    }
+
+    Ok((id, secret.to_string()))
+}
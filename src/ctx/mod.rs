@@ -1,9 +1,15 @@
 use uuid::Uuid;
 
+use crate::graphql::Scope;
+
 #[derive(Clone, Debug)]
 pub struct Ctx {
     pub user_id: Uuid,
     pub account_id: Uuid,
+    /// login session of access token, `None` for personal API token
+    pub session_id: Option<Uuid>,
+    /// scopes of personal API token, login session isn't limited
+    pub scopes: Option<Vec<Scope>>,
     pub name: String,
     pub kind: String,
 }
@@ -13,8 +19,27 @@ impl Ctx {
         Self {
             user_id,
             account_id,
+            session_id: None,
+            scopes: None,
             name: name.to_string(),
             kind: kind.to_string(),
         }
     }
+
+    pub fn with_session(mut self, session_id: Uuid) -> Self {
+        self.session_id = Some(session_id);
+        self
+    }
+
+    pub fn with_scopes(mut self, scopes: Vec<Scope>) -> Self {
+        self.scopes = Some(scopes);
+        self
+    }
+
+    pub fn allows(&self, scope: Scope) -> bool {
+        match &self.scopes {
+            Some(scopes) => scopes.iter().any(|granted| granted.covers(scope)),
+            None => true,
+        }
+    }
 }
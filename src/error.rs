@@ -3,7 +3,7 @@ use std::fmt;
 use serde::Serialize;
 use serde_with::serde_as;
 
-use crate::model;
+use crate::{model, services};
 
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -12,6 +12,7 @@ pub type Result<T> = std::result::Result<T, Error>;
 pub enum Error {
     EnvVar(String, String),
     Model(model::Error),
+    ImageStore(services::error::Error),
     Encryption(String),
     ConfigWrongFormat(String),
 }
@@ -22,6 +23,12 @@ impl From<model::Error> for Error {
     }
 }
 
+impl From<services::error::Error> for Error {
+    fn from(e: services::error::Error) -> Self {
+        Self::ImageStore(e)
+    }
+}
+
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{self:?}")
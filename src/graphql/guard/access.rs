@@ -30,6 +30,25 @@ impl TryInto<i32> for Accessship {
     }
 }
 
+impl Accessship {
+    /// whether resource of `public_lvl` is visible with this access
+    pub fn allows(&self, public_lvl: i32) -> bool {
+        matches!(
+            (self, public_lvl),
+            (Accessship::AllowedSubscriber, 1)
+                | (Accessship::AllowedSubscriber, 2)
+                | (Accessship::AllowedPublic, 2)
+                | (Accessship::Admin, _)
+                | (Accessship::Owner, _)
+        )
+    }
+
+    /// whether comments of resource can be hidden, restored and deleted with this access
+    pub fn moderates(&self) -> bool {
+        matches!(self, Accessship::Admin | Accessship::Owner)
+    }
+}
+
 pub struct CreatorGuard {
     post_id: Uuid,
     admin_allowed: bool,
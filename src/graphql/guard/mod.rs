@@ -1,11 +1,13 @@
 mod access;
 mod resource;
 mod role;
+mod scope;
 mod self_or_admin;
 mod user;
 
 pub use access::{Accessship, CreatorGuard, ImageCreatorGuard};
 pub use resource::{HasAccess, ResourceGuard};
 pub use role::{Role, RoleGuard};
+pub use scope::{ScopeGuard, SessionGuard};
 pub use self_or_admin::SelfOrAdminGuard;
 pub use user::UserQueryGuard;
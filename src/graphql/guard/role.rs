@@ -37,6 +37,10 @@ impl Guard for RoleGuard {
         };
 
         let user = match app_ctx {
+            // API tokens never act as admin
+            Some(ctx) if ctx.scopes.is_some() && self.role == Role::Admin => {
+                return Err(GraphQLError::AccessError(ctx.account_id.to_string()).into())
+            }
             Some(ctx) => ctx.account_id,
             None => match self.role {
                 Role::Guest => return Ok(()),
@@ -0,0 +1,48 @@
+use async_graphql::{Context, Guard, Result};
+
+use crate::ctx::Ctx;
+use crate::graphql::Scope;
+use crate::web::graphql::error::Error as GraphQLError;
+
+/// Personal API token must have `scope`, login session and guest pass.\
+/// Field guard replaces guard of its `#[Object]`, so fields with own guard
+/// have to repeat the scope, e.g. `RoleGuard::new(..).and(ScopeGuard::new(..))`.
+pub struct ScopeGuard {
+    scope: Scope,
+}
+
+impl ScopeGuard {
+    pub fn new(scope: Scope) -> Self {
+        Self { scope }
+    }
+}
+
+#[async_trait::async_trait]
+impl Guard for ScopeGuard {
+    async fn check(&self, ctx: &Context<'_>) -> Result<()> {
+        match ctx.data_opt::<Ctx>() {
+            Some(app_ctx) if !app_ctx.allows(self.scope) => Err(GraphQLError::AccessError(
+                format!("API token is missing scope {}", self.scope),
+            )
+            .into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Only login session, e.g. managing sessions and API tokens isn't allowed by API token.
+pub struct SessionGuard;
+
+#[async_trait::async_trait]
+impl Guard for SessionGuard {
+    async fn check(&self, ctx: &Context<'_>) -> Result<()> {
+        match ctx.data_opt::<Ctx>() {
+            Some(app_ctx) if app_ctx.session_id.is_some() => Ok(()),
+            Some(_) => Err(GraphQLError::AccessError(
+                "API token can't be used for this".to_string(),
+            )
+            .into()),
+            None => Err(GraphQLError::AccessError("No user logged in".to_string()).into()),
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use std::fmt;
+
 use async_graphql::Enum;
 use serde::{Deserialize, Serialize};
 
@@ -28,3 +30,104 @@ impl ToString for ImageKind {
         }
     }
 }
+
+/// ### EXIF policy
+/// What happens with EXIF of uploaded originals:
+/// - `Keep` - original is stored as uploaded
+/// - `StripGps` - GPS location is removed
+/// - `StripAll` - all EXIF and XMP is removed, only orientation is kept
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Enum, Deserialize, Serialize)]
+pub enum ExifPolicy {
+    #[default]
+    Keep,
+    StripGps,
+    StripAll,
+}
+
+impl From<String> for ExifPolicy {
+    fn from(s: String) -> Self {
+        match s.as_str() {
+            "strip_gps" => Self::StripGps,
+            "strip_all" => Self::StripAll,
+            _ => Self::Keep,
+        }
+    }
+}
+
+impl fmt::Display for ExifPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Keep => write!(f, "keep"),
+            Self::StripGps => write!(f, "strip_gps"),
+            Self::StripAll => write!(f, "strip_all"),
+        }
+    }
+}
+
+/// ### API token scope
+/// What personal API token may do, stored as `resource:action`, e.g. `images:write`.\
+/// Write scope includes read of the same resource.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum, Deserialize, Serialize)]
+pub enum Scope {
+    ImagesRead,
+    ImagesWrite,
+    PostsRead,
+    PostsWrite,
+    AlbumsRead,
+    AlbumsWrite,
+    CommentsRead,
+    CommentsWrite,
+    AccountRead,
+    AccountWrite,
+}
+
+impl Scope {
+    /// whether granted scope covers `required`
+    pub fn covers(&self, required: Scope) -> bool {
+        match (self, required) {
+            (Self::ImagesWrite, Self::ImagesRead)
+            | (Self::PostsWrite, Self::PostsRead)
+            | (Self::AlbumsWrite, Self::AlbumsRead)
+            | (Self::CommentsWrite, Self::CommentsRead)
+            | (Self::AccountWrite, Self::AccountRead) => true,
+            (granted, required) => *granted == required,
+        }
+    }
+}
+
+impl std::str::FromStr for Scope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "images:read" => Ok(Self::ImagesRead),
+            "images:write" => Ok(Self::ImagesWrite),
+            "posts:read" => Ok(Self::PostsRead),
+            "posts:write" => Ok(Self::PostsWrite),
+            "albums:read" => Ok(Self::AlbumsRead),
+            "albums:write" => Ok(Self::AlbumsWrite),
+            "comments:read" => Ok(Self::CommentsRead),
+            "comments:write" => Ok(Self::CommentsWrite),
+            "account:read" => Ok(Self::AccountRead),
+            "account:write" => Ok(Self::AccountWrite),
+            _ => Err(format!("unknown scope: {s}")),
+        }
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ImagesRead => write!(f, "images:read"),
+            Self::ImagesWrite => write!(f, "images:write"),
+            Self::PostsRead => write!(f, "posts:read"),
+            Self::PostsWrite => write!(f, "posts:write"),
+            Self::AlbumsRead => write!(f, "albums:read"),
+            Self::AlbumsWrite => write!(f, "albums:write"),
+            Self::CommentsRead => write!(f, "comments:read"),
+            Self::CommentsWrite => write!(f, "comments:write"),
+            Self::AccountRead => write!(f, "account:read"),
+            Self::AccountWrite => write!(f, "account:write"),
+        }
+    }
+}
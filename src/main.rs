@@ -33,10 +33,17 @@ async fn main() -> Result<()> {
     let mm = model::ModelManager::new().await?;
     mm.run_migration();
 
+    let store = services::store::new_image_store()?;
+    let mail = services::mail::new_mail_transport()?;
+
     let routes_all = Router::new()
         .merge(routes_login::routes(mm.clone()))
-        .nest("/graphql", graphql_routes::routes(mm.clone()))
-        .nest("/api", web::api::routes(mm.clone()))
+        .nest(
+            "/graphql",
+            graphql_routes::routes(mm.clone(), store.clone(), mail),
+        )
+        .nest("/api", web::api::routes(mm.clone(), store.clone()))
+        .nest("/public", web::api::public_routes(mm.clone(), store))
         .route("/hello", get(hello_world))
         .layer(middleware::from_fn_with_state(mm.clone(), mw_ctx_resolve))
         .layer(CookieManagerLayer::new());
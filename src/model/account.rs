@@ -7,7 +7,8 @@ use crate::{
     schema::{account, referral, users},
 };
 
-use super::{referral::ReferralForCreate, ModelManager, Result};
+use super::referral::{lowest_public_lvl, ReferralBmc, ReferralForCreate};
+use super::{session::SessionBmc, ModelManager, Result};
 
 #[derive(Debug, Clone, PartialEq, Identifiable, Queryable)]
 #[diesel(table_name = account)]
@@ -21,6 +22,13 @@ pub struct Account {
     pub is_banned: bool,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
+    pub exif_policy: Option<String>,
+    pub exif_retain_private: Option<bool>,
+    /// `None` when sys config default is used
+    pub quota_bytes: Option<i64>,
+    pub quota_images: Option<i32>,
+    pub used_bytes: i64,
+    pub used_images: i32,
 }
 
 #[derive(Insertable)]
@@ -42,6 +50,12 @@ pub struct AccountForUpdate {
     pub is_admin: Option<bool>,
     pub public_lvl: Option<i32>,
     pub is_banned: Option<bool>,
+    /// `Some(None)` resets to sys config default
+    pub exif_policy: Option<Option<String>>,
+    pub exif_retain_private: Option<Option<bool>>,
+    /// `Some(None)` resets to sys config default
+    pub quota_bytes: Option<Option<i64>>,
+    pub quota_images: Option<Option<i32>>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
 
@@ -62,6 +76,7 @@ impl AccountBmc {
         referrer_id: &Uuid,
         exp: Option<chrono::DateTime<chrono::Utc>>,
         new_account: AccountForCreate,
+        granted_by: &Uuid,
     ) -> Result<Account> {
         let mut connection = mm.conn()?;
 
@@ -70,7 +85,8 @@ impl AccountBmc {
                 .values(new_account)
                 .get_result::<Account>(connection)?;
 
-            let referral_to_create = ReferralForCreate::new(referrer_id, &account.id, exp);
+            let referral_to_create =
+                ReferralForCreate::new(referrer_id, &account.id, exp, granted_by);
             diesel::insert_into(referral::dsl::referral)
                 .values(referral_to_create)
                 .execute(connection)?;
@@ -84,10 +100,11 @@ impl AccountBmc {
         referrer_id: &Uuid,
         user_id: &Uuid,
         exp: Option<chrono::DateTime<chrono::Utc>>,
+        granted_by: &Uuid,
     ) -> Result<Referral> {
         let mut connection = mm.conn()?;
 
-        let referral_to_create = ReferralForCreate::new(referrer_id, user_id, exp);
+        let referral_to_create = ReferralForCreate::new(referrer_id, user_id, exp, granted_by);
         diesel::insert_into(referral::dsl::referral)
             .values(referral_to_create)
             .get_result(&mut connection)
@@ -133,6 +150,7 @@ impl AccountBmc {
             .map_err(|e| e.into())
     }
 
+    /// ban revokes all sessions of account users
     pub fn update(
         mm: &ModelManager,
         id: &Uuid,
@@ -140,10 +158,22 @@ impl AccountBmc {
     ) -> Result<Account> {
         let mut connection = mm.conn()?;
 
-        diesel::update(account::dsl::account.filter(account::dsl::id.eq(id)))
-            .set(update_account)
-            .get_result(&mut connection)
-            .map_err(|e| e.into())
+        connection.transaction(|conn| {
+            let banned = update_account.is_banned == Some(true);
+            let account = diesel::update(account::dsl::account.filter(account::dsl::id.eq(id)))
+                .set(update_account)
+                .get_result::<Account>(conn)?;
+
+            if banned {
+                let user_ids = users::dsl::users
+                    .filter(users::dsl::account_id.eq(account.id))
+                    .select(users::dsl::id)
+                    .load::<Uuid>(conn)?;
+                SessionBmc::revoke_users(conn, &user_ids)?;
+            }
+
+            Ok(account)
+        })
     }
 
     pub fn delete(mm: &ModelManager, id: &Uuid) -> Result<usize> {
@@ -176,10 +206,12 @@ impl Account {
             return Ok(Accessship::Owner);
         }
 
-        let has_refferal = account.has_refferal(mm, self.id)?;
-        match (self.public_lvl, has_refferal) {
+        let referral_lvl = account.referral_lvl(mm, self.id)?;
+        match (self.public_lvl, referral_lvl) {
             (2, _) => Ok(Accessship::AllowedPublic),
-            (lvl, true) if lvl > 0 => Ok(Accessship::AllowedSubscriber),
+            (lvl, Some(1)) if lvl > 0 => Ok(Accessship::AllowedSubscriber),
+            // public only referral to subscriber creator
+            (1, Some(2)) => Ok(Accessship::AllowedPublic),
             _ => Ok(Accessship::None),
         }
     }
@@ -206,15 +238,16 @@ impl Account {
         self.compare_access(mm, user_account)
     }
 
-    pub fn has_refferal(&self, mm: &ModelManager, account_id: Uuid) -> Result<bool> {
-        let mut connection = mm.conn()?;
-
-        let has_refferal = referral::dsl::referral
-            .filter(referral::dsl::user_id.eq(account_id))
-            .filter(referral::dsl::referrer_id.eq(self.id))
-            .first::<Referral>(&mut connection)
-            .is_ok();
-
-        Ok(has_refferal)
+    /// lowest public lvl of `referrer_id` content self may see by valid referrals,
+    /// `None` without referral
+    pub fn referral_lvl(&self, mm: &ModelManager, referrer_id: Uuid) -> Result<Option<i32>> {
+        let referrals = ReferralBmc::get_by_user_id(mm, &self.id)?;
+
+        Ok(lowest_public_lvl(
+            &referrals,
+            &self.id,
+            &referrer_id,
+            chrono::Utc::now(),
+        ))
     }
 }
@@ -1,6 +1,7 @@
 use diesel::prelude::*;
 use uuid::Uuid;
 
+use super::page::{keyset_page, Cursor, HasCursor, Page, PageRequest};
 use super::{post::Post, ModelManager, Result};
 
 use crate::schema::{album, album_post, post};
@@ -19,6 +20,15 @@ pub struct Album {
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
 
+impl HasCursor for Album {
+    fn cursor(&self) -> Cursor {
+        Cursor {
+            created_at: self.created_at,
+            id: self.id,
+        }
+    }
+}
+
 #[derive(Insertable)]
 #[diesel(table_name = album)]
 pub struct AlbumForCreate {
@@ -76,10 +86,24 @@ impl AlbumBmc {
             .map_err(|e| e.into())
     }
 
-    pub fn list(mm: &ModelManager) -> Result<Vec<Album>> {
+    pub fn list(mm: &ModelManager, page: &PageRequest) -> Result<Page<Album>> {
+        let mut connection = mm.conn()?;
+
+        let total_count = album::dsl::album
+            .count()
+            .get_result::<i64>(&mut connection)?;
+        let albums = keyset_page!(album::dsl::album.into_boxed(), album, page)
+            .load::<Album>(&mut connection)?;
+
+        Ok(Page::new(albums, page, total_count))
+    }
+
+    /// albums using image as their picture
+    pub fn list_by_picture(mm: &ModelManager, image_id: &Uuid) -> Result<Vec<Album>> {
         let mut connection = mm.conn()?;
 
         album::dsl::album
+            .filter(album::dsl::picture.eq(image_id))
             .load::<Album>(&mut connection)
             .map_err(|e| e.into())
     }
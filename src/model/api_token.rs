@@ -0,0 +1,128 @@
+use diesel::prelude::*;
+use uuid::Uuid;
+
+use super::{Error, ModelManager, Result};
+
+use crate::crypt::token::PersonalToken;
+use crate::schema::api_token;
+
+/// ### ApiToken
+/// Personal API token of user, limited to its `scopes` (e.g. `images:write`).\
+/// Only HMAC of the token secret is stored, token itself is shown just once.
+#[derive(Debug, Clone, PartialEq, Identifiable, Queryable)]
+#[diesel(table_name = api_token)]
+pub struct ApiToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub token_hash: String,
+    pub scopes: Vec<Option<String>>,
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub last_used_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl ApiToken {
+    pub fn scopes(&self) -> impl Iterator<Item = &str> {
+        self.scopes.iter().flatten().map(|scope| scope.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = api_token)]
+pub struct ApiTokenForCreate {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub token_hash: String,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+pub struct ApiTokenBmc;
+
+impl ApiTokenBmc {
+    /// creates token of user, returns it with the only copy of its secret
+    pub fn create(
+        mm: &ModelManager,
+        user_id: &Uuid,
+        name: &str,
+        scopes: Vec<String>,
+        expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<(ApiToken, PersonalToken)> {
+        let mut connection = mm.conn()?;
+
+        let id = Uuid::new_v4();
+        let token = PersonalToken::new(&id);
+        let token_hash = token.hash().map_err(|_| Error::FailedToHashToken)?;
+
+        let api_token = diesel::insert_into(api_token::dsl::api_token)
+            .values(ApiTokenForCreate {
+                id,
+                user_id: *user_id,
+                name: name.to_string(),
+                token_hash,
+                scopes,
+                expires_at,
+            })
+            .get_result::<ApiToken>(&mut connection)?;
+
+        Ok((api_token, token))
+    }
+
+    pub fn list_by_user(mm: &ModelManager, user_id: &Uuid) -> Result<Vec<ApiToken>> {
+        let mut connection = mm.conn()?;
+
+        api_token::dsl::api_token
+            .filter(api_token::dsl::user_id.eq(user_id))
+            .order(api_token::dsl::created_at.desc())
+            .load::<ApiToken>(&mut connection)
+            .map_err(|e| -> crate::model::Error { e.into() })
+    }
+
+    /// revokes token of user
+    pub fn delete(mm: &ModelManager, user_id: &Uuid, api_token_id: &Uuid) -> Result<usize> {
+        let mut connection = mm.conn()?;
+
+        diesel::delete(api_token::dsl::api_token)
+            .filter(api_token::dsl::id.eq(api_token_id))
+            .filter(api_token::dsl::user_id.eq(user_id))
+            .execute(&mut connection)
+            .map_err(|e| -> crate::model::Error { e.into() })
+    }
+
+    /// revokes all API tokens of users, within transaction of caller, e.g. password reset
+    pub(super) fn revoke_users(connection: &mut PgConnection, user_ids: &[Uuid]) -> Result<usize> {
+        diesel::delete(api_token::dsl::api_token)
+            .filter(api_token::dsl::user_id.eq_any(user_ids))
+            .execute(connection)
+            .map_err(|e| -> crate::model::Error { e.into() })
+    }
+
+    /// stored token of presented one, fails when secret doesn't match or token has expired
+    pub fn authenticate(mm: &ModelManager, token: &PersonalToken) -> Result<ApiToken> {
+        let mut connection = mm.conn()?;
+        let now = chrono::Utc::now();
+
+        let api_token = api_token::dsl::api_token
+            .filter(api_token::dsl::id.eq(token.id))
+            .first::<ApiToken>(&mut connection)
+            .optional()?
+            .ok_or(Error::AccessDenied)?;
+
+        token
+            .verify(&api_token.token_hash)
+            .map_err(|_| Error::AccessDenied)?;
+        if api_token
+            .expires_at
+            .is_some_and(|expires_at| expires_at <= now)
+        {
+            return Err(Error::AccessDenied);
+        }
+
+        diesel::update(api_token::dsl::api_token.find(api_token.id))
+            .set(api_token::dsl::last_used_at.eq(Some(now)))
+            .get_result::<ApiToken>(&mut connection)
+            .map_err(|e| -> crate::model::Error { e.into() })
+    }
+}
@@ -0,0 +1,114 @@
+use diesel::prelude::*;
+use diesel::PgConnection;
+use uuid::Uuid;
+
+use crate::schema::blob;
+
+/// ### Blob
+/// Original stored in image store, shared by all images with the same content hash.\
+/// It's removed from the store only when `ref_count` drops to 0.
+#[derive(Debug, Clone, PartialEq, Identifiable, Queryable)]
+#[diesel(table_name = blob, primary_key(path))]
+pub struct Blob {
+    pub path: Uuid,
+    pub hash: Option<String>,
+    pub user_id: Option<Uuid>,
+    pub size: Option<i64>,
+    pub ref_count: i32,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = blob)]
+pub struct BlobForCreate {
+    pub path: Uuid,
+    pub hash: Option<String>,
+    pub user_id: Option<Uuid>,
+    pub size: Option<i64>,
+}
+
+pub struct BlobBmc;
+
+impl BlobBmc {
+    pub fn get(mm: &super::ModelManager, search_path: &Uuid) -> super::Result<Option<Blob>> {
+        let mut connection = mm.conn()?;
+
+        blob::dsl::blob
+            .filter(blob::dsl::path.eq(search_path))
+            .first::<Blob>(&mut connection)
+            .optional()
+            .map_err(|e| -> crate::model::Error { e.into() })
+    }
+
+    /// takes reference to already stored blob with the same hash,
+    /// `user_id` limits lookup to blobs uploaded by that user
+    pub(super) fn acquire(
+        connection: &mut PgConnection,
+        search_hash: &str,
+        user_id: Option<&Uuid>,
+    ) -> QueryResult<Option<Uuid>> {
+        // row lock keeps blob from being released while reference is taken
+        let query = blob::dsl::blob
+            .filter(blob::dsl::hash.eq(search_hash))
+            .filter(blob::dsl::ref_count.gt(0))
+            .select(blob::dsl::path)
+            .limit(1)
+            .for_update();
+        let path = match user_id {
+            Some(user_id) => query
+                .filter(blob::dsl::user_id.eq(user_id))
+                .first::<Uuid>(connection)
+                .optional()?,
+            None => query.first::<Uuid>(connection).optional()?,
+        };
+
+        if let Some(path) = path {
+            diesel::update(blob::dsl::blob.filter(blob::dsl::path.eq(path)))
+                .set(blob::dsl::ref_count.eq(blob::dsl::ref_count + 1))
+                .execute(connection)?;
+        }
+
+        Ok(path)
+    }
+
+    pub(super) fn insert(
+        connection: &mut PgConnection,
+        new_blob: BlobForCreate,
+    ) -> QueryResult<()> {
+        diesel::insert_into(blob::dsl::blob)
+            .values(new_blob)
+            .execute(connection)
+            .map(|_| ())
+    }
+
+    /// stored size, images without blob record or size count as empty
+    pub(super) fn size(connection: &mut PgConnection, search_path: &Uuid) -> QueryResult<i64> {
+        blob::dsl::blob
+            .filter(blob::dsl::path.eq(search_path))
+            .select(blob::dsl::size)
+            .first::<Option<i64>>(connection)
+            .optional()
+            .map(|size| size.flatten().unwrap_or(0))
+    }
+
+    /// drops single reference, returns `true` when blob isn't referenced
+    /// anymore and should be removed from image store
+    pub(super) fn release(connection: &mut PgConnection, search_path: &Uuid) -> QueryResult<bool> {
+        let ref_count = diesel::update(blob::dsl::blob.filter(blob::dsl::path.eq(search_path)))
+            .set(blob::dsl::ref_count.eq(blob::dsl::ref_count - 1))
+            .returning(blob::dsl::ref_count)
+            .get_result::<i32>(connection)
+            .optional()?;
+
+        match ref_count {
+            Some(ref_count) if ref_count > 0 => Ok(false),
+            Some(_) => {
+                diesel::delete(blob::dsl::blob.filter(blob::dsl::path.eq(search_path)))
+                    .execute(connection)?;
+                Ok(true)
+            }
+            // image without blob record is its only reference
+            None => Ok(true),
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use diesel::prelude::*;
 use uuid::Uuid;
 
+use super::page::{keyset_page, Cursor, HasCursor, Page, PageRequest};
 use super::{ModelManager, Result};
 
 use crate::schema::comment;
@@ -8,28 +9,39 @@ use crate::schema::comment;
 #[derive(Debug, Clone, PartialEq, Identifiable, Queryable)]
 #[diesel(table_name = comment)]
 pub struct Comment {
-    id: Uuid,
-    user_id: Uuid,
-    post_id: Uuid,
-    body: String,
-    created_at: chrono::DateTime<chrono::Utc>,
-    updated_at: chrono::DateTime<chrono::Utc>,
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub post_id: Uuid,
+    pub body: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+    /// hidden by moderator, visible only to moderators
+    pub hidden_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl HasCursor for Comment {
+    fn cursor(&self) -> Cursor {
+        Cursor {
+            created_at: self.created_at,
+            id: self.id,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Insertable)]
 #[diesel(table_name = comment)]
 pub struct CommentForCreate {
     pub id: Uuid,
-    user_id: Uuid,
-    post_id: Uuid,
-    body: String,
+    pub user_id: Uuid,
+    pub post_id: Uuid,
+    pub body: String,
 }
 
 #[derive(Default, Debug, Clone, AsChangeset)]
 #[diesel(table_name = comment)]
 pub struct CommentForUpdate {
-    body: Option<String>,
-    updated_at: chrono::DateTime<chrono::Utc>,
+    pub body: Option<String>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
 }
 
 pub struct CommentBmc;
@@ -53,13 +65,30 @@ impl CommentBmc {
             .map_err(|e| -> crate::model::Error { e.into() })
     }
 
-    pub fn list(mm: &ModelManager, post_id: &Uuid) -> Result<Vec<Comment>> {
+    /// comments of post, hidden ones are listed only `with_hidden`
+    pub fn list(
+        mm: &ModelManager,
+        post_id: &Uuid,
+        with_hidden: bool,
+        page: &PageRequest,
+    ) -> Result<Page<Comment>> {
         let mut connection = mm.conn()?;
 
-        comment::dsl::comment
-            .filter(comment::dsl::post_id.eq(post_id))
-            .load::<Comment>(&mut connection)
-            .map_err(|e| -> crate::model::Error { e.into() })
+        let post_comments = || {
+            let query = comment::dsl::comment
+                .filter(comment::dsl::post_id.eq(post_id))
+                .into_boxed();
+            match with_hidden {
+                true => query,
+                false => query.filter(comment::dsl::hidden_at.is_null()),
+            }
+        };
+
+        let total_count = post_comments().count().get_result::<i64>(&mut connection)?;
+        let comments =
+            keyset_page!(post_comments(), comment, page).load::<Comment>(&mut connection)?;
+
+        Ok(Page::new(comments, page, total_count))
     }
 
     pub fn update(
@@ -75,6 +104,17 @@ impl CommentBmc {
             .map_err(|e| -> crate::model::Error { e.into() })
     }
 
+    /// hides comment from everybody but moderators, or restores it
+    pub fn set_hidden(mm: &ModelManager, search_id: &Uuid, hidden: bool) -> Result<Comment> {
+        let mut connection = mm.conn()?;
+
+        let hidden_at = hidden.then(chrono::Utc::now);
+        diesel::update(comment::dsl::comment.filter(comment::dsl::id.eq(search_id)))
+            .set(comment::dsl::hidden_at.eq(hidden_at))
+            .get_result::<Comment>(&mut connection)
+            .map_err(|e| -> crate::model::Error { e.into() })
+    }
+
     pub fn delete(mm: &ModelManager, search_id: &Uuid) -> Result<usize> {
         let mut connection = mm.conn()?;
 
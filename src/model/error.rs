@@ -4,6 +4,10 @@ use serde_with::serde_as;
 use tracing::debug;
 use uuid::Uuid;
 
+use super::invite::InviteRejection;
+use super::password::PasswordRejection;
+use super::registration::RegistrationRejection;
+use super::session::SessionRejection;
 use super::store;
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -26,7 +30,53 @@ pub enum Error {
 
     AccessDenied,
 
-    EntityNotFound { entity: &'static str, id: Uuid },
+    EntityNotFound {
+        entity: &'static str,
+        id: Uuid,
+    },
+
+    // storage quota of the account
+    StorageQuotaExceeded {
+        used: i64,
+        requested: i64,
+        quota: i64,
+    },
+    ImageQuotaExceeded {
+        used: i32,
+        requested: i32,
+        quota: i32,
+    },
+
+    RegistrationRejected(RegistrationRejection),
+    InviteRejected(InviteRejection),
+    SessionRejected(SessionRejection),
+    PasswordRejected(PasswordRejection),
+    FailedToEncryptPassword,
+    FailedToHashToken,
+}
+
+impl From<RegistrationRejection> for Error {
+    fn from(e: RegistrationRejection) -> Self {
+        Error::RegistrationRejected(e)
+    }
+}
+
+impl From<InviteRejection> for Error {
+    fn from(e: InviteRejection) -> Self {
+        Error::InviteRejected(e)
+    }
+}
+
+impl From<SessionRejection> for Error {
+    fn from(e: SessionRejection) -> Self {
+        Error::SessionRejected(e)
+    }
+}
+
+impl From<PasswordRejection> for Error {
+    fn from(e: PasswordRejection) -> Self {
+        Error::PasswordRejected(e)
+    }
 }
 
 impl From<store::Error> for Error {
@@ -8,57 +8,75 @@ use crate::schema::fav;
 #[derive(Debug, Clone, PartialEq, Identifiable, Queryable)]
 #[diesel(table_name = fav)]
 pub struct Fav {
-    id: Uuid,
-    user_id: Uuid,
-    post_id: Uuid,
-    created_at: chrono::DateTime<chrono::Utc>,
-    updated_at: chrono::DateTime<chrono::Utc>,
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub post_id: Uuid,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
 }
 
 #[derive(Debug, Clone, Insertable)]
 #[diesel(table_name = fav)]
 pub struct FavForCreate {
     pub id: Uuid,
-    user_id: Uuid,
-    post_id: Uuid,
+    pub user_id: Uuid,
+    pub post_id: Uuid,
+}
+
+impl FavForCreate {
+    pub fn new(user_id: &Uuid, post_id: &Uuid) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            user_id: *user_id,
+            post_id: *post_id,
+        }
+    }
 }
 
 pub struct FavBmc;
 
 impl FavBmc {
-    pub fn create(mm: &ModelManager, fav: FavForCreate) -> Result<Fav> {
+    /// favorites post once, favoriting it again is no-op
+    pub fn favorite(mm: &ModelManager, user_id: &Uuid, post_id: &Uuid) -> Result<usize> {
         let mut connection = mm.conn()?;
 
         diesel::insert_into(fav::dsl::fav)
-            .values(fav)
-            .get_result::<Fav>(&mut connection)
+            .values(FavForCreate::new(user_id, post_id))
+            .on_conflict((fav::dsl::user_id, fav::dsl::post_id))
+            .do_nothing()
+            .execute(&mut connection)
             .map_err(|e| -> crate::model::Error { e.into() })
     }
 
-    pub fn get(mm: &ModelManager, search_id: &Uuid) -> Result<Fav> {
+    pub fn unfavorite(mm: &ModelManager, user_id: &Uuid, post_id: &Uuid) -> Result<usize> {
         let mut connection = mm.conn()?;
 
-        fav::dsl::fav
-            .filter(fav::dsl::id.eq(search_id))
-            .first::<Fav>(&mut connection)
+        diesel::delete(fav::dsl::fav)
+            .filter(fav::dsl::user_id.eq(user_id))
+            .filter(fav::dsl::post_id.eq(post_id))
+            .execute(&mut connection)
             .map_err(|e| -> crate::model::Error { e.into() })
     }
 
-    pub fn list(mm: &ModelManager, user_id: &Uuid) -> Result<Vec<Fav>> {
+    pub fn count_post(mm: &ModelManager, post_id: &Uuid) -> Result<i64> {
         let mut connection = mm.conn()?;
 
         fav::dsl::fav
-            .filter(fav::dsl::user_id.eq(user_id))
-            .load::<Fav>(&mut connection)
+            .filter(fav::dsl::post_id.eq(post_id))
+            .count()
+            .get_result::<i64>(&mut connection)
             .map_err(|e| -> crate::model::Error { e.into() })
     }
 
-    pub fn delete(mm: &ModelManager, id: &Uuid) -> Result<usize> {
+    pub fn is_favorited(mm: &ModelManager, user_id: &Uuid, post_id: &Uuid) -> Result<bool> {
         let mut connection = mm.conn()?;
 
-        diesel::delete(fav::dsl::fav)
-            .filter(fav::dsl::id.eq(id))
-            .execute(&mut connection)
-            .map_err(|e| -> crate::model::Error { e.into() })
+        diesel::select(diesel::dsl::exists(
+            fav::dsl::fav
+                .filter(fav::dsl::user_id.eq(user_id))
+                .filter(fav::dsl::post_id.eq(post_id)),
+        ))
+        .get_result::<bool>(&mut connection)
+        .map_err(|e| -> crate::model::Error { e.into() })
     }
 }
@@ -5,10 +5,18 @@ use diesel::{
 use serde::Serialize;
 use uuid::Uuid;
 
-use crate::graphql::guard::HasAccess;
-use crate::schema::{image, post_image};
+use crate::config::DedupScope;
+use crate::graphql::guard::{Accessship, HasAccess};
+use crate::schema::{image, image_metadata, post_image};
 
 use super::account::AccountBmc;
+use super::album::AlbumBmc;
+use super::blob::{BlobBmc, BlobForCreate};
+use super::image_metadata::{ImageMetadataFilter, ImageMetadataForCreate};
+use super::page::{keyset_page, Cursor, HasCursor, Page, PageRequest};
+use super::post::PostBmc;
+use super::quota::QuotaBmc;
+use super::user::UserBmc;
 use super::Result;
 
 #[derive(Debug, Clone, PartialEq, Identifiable, Queryable, Serialize)]
@@ -21,6 +29,10 @@ pub struct Image {
     pub path: Uuid,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
+    pub hash: Option<String>,
+    /// placeholders shown instead of image in content viewer may not see
+    pub blurhash: Option<String>,
+    pub preview: Option<String>,
 }
 
 #[derive(Debug, Clone, Insertable)]
@@ -31,6 +43,17 @@ pub struct ImageForCreate {
     pub name: Option<String>,
     pub kind: String,
     pub path: Uuid,
+    pub hash: Option<String>,
+    pub blurhash: Option<String>,
+    pub preview: Option<String>,
+}
+
+/// Uploaded image together with its stored blob and extracted metadata
+#[derive(Debug, Clone)]
+pub struct ImageForUpload {
+    pub image: ImageForCreate,
+    pub blob: BlobForCreate,
+    pub metadata: ImageMetadataForCreate,
 }
 
 #[derive(Default, Debug, Clone, AsChangeset)]
@@ -42,13 +65,60 @@ pub struct ImageForUpdate {
 
 pub struct ImageBmc;
 impl ImageBmc {
-    pub fn create(mm: &crate::model::ModelManager, image: ImageForCreate) -> Result<Image> {
+    pub fn create(
+        mm: &crate::model::ModelManager,
+        upload: ImageForUpload,
+        scope: DedupScope,
+    ) -> Result<Image> {
+        Self::create_many(mm, vec![upload], scope)?
+            .pop()
+            .ok_or(crate::model::Error::DbEntityNotFound)
+    }
+
+    /// creates all images in single transaction, none is created if any insert fails
+    /// or storage quota of the uploader would be exceeded.\
+    /// When blob with the same hash is already stored (within `scope`) image references it instead,
+    /// so its `path` differs from uploaded one and uploaded copy should be removed from store.
+    pub fn create_many(
+        mm: &crate::model::ModelManager,
+        images: Vec<ImageForUpload>,
+        scope: DedupScope,
+    ) -> Result<Vec<Image>> {
         let mut connection = mm.conn()?;
 
-        diesel::insert_into(image::dsl::image)
-            .values(image)
-            .get_result::<Image>(&mut connection)
-            .map_err(|e| -> crate::model::Error { e.into() })
+        connection.transaction(|connection| {
+            let mut created = Vec::with_capacity(images.len());
+            for ImageForUpload {
+                image: mut new_image,
+                blob,
+                metadata,
+            } in images
+            {
+                QuotaBmc::charge(connection, &new_image.user_id, blob.size.unwrap_or(0), 1)?;
+
+                let user_id = match scope {
+                    DedupScope::User => Some(&new_image.user_id),
+                    DedupScope::Global => None,
+                };
+                let existing = match &blob.hash {
+                    Some(hash) => BlobBmc::acquire(connection, hash, user_id)?,
+                    None => None,
+                };
+                match existing {
+                    Some(path) => new_image.path = path,
+                    None => BlobBmc::insert(connection, blob)?,
+                }
+
+                let image = diesel::insert_into(image::dsl::image)
+                    .values(new_image)
+                    .get_result::<Image>(connection)?;
+                diesel::insert_into(image_metadata::dsl::image_metadata)
+                    .values(metadata)
+                    .execute(connection)?;
+                created.push(image);
+            }
+            Ok(created)
+        })
     }
 
     pub fn get(mm: &crate::model::ModelManager, search_id: &Uuid) -> Result<Image> {
@@ -68,15 +138,86 @@ impl ImageBmc {
             .map_err(|e| -> crate::model::Error { e.into() })
     }
 
-    pub fn list_user(mm: &crate::model::ModelManager, user_id: &Uuid) -> Result<Vec<Image>> {
+    pub fn list_user(
+        mm: &crate::model::ModelManager,
+        user_id: &Uuid,
+        page: &PageRequest,
+    ) -> Result<Page<Image>> {
+        let mut connection = mm.conn()?;
+
+        let user_images = || {
+            image::dsl::image
+                .filter(image::dsl::user_id.eq(user_id))
+                .into_boxed()
+        };
+
+        let total_count = user_images().count().get_result::<i64>(&mut connection)?;
+        let images = keyset_page!(user_images(), image, page).load::<Image>(&mut connection)?;
+
+        Ok(Page::new(images, page, total_count))
+    }
+
+    /// images stored under `path`, more than one when blob is shared
+    pub fn list_by_path(mm: &crate::model::ModelManager, path: &Uuid) -> Result<Vec<Image>> {
         let mut connection = mm.conn()?;
 
         image::dsl::image
-            .filter(image::dsl::user_id.eq(user_id))
+            .filter(image::dsl::path.eq(path))
             .load::<Image>(&mut connection)
             .map_err(|e| -> crate::model::Error { e.into() })
     }
 
+    /// user images matching metadata filter, images without metadata never match
+    pub fn search_user(
+        mm: &crate::model::ModelManager,
+        user_id: &Uuid,
+        filter: ImageMetadataFilter,
+        page: &PageRequest,
+    ) -> Result<Page<Image>> {
+        let mut connection = mm.conn()?;
+
+        let matching = || {
+            let filter = filter.clone();
+            let mut query = image::dsl::image
+                .inner_join(image_metadata::dsl::image_metadata)
+                .filter(image::dsl::user_id.eq(user_id))
+                .select(image::all_columns)
+                .into_boxed();
+
+            if let Some(mime_type) = filter.mime_type {
+                query = query.filter(image_metadata::dsl::mime_type.eq(mime_type));
+            }
+            if let Some(camera_model) = filter.camera_model {
+                query = query
+                    .filter(image_metadata::dsl::camera_model.ilike(format!("%{}%", camera_model)));
+            }
+            if let Some(captured_after) = filter.captured_after {
+                query = query.filter(image_metadata::dsl::captured_at.ge(captured_after));
+            }
+            if let Some(captured_before) = filter.captured_before {
+                query = query.filter(image_metadata::dsl::captured_at.lt(captured_before));
+            }
+            if let Some(min_width) = filter.min_width {
+                query = query.filter(image_metadata::dsl::width.ge(min_width));
+            }
+            if let Some(min_height) = filter.min_height {
+                query = query.filter(image_metadata::dsl::height.ge(min_height));
+            }
+            match filter.has_gps {
+                Some(true) => query = query.filter(image_metadata::dsl::gps_latitude.is_not_null()),
+                Some(false) => query = query.filter(image_metadata::dsl::gps_latitude.is_null()),
+                None => (),
+            }
+
+            query
+        };
+
+        let total_count = matching().count().get_result::<i64>(&mut connection)?;
+        let images = keyset_page!(matching(), image, page).load::<Image>(&mut connection)?;
+
+        Ok(Page::new(images, page, total_count))
+    }
+
     pub fn list_post(mm: &crate::model::ModelManager, post_id: &Uuid) -> Result<Vec<Image>> {
         let mut connection = mm.conn()?;
 
@@ -88,6 +229,20 @@ impl ImageBmc {
             .map_err(|e| -> crate::model::Error { e.into() })
     }
 
+    /// image shown first in post, placeholders of locked post are taken from it
+    pub fn first_in_post(mm: &crate::model::ModelManager, post_id: &Uuid) -> Result<Option<Image>> {
+        let mut connection = mm.conn()?;
+
+        image::dsl::image
+            .inner_join(post_image::dsl::post_image)
+            .filter(post_image::dsl::post_id.eq(post_id))
+            .select(image::all_columns)
+            .order((image::dsl::created_at.asc(), image::dsl::id.asc()))
+            .first::<Image>(&mut connection)
+            .optional()
+            .map_err(|e| -> crate::model::Error { e.into() })
+    }
+
     pub fn update(
         mm: &crate::model::ModelManager,
         id: Uuid,
@@ -101,12 +256,124 @@ impl ImageBmc {
             .map_err(|e| -> crate::model::Error { e.into() })
     }
 
-    pub fn delete(mm: &crate::model::ModelManager, id: Uuid) -> Result<usize> {
+    /// deletes image, drops its blob reference and frees its size from storage usage,
+    /// returns blob path when no other image references it and it should be removed from store
+    pub fn delete(mm: &crate::model::ModelManager, id: Uuid) -> Result<Option<Uuid>> {
         let mut connection = mm.conn()?;
 
-        diesel::delete(image::dsl::image.filter(image::dsl::id.eq(id)))
-            .execute(&mut connection)
-            .map_err(|e| -> crate::model::Error { e.into() })
+        connection.transaction(|connection| {
+            let deleted = diesel::delete(image::dsl::image.filter(image::dsl::id.eq(id)))
+                .returning((image::dsl::path, image::dsl::user_id))
+                .get_result::<(Uuid, Uuid)>(connection)
+                .optional()?;
+            let (path, user_id) = match deleted {
+                Some(deleted) => deleted,
+                None => return Ok(None),
+            };
+
+            let size = BlobBmc::size(connection, &path)?;
+            QuotaBmc::refund(connection, &user_id, size, 1)?;
+
+            match BlobBmc::release(connection, &path)? {
+                true => Ok(Some(path)),
+                false => Ok(None),
+            }
+        })
+    }
+}
+
+impl Image {
+    /// Image is visible when logged user can see any post, album or profile showing it,
+    /// with the same rules as posts. Image not shown anywhere is visible only to owner and admin.
+    pub fn is_visible(
+        &self,
+        mm: &crate::model::ModelManager,
+        user_id: &Uuid,
+        account_id: &Uuid,
+    ) -> Result<bool> {
+        if &self.user_id == user_id {
+            return Ok(true);
+        }
+
+        let account = AccountBmc::get(mm, account_id)?;
+        if account.is_admin {
+            return Ok(true);
+        }
+        if account.is_banned {
+            return Ok(false);
+        }
+
+        let owner = AccountBmc::get(mm, &UserBmc::get(mm, &self.user_id)?.account_id)?;
+        if owner.is_banned {
+            return Ok(false);
+        }
+
+        for post in PostBmc::list_by_image(mm, &self.id)? {
+            if post.user_access(mm, *account_id)?.allows(post.public_lvl) {
+                return Ok(true);
+            }
+        }
+
+        for album in AlbumBmc::list_by_picture(mm, &self.id)? {
+            let album_owner = AccountBmc::get(mm, &UserBmc::get(mm, &album.user_id)?.account_id)?;
+            if album_owner
+                .has_access(mm, Some(*account_id))?
+                .allows(album.public_lvl)
+            {
+                return Ok(true);
+            }
+        }
+
+        // profile picture is visible with profile, only private profiles are hidden
+        for user in UserBmc::list_by_picture(mm, &self.id)? {
+            let profile = AccountBmc::get(mm, &user.account_id)?;
+            if profile.has_access(mm, Some(*account_id))? != Accessship::None {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Image is public when it's visible without logged user,
+    /// so its responses can be cached by shared caches.
+    pub fn is_public(&self, mm: &crate::model::ModelManager) -> Result<bool> {
+        let owner = AccountBmc::get(mm, &UserBmc::get(mm, &self.user_id)?.account_id)?;
+        if owner.is_banned {
+            return Ok(false);
+        }
+
+        for post in PostBmc::list_by_image(mm, &self.id)? {
+            let post_owner = AccountBmc::get(mm, &UserBmc::get(mm, &post.user_id)?.account_id)?;
+            if post_owner.has_access(mm, None)?.allows(post.public_lvl) {
+                return Ok(true);
+            }
+        }
+
+        for album in AlbumBmc::list_by_picture(mm, &self.id)? {
+            let album_owner = AccountBmc::get(mm, &UserBmc::get(mm, &album.user_id)?.account_id)?;
+            if album_owner.has_access(mm, None)?.allows(album.public_lvl) {
+                return Ok(true);
+            }
+        }
+
+        for user in UserBmc::list_by_picture(mm, &self.id)? {
+            let profile = AccountBmc::get(mm, &user.account_id)?;
+            if profile.has_access(mm, None)? != Accessship::None {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+}
+
+impl HasCursor for Image {
+    fn cursor(&self) -> Cursor {
+        Cursor {
+            created_at: self.created_at,
+            id: self.id,
+        }
     }
 }
 
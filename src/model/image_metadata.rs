@@ -0,0 +1,116 @@
+use diesel::prelude::*;
+use uuid::Uuid;
+
+use super::{ModelManager, Result};
+
+use crate::graphql::ExifPolicy;
+use crate::schema::image_metadata;
+use crate::services::metadata::ImageMetadata as ExtractedMetadata;
+
+use super::sys_config::ExifSettings;
+
+/// ### ImageMetadata
+/// Dimensions, format and EXIF details read from image original on upload.
+#[derive(Debug, Clone, PartialEq, Identifiable, Queryable)]
+#[diesel(table_name = image_metadata, primary_key(image_id))]
+pub struct ImageMetadata {
+    pub image_id: Uuid,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub mime_type: Option<String>,
+    pub byte_size: i64,
+    pub captured_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub camera_model: Option<String>,
+    pub orientation: Option<i32>,
+    pub gps_latitude: Option<f64>,
+    pub gps_longitude: Option<f64>,
+    pub gps_altitude: Option<f64>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    /// policy applied to stored original, fields it stripped are private to the owner
+    pub exif_policy: String,
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = image_metadata)]
+pub struct ImageMetadataForCreate {
+    pub image_id: Uuid,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub mime_type: Option<String>,
+    pub byte_size: i64,
+    pub captured_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub camera_model: Option<String>,
+    pub orientation: Option<i32>,
+    pub gps_latitude: Option<f64>,
+    pub gps_longitude: Option<f64>,
+    pub gps_altitude: Option<f64>,
+    pub exif_policy: String,
+}
+
+impl ImageMetadataForCreate {
+    /// fields stripped from original are dropped unless they are retained privately
+    pub fn new(image_id: Uuid, metadata: &ExtractedMetadata, exif: ExifSettings) -> Self {
+        let mut metadata = metadata.clone();
+        if !exif.retain_private {
+            metadata.strip(exif.policy);
+        }
+
+        Self {
+            image_id,
+            width: metadata.width.map(|width| width as i32),
+            height: metadata.height.map(|height| height as i32),
+            mime_type: metadata.mime_type,
+            byte_size: metadata.byte_size as i64,
+            captured_at: metadata.captured_at,
+            camera_model: metadata.camera_model,
+            orientation: metadata.orientation.map(|orientation| orientation as i32),
+            gps_latitude: metadata.gps.map(|gps| gps.latitude),
+            gps_longitude: metadata.gps.map(|gps| gps.longitude),
+            gps_altitude: metadata.gps.and_then(|gps| gps.altitude),
+            exif_policy: exif.policy.to_string(),
+        }
+    }
+}
+
+impl ImageMetadata {
+    /// hides fields stripped from original, for anyone but the owner
+    pub fn hide_private(&mut self) {
+        let policy: ExifPolicy = self.exif_policy.clone().into();
+        if policy != ExifPolicy::Keep {
+            self.gps_latitude = None;
+            self.gps_longitude = None;
+            self.gps_altitude = None;
+        }
+        if policy == ExifPolicy::StripAll {
+            self.captured_at = None;
+            self.camera_model = None;
+        }
+    }
+}
+
+/// Image search criteria, all set fields have to match
+#[derive(Debug, Clone, Default)]
+pub struct ImageMetadataFilter {
+    pub mime_type: Option<String>,
+    /// case insensitive substring of camera model
+    pub camera_model: Option<String>,
+    pub captured_after: Option<chrono::DateTime<chrono::Utc>>,
+    pub captured_before: Option<chrono::DateTime<chrono::Utc>>,
+    pub min_width: Option<i32>,
+    pub min_height: Option<i32>,
+    pub has_gps: Option<bool>,
+}
+
+pub struct ImageMetadataBmc;
+
+impl ImageMetadataBmc {
+    pub fn get(mm: &ModelManager, search_image_id: &Uuid) -> Result<Option<ImageMetadata>> {
+        let mut connection = mm.conn()?;
+
+        image_metadata::dsl::image_metadata
+            .filter(image_metadata::dsl::image_id.eq(search_image_id))
+            .first::<ImageMetadata>(&mut connection)
+            .optional()
+            .map_err(|e| -> crate::model::Error { e.into() })
+    }
+}
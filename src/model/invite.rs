@@ -0,0 +1,191 @@
+use diesel::prelude::*;
+use rand::RngCore;
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::referral::{Referral, ReferralBmc, ReferralForCreate};
+use super::{ModelManager, Result};
+
+use crate::crypt::b64u_encode;
+use crate::schema::{invite, referral};
+
+/// random bytes of invite code, 12 characters when encoded
+const CODE_BYTES: usize = 9;
+
+#[derive(Debug, Clone, PartialEq, Identifiable, Queryable)]
+#[diesel(table_name = invite)]
+pub struct Invite {
+    pub id: Uuid,
+    pub code: String,
+    pub referrer_id: Uuid,
+    /// lowest public lvl of referrer content redeemed referral opens
+    pub public_lvl: i32,
+    pub max_uses: i32,
+    pub uses: i32,
+    /// invite can't be redeemed after, referrals created by it expire at the same time
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = invite)]
+pub struct InviteForCreate {
+    pub id: Uuid,
+    pub code: String,
+    pub referrer_id: Uuid,
+    pub public_lvl: i32,
+    pub max_uses: i32,
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl InviteForCreate {
+    /// invite with new random code
+    pub fn new(
+        referrer_id: &Uuid,
+        public_lvl: i32,
+        max_uses: i32,
+        expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Self {
+        let mut code = [0u8; CODE_BYTES];
+        rand::thread_rng().fill_bytes(&mut code);
+
+        Self {
+            id: Uuid::new_v4(),
+            code: b64u_encode(code),
+            referrer_id: *referrer_id,
+            public_lvl,
+            max_uses,
+            expires_at,
+        }
+    }
+}
+
+/// Reason why invite code was not redeemed.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum InviteRejection {
+    InviteInvalid,
+    InviteExpired,
+    InviteUsedUp,
+    OwnInvite,
+    /// user already has referral opening the same content
+    AlreadyReferred,
+}
+
+impl std::fmt::Display for InviteRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InviteInvalid => write!(f, "Invite code is not valid"),
+            Self::InviteExpired => write!(f, "Invite code has expired"),
+            Self::InviteUsedUp => write!(f, "Invite code was already used"),
+            Self::OwnInvite => write!(f, "Own invite code can't be redeemed"),
+            Self::AlreadyReferred => write!(f, "Invite was already redeemed"),
+        }
+    }
+}
+
+pub struct InviteBmc;
+
+impl InviteBmc {
+    pub fn create(mm: &ModelManager, invite: InviteForCreate) -> Result<Invite> {
+        let mut connection = mm.conn()?;
+
+        diesel::insert_into(invite::dsl::invite)
+            .values(invite)
+            .get_result::<Invite>(&mut connection)
+            .map_err(|e| -> crate::model::Error { e.into() })
+    }
+
+    pub fn list_by_referrer(mm: &ModelManager, referrer_id: &Uuid) -> Result<Vec<Invite>> {
+        let mut connection = mm.conn()?;
+
+        invite::dsl::invite
+            .filter(invite::dsl::referrer_id.eq(referrer_id))
+            .order(invite::dsl::created_at.desc())
+            .load::<Invite>(&mut connection)
+            .map_err(|e| -> crate::model::Error { e.into() })
+    }
+
+    /// deletes invite of referrer, referrals already created by it are kept
+    pub fn delete(mm: &ModelManager, referrer_id: &Uuid, invite_id: &Uuid) -> Result<usize> {
+        let mut connection = mm.conn()?;
+
+        diesel::delete(invite::dsl::invite)
+            .filter(invite::dsl::id.eq(invite_id))
+            .filter(invite::dsl::referrer_id.eq(referrer_id))
+            .execute(&mut connection)
+            .map_err(|e| -> crate::model::Error { e.into() })
+    }
+
+    /// creates referral of `account_id` to invite referrer
+    pub fn redeem(mm: &ModelManager, code: &str, account_id: &Uuid) -> Result<Referral> {
+        let mut connection = mm.conn()?;
+
+        connection.transaction(|conn| Self::redeem_with(conn, code, account_id))
+    }
+
+    /// redeems invite within transaction of caller, e.g. registration of `account_id`
+    pub(super) fn redeem_with(
+        connection: &mut PgConnection,
+        code: &str,
+        account_id: &Uuid,
+    ) -> Result<Referral> {
+        let now = chrono::Utc::now();
+
+        let invite = invite::dsl::invite
+            .filter(invite::dsl::code.eq(code))
+            .for_update()
+            .first::<Invite>(connection)
+            .optional()?
+            .ok_or(InviteRejection::InviteInvalid)?;
+
+        if invite.referrer_id == *account_id {
+            return Err(InviteRejection::OwnInvite.into());
+        }
+        if invite
+            .expires_at
+            .is_some_and(|expires_at| expires_at <= now)
+        {
+            return Err(InviteRejection::InviteExpired.into());
+        }
+        if invite.uses >= invite.max_uses {
+            return Err(InviteRejection::InviteUsedUp.into());
+        }
+
+        let referred = diesel::select(diesel::dsl::exists(
+            referral::dsl::referral
+                .filter(referral::dsl::user_id.eq(account_id))
+                .filter(referral::dsl::referrer_id.eq(invite.referrer_id))
+                .filter(referral::dsl::public_lvl.le(invite.public_lvl))
+                .filter(
+                    referral::dsl::expires_at
+                        .is_null()
+                        .or(referral::dsl::expires_at.gt(Some(now))),
+                ),
+        ))
+        .get_result::<bool>(connection)?;
+        if referred {
+            return Err(InviteRejection::AlreadyReferred.into());
+        }
+
+        diesel::update(invite::dsl::invite.filter(invite::dsl::id.eq(invite.id)))
+            .set((
+                invite::dsl::uses.eq(invite::dsl::uses + 1),
+                invite::dsl::updated_at.eq(now),
+            ))
+            .execute(connection)?;
+
+        ReferralBmc::insert(
+            connection,
+            ReferralForCreate {
+                public_lvl: invite.public_lvl,
+                ..ReferralForCreate::new(
+                    &invite.referrer_id,
+                    account_id,
+                    invite.expires_at,
+                    &invite.referrer_id,
+                )
+            },
+        )
+    }
+}
@@ -4,15 +4,25 @@ use self::store::{DbConn, Pool};
 
 pub mod account;
 pub mod album;
+pub mod api_token;
+pub mod blob;
 pub mod comment;
 pub mod error;
 pub mod favorite_image;
 pub mod image;
+pub mod image_metadata;
+pub mod invite;
+pub mod page;
+pub mod password;
 pub mod post;
+pub mod quota;
 pub mod referral;
+pub mod registration;
+pub mod session;
 mod store;
 pub mod sys_config;
 pub mod tag;
+pub mod upload;
 pub mod user;
 
 pub use self::error::{Error, Result};
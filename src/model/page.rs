@@ -0,0 +1,105 @@
+use uuid::Uuid;
+
+pub const DEFAULT_PAGE_SIZE: i64 = 20;
+pub const MAX_PAGE_SIZE: i64 = 100;
+
+/// Position of row in lists ordered by `(created_at, id)`, newest first
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cursor {
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub id: Uuid,
+}
+
+pub trait HasCursor {
+    fn cursor(&self) -> Cursor;
+}
+
+/// ### PageRequest
+/// `first` rows after `after` cursor, rows from the beginning when cursor is not given.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PageRequest {
+    pub first: i64,
+    pub after: Option<Cursor>,
+}
+
+impl PageRequest {
+    /// page size is clamped to `1..=MAX_PAGE_SIZE`
+    pub fn new(first: Option<i64>, after: Option<Cursor>) -> Self {
+        Self {
+            first: first.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE),
+            after,
+        }
+    }
+
+    /// one row more than requested is loaded to tell whether there is next page
+    pub fn limit(&self) -> i64 {
+        self.first + 1
+    }
+}
+
+impl Default for PageRequest {
+    fn default() -> Self {
+        Self::new(None, None)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub has_next_page: bool,
+    /// count of all rows in the list, not only in page
+    pub total_count: i64,
+}
+
+impl<T> Page<T> {
+    /// `rows` are loaded with `request.limit()`
+    pub fn new(mut rows: Vec<T>, request: &PageRequest, total_count: i64) -> Self {
+        let has_next_page = rows.len() as i64 > request.first;
+        rows.truncate(request.first as usize);
+
+        Self {
+            items: rows,
+            has_next_page,
+            total_count,
+        }
+    }
+
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            has_next_page: self.has_next_page,
+            total_count: self.total_count,
+        }
+    }
+
+    pub fn try_map<U, E>(self, f: impl FnMut(T) -> Result<U, E>) -> Result<Page<U>, E> {
+        Ok(Page {
+            items: self.items.into_iter().map(f).collect::<Result<_, _>>()?,
+            has_next_page: self.has_next_page,
+            total_count: self.total_count,
+        })
+    }
+}
+
+/// Orders boxed query of `table` by `(created_at, id)` descending,
+/// skips rows up to `request.after` cursor and limits it to `request.limit()` rows.
+macro_rules! keyset_page {
+    ($query:expr, $table:ident, $request:expr) => {{
+        let request: &$crate::model::page::PageRequest = $request;
+        let query = match request.after {
+            Some(cursor) => $query.filter(
+                $table::created_at
+                    .lt(cursor.created_at)
+                    .or($table::created_at
+                        .eq(cursor.created_at)
+                        .and($table::id.lt(cursor.id))),
+            ),
+            None => $query,
+        };
+        query
+            .order(($table::created_at.desc(), $table::id.desc()))
+            .limit(request.limit())
+    }};
+}
+
+pub(crate) use keyset_page;
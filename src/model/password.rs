@@ -0,0 +1,187 @@
+use diesel::prelude::*;
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::api_token::ApiTokenBmc;
+use super::registration::MIN_PASSWORD_LENGTH;
+use super::session::SessionBmc;
+use super::user::User;
+use super::{Error, ModelManager, Result};
+
+use crate::config;
+use crate::crypt::pass::{encrypt_pwd, validate_pwd};
+use crate::crypt::token::ResetToken;
+use crate::schema::{password_reset, users};
+
+/// ### PasswordReset
+/// Single-use token mailed to user who forgot password.\
+/// Only HMAC of the token secret is stored, requesting new token drops the unused ones.
+#[derive(Debug, Clone, PartialEq, Identifiable, Queryable)]
+#[diesel(table_name = password_reset)]
+pub struct PasswordReset {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub token_hash: String,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+    pub used_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = password_reset)]
+pub struct PasswordResetForCreate {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub token_hash: String,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Reason why password was not changed.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum PasswordRejection {
+    /// old password doesn't match
+    PasswordNotMatching,
+    PasswordTooShort {
+        min_length: usize,
+    },
+    ResetTokenInvalid,
+    ResetTokenExpired,
+}
+
+impl std::fmt::Display for PasswordRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::PasswordNotMatching => write!(f, "Password is not correct"),
+            Self::PasswordTooShort { min_length } => {
+                write!(f, "Password must have at least {min_length} characters")
+            }
+            Self::ResetTokenInvalid => write!(f, "Reset token is not valid"),
+            Self::ResetTokenExpired => write!(f, "Reset token has expired"),
+        }
+    }
+}
+
+/// checks length of new password and hashes it
+fn new_hash(password: &str) -> Result<String> {
+    if password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err(PasswordRejection::PasswordTooShort {
+            min_length: MIN_PASSWORD_LENGTH,
+        }
+        .into());
+    }
+
+    encrypt_pwd(password).map_err(|_| Error::FailedToEncryptPassword)
+}
+
+pub struct PasswordBmc;
+
+impl PasswordBmc {
+    /// changes password of user knowing the old one, sessions but `keep` are revoked
+    pub fn change(
+        mm: &ModelManager,
+        user_id: &Uuid,
+        old_password: &str,
+        new_password: &str,
+        keep: &Uuid,
+    ) -> Result<User> {
+        let mut connection = mm.conn()?;
+
+        let user = users::dsl::users
+            .filter(users::dsl::id.eq(user_id))
+            .first::<User>(&mut connection)?;
+        validate_pwd(old_password, &user.hash)
+            .map_err(|_| PasswordRejection::PasswordNotMatching)?;
+        let hash = new_hash(new_password)?;
+
+        connection.transaction(|conn| {
+            let user = Self::set_hash(conn, user_id, hash)?;
+            SessionBmc::revoke_others_with(conn, user_id, keep)?;
+
+            Ok(user)
+        })
+    }
+
+    /// issues reset token of user with `email`, none when there is no such user
+    pub fn request_reset(mm: &ModelManager, email: &str) -> Result<Option<(User, ResetToken)>> {
+        let mut connection = mm.conn()?;
+
+        let user = users::dsl::users
+            .filter(users::dsl::email.eq(email))
+            .first::<User>(&mut connection)
+            .optional()?;
+        let Some(user) = user else {
+            return Ok(None);
+        };
+
+        let id = Uuid::new_v4();
+        let token = ResetToken::new(&id);
+        let token_hash = token.hash().map_err(|_| Error::FailedToHashToken)?;
+        let expires_at =
+            chrono::Utc::now() + chrono::Duration::seconds(config().PASSWORD_RESET_DURATION);
+
+        connection.transaction::<_, Error, _>(|conn| {
+            diesel::delete(password_reset::dsl::password_reset)
+                .filter(password_reset::dsl::user_id.eq(user.id))
+                .filter(password_reset::dsl::used_at.is_null())
+                .execute(conn)?;
+
+            diesel::insert_into(password_reset::dsl::password_reset)
+                .values(PasswordResetForCreate {
+                    id,
+                    user_id: user.id,
+                    token_hash,
+                    expires_at,
+                })
+                .execute(conn)?;
+
+            Ok(())
+        })?;
+
+        Ok(Some((user, token)))
+    }
+
+    /// sets new password with reset token, token is used up and all sessions and API tokens are revoked
+    pub fn reset(mm: &ModelManager, token: &ResetToken, new_password: &str) -> Result<User> {
+        let hash = new_hash(new_password)?;
+
+        let mut connection = mm.conn()?;
+        connection.transaction(|conn| {
+            let now = chrono::Utc::now();
+
+            let reset = password_reset::dsl::password_reset
+                .filter(password_reset::dsl::id.eq(token.id))
+                .for_update()
+                .first::<PasswordReset>(conn)
+                .optional()?
+                .ok_or(PasswordRejection::ResetTokenInvalid)?;
+
+            if token.verify(&reset.token_hash).is_err() || reset.used_at.is_some() {
+                return Err(PasswordRejection::ResetTokenInvalid.into());
+            }
+            if reset.expires_at <= now {
+                return Err(PasswordRejection::ResetTokenExpired.into());
+            }
+
+            diesel::update(password_reset::dsl::password_reset.find(reset.id))
+                .set(password_reset::dsl::used_at.eq(Some(now)))
+                .execute(conn)?;
+
+            let user = Self::set_hash(conn, &reset.user_id, hash)?;
+            SessionBmc::revoke_users(conn, &[user.id])?;
+            ApiTokenBmc::revoke_users(conn, &[user.id])?;
+
+            Ok(user)
+        })
+    }
+
+    fn set_hash(connection: &mut PgConnection, user_id: &Uuid, hash: String) -> Result<User> {
+        diesel::update(users::dsl::users)
+            .filter(users::dsl::id.eq(user_id))
+            .set((
+                users::dsl::hash.eq(hash),
+                users::dsl::updated_at.eq(chrono::Utc::now()),
+            ))
+            .get_result::<User>(connection)
+            .map_err(|e| -> crate::model::Error { e.into() })
+    }
+}
@@ -1,3 +1,4 @@
+use diesel::pg::Pg;
 use diesel::prelude::*;
 use diesel::{
     query_builder::AsChangeset, ExpressionMethods, Identifiable, Insertable, Queryable, RunQueryDsl,
@@ -5,9 +6,13 @@ use diesel::{
 use uuid::Uuid;
 
 use crate::graphql::guard::Accessship;
-use crate::schema::{post, post_image};
+use crate::schema::{account, fav, post, post_image, referral, users};
 
-use super::account::AccountBmc;
+use super::account::{Account, AccountBmc};
+use super::image::ImageBmc;
+use super::page::{keyset_page, Cursor, HasCursor, Page, PageRequest};
+use super::sys_config::SysConfigBmc;
+use super::user::UserBmc;
 use super::{ModelManager, Result};
 
 #[derive(Debug, Clone, PartialEq, Identifiable, Queryable)]
@@ -24,6 +29,65 @@ pub struct Post {
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
 
+/// ### LockedPost
+/// Stub of post which viewer may not see yet (e.g. subscriber-only),
+/// with placeholders of its first image instead of content.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LockedPost {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub public_lvl: i32,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub blurhash: Option<String>,
+    pub preview: Option<String>,
+}
+
+impl LockedPost {
+    fn new(mm: &ModelManager, post: Post) -> Result<Self> {
+        let image = ImageBmc::first_in_post(mm, &post.id)?;
+        let (blurhash, preview) = match image {
+            Some(image) => (image.blurhash, image.preview),
+            None => (None, None),
+        };
+
+        Ok(Self {
+            id: post.id,
+            user_id: post.user_id,
+            public_lvl: post.public_lvl,
+            created_at: post.created_at,
+            blurhash,
+            preview,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PostListItem {
+    Post(Post),
+    Locked(LockedPost),
+}
+
+impl HasCursor for Post {
+    fn cursor(&self) -> Cursor {
+        Cursor {
+            created_at: self.created_at,
+            id: self.id,
+        }
+    }
+}
+
+impl HasCursor for PostListItem {
+    fn cursor(&self) -> Cursor {
+        match self {
+            PostListItem::Post(post) => post.cursor(),
+            PostListItem::Locked(post) => Cursor {
+                created_at: post.created_at,
+                id: post.id,
+            },
+        }
+    }
+}
+
 #[derive(Insertable)]
 #[diesel(table_name = post)]
 pub struct PostForCreate {
@@ -137,7 +201,8 @@ impl PostBmc {
         mm: &ModelManager,
         user_account_id: Option<Uuid>,
         target_user_id: &Uuid,
-    ) -> Result<Vec<Option<Post>>> {
+        page: &PageRequest,
+    ) -> Result<Page<PostListItem>> {
         let mut connection = mm.conn()?;
 
         let target_account = AccountBmc::get(mm, target_user_id)?;
@@ -146,33 +211,124 @@ impl PostBmc {
             .has_user_access(mm, user_account_id)?
             .try_into()?;
 
-        let posts = post::dsl::post
-            .filter(post::user_id.eq(target_user_id))
-            .order(post::created_at.desc())
-            .load::<Post>(&mut connection)
-            .map(|posts: Vec<Post>| {
-                posts
-                    .into_iter()
-                    .filter(|post| {
-                        // filter out all private post if user is subscriber or no user
-                        if access_lvl != 0 && post.public_lvl == 0 {
-                            false
-                        } else {
-                            true
-                        }
-                    })
-                    .map(|post| {
-                        // for non sub and no user display only public posts and rest should be null
-                        if post.public_lvl <= access_lvl {
-                            Some(post)
-                        } else {
-                            None
-                        }
-                    })
-                    .collect::<Vec<Option<Post>>>()
-            })?;
+        // filter out all private post if user is subscriber or no user
+        let visible = || {
+            let query = post::dsl::post
+                .filter(post::user_id.eq(target_user_id))
+                .into_boxed();
+            match access_lvl {
+                0 => query,
+                _ => query.filter(post::public_lvl.ne(0)),
+            }
+        };
+
+        let total_count = visible().count().get_result::<i64>(&mut connection)?;
+        let posts = keyset_page!(visible(), post, page).load::<Post>(&mut connection)?;
+
+        Page::new(posts, page, total_count).try_map(|post| {
+            // for non sub and no user display only public posts and rest should be locked
+            if post.public_lvl <= access_lvl {
+                Ok(PostListItem::Post(post))
+            } else {
+                LockedPost::new(mm, post).map(PostListItem::Locked)
+            }
+        })
+    }
 
-        Ok(posts)
+    /// ### Feed
+    /// `add_to_feed` posts of creators the account has valid referral to,
+    /// or only of `single_user_feed` user when it's set in sys config.\
+    /// Only posts the account may see are listed, newest first.
+    pub fn feed(
+        mm: &ModelManager,
+        user_account_id: Option<Uuid>,
+        page: &PageRequest,
+    ) -> Result<Page<Post>> {
+        let mut connection = mm.conn()?;
+
+        let creators = match (SysConfigBmc::get(mm)?.single_user_feed, user_account_id) {
+            (Some(user_id), _) => vec![user_id],
+            (None, Some(account_id)) => {
+                let referred = referral::dsl::referral
+                    .filter(referral::dsl::user_id.eq(account_id))
+                    .filter(
+                        referral::dsl::expires_at
+                            .is_null()
+                            .or(referral::dsl::expires_at.gt(Some(chrono::Utc::now()))),
+                    )
+                    .select(referral::dsl::referrer_id);
+                users::dsl::users
+                    .filter(users::dsl::account_id.eq_any(referred))
+                    .select(users::dsl::id)
+                    .load::<Uuid>(&mut connection)?
+            }
+            (None, None) => vec![],
+        };
+        let creators = creators_with_accounts(&mut connection, creators)?;
+        let visible_from = visible_from(mm, creators, user_account_id)?;
+
+        let feed = || {
+            visible(
+                post::dsl::post
+                    .filter(post::add_to_feed.eq(true))
+                    .into_boxed(),
+                &visible_from,
+            )
+        };
+
+        let total_count = feed().count().get_result::<i64>(&mut connection)?;
+        let posts = keyset_page!(feed(), post, page).load::<Post>(&mut connection)?;
+
+        Ok(Page::new(posts, page, total_count))
+    }
+
+    /// posts favorited by user, posts user lost access to are left out
+    pub fn list_favorites(
+        mm: &ModelManager,
+        user_id: &Uuid,
+        user_account_id: &Uuid,
+        page: &PageRequest,
+    ) -> Result<Page<Post>> {
+        let mut connection = mm.conn()?;
+
+        let favorited = || {
+            fav::dsl::fav
+                .filter(fav::dsl::user_id.eq(user_id))
+                .select(fav::dsl::post_id)
+        };
+        let creators = post::dsl::post
+            .filter(post::id.eq_any(favorited()))
+            .select(post::user_id)
+            .distinct()
+            .load::<Uuid>(&mut connection)?;
+        let creators = creators_with_accounts(&mut connection, creators)?;
+        let visible_from = visible_from(mm, creators, Some(*user_account_id))?;
+
+        let favorites = || {
+            visible(
+                post::dsl::post
+                    .filter(post::id.eq_any(favorited()))
+                    .into_boxed(),
+                &visible_from,
+            )
+        };
+
+        let total_count = favorites().count().get_result::<i64>(&mut connection)?;
+        let posts = keyset_page!(favorites(), post, page).load::<Post>(&mut connection)?;
+
+        Ok(Page::new(posts, page, total_count))
+    }
+
+    /// posts showing image
+    pub fn list_by_image(mm: &ModelManager, image_id: &Uuid) -> Result<Vec<Post>> {
+        let mut connection = mm.conn()?;
+
+        post::dsl::post
+            .inner_join(post_image::dsl::post_image)
+            .filter(post_image::dsl::image_id.eq(image_id))
+            .select(post::all_columns)
+            .load::<Post>(&mut connection)
+            .map_err(|e| e.into())
     }
 
     pub fn delete_post_image(
@@ -199,10 +355,56 @@ impl PostBmc {
     }
 }
 
+/// creator users with their accounts
+fn creators_with_accounts(
+    connection: &mut PgConnection,
+    user_ids: Vec<Uuid>,
+) -> Result<Vec<(Uuid, Account)>> {
+    users::dsl::users
+        .inner_join(account::dsl::account)
+        .filter(users::dsl::id.eq_any(user_ids))
+        .select((users::dsl::id, account::all_columns))
+        .load::<(Uuid, Account)>(connection)
+        .map_err(|e| e.into())
+}
+
+/// user ids of creators grouped by lowest `public_lvl` of their posts the account may see,
+/// creators account has no access to are left out
+fn visible_from(
+    mm: &ModelManager,
+    creators: Vec<(Uuid, Account)>,
+    user_account_id: Option<Uuid>,
+) -> Result<[Vec<Uuid>; 3]> {
+    let mut visible_from: [Vec<Uuid>; 3] = Default::default();
+    for (user_id, creator) in creators {
+        let access_lvl: Result<i32> = creator.has_access(mm, user_account_id)?.try_into();
+        if let Ok(lvl @ 0..=2) = access_lvl {
+            visible_from[lvl as usize].push(user_id);
+        }
+    }
+
+    Ok(visible_from)
+}
+
+/// filters out posts not visible by `visible_from` creators levels
+fn visible<'a>(
+    query: post::BoxedQuery<'a, Pg>,
+    visible_from: &[Vec<Uuid>; 3],
+) -> post::BoxedQuery<'a, Pg> {
+    let [all, subscribed, public] = visible_from.clone();
+    query.filter(
+        post::user_id
+            .eq_any(all)
+            .or(post::user_id.eq_any(subscribed).and(post::public_lvl.ge(1)))
+            .or(post::user_id.eq_any(public).and(post::public_lvl.ge(2))),
+    )
+}
+
 impl Post {
-    pub fn user_access(&self, mm: &ModelManager, user_id: Uuid) -> Result<Accessship> {
-        let account = AccountBmc::get_by_user_id(mm, &self.user_id)?;
-        let has_access = account.has_user_access(mm, Some(user_id))?;
+    /// access of `account_id` to the post owner's content
+    pub fn user_access(&self, mm: &ModelManager, account_id: Uuid) -> Result<Accessship> {
+        let owner = AccountBmc::get(mm, &UserBmc::get(mm, &self.user_id)?.account_id)?;
+        let has_access = owner.has_access(mm, Some(account_id))?;
 
         Ok(has_access)
     }
@@ -0,0 +1,148 @@
+use diesel::prelude::*;
+use diesel::PgConnection;
+use uuid::Uuid;
+
+use super::{Error, ModelManager, Result};
+
+use crate::schema::{account, sys_config, users};
+
+/// Limits of account storage, `None` is unlimited
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StorageQuota {
+    pub bytes: Option<i64>,
+    pub images: Option<i32>,
+}
+
+/// ### StorageUsage
+/// Stored bytes and image count of account together with its effective quota.\
+/// Every image counts size of its original, even when blob is shared by deduplication.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StorageUsage {
+    pub bytes: i64,
+    pub images: i32,
+    pub quota: StorageQuota,
+}
+
+impl StorageUsage {
+    /// fails when `bytes` and `images` more would exceed the quota
+    pub fn check(&self, bytes: i64, images: i32) -> Result<()> {
+        if let Some(quota) = self.quota.bytes {
+            if self.bytes + bytes > quota {
+                return Err(Error::StorageQuotaExceeded {
+                    used: self.bytes,
+                    requested: bytes,
+                    quota,
+                });
+            }
+        }
+        if let Some(quota) = self.quota.images {
+            if self.images + images > quota {
+                return Err(Error::ImageQuotaExceeded {
+                    used: self.images,
+                    requested: images,
+                    quota,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+type UsageRow = (i64, i32, Option<i64>, Option<i32>);
+
+pub struct QuotaBmc;
+
+impl QuotaBmc {
+    pub fn usage(mm: &ModelManager, account_id: &Uuid) -> Result<StorageUsage> {
+        let mut connection = mm.conn()?;
+
+        let row = account::dsl::account
+            .filter(account::dsl::id.eq(account_id))
+            .select(Self::usage_columns())
+            .first::<UsageRow>(&mut connection)?;
+        Self::with_defaults(&mut connection, row).map_err(|e| e.into())
+    }
+
+    /// adds uploaded images to usage of user account, fails when quota would be exceeded
+    pub(super) fn charge(
+        connection: &mut PgConnection,
+        user_id: &Uuid,
+        bytes: i64,
+        images: i32,
+    ) -> Result<()> {
+        // row lock keeps concurrent uploads from passing the check together
+        let account_id = users::dsl::users
+            .filter(users::dsl::id.eq(user_id))
+            .select(users::dsl::account_id)
+            .first::<Uuid>(connection)?;
+        let row = account::dsl::account
+            .filter(account::dsl::id.eq(account_id))
+            .select(Self::usage_columns())
+            .for_update()
+            .first::<UsageRow>(connection)?;
+
+        Self::with_defaults(connection, row)?.check(bytes, images)?;
+        Self::add(connection, &account_id, bytes, images)?;
+        Ok(())
+    }
+
+    /// removes deleted image from usage of user account
+    pub(super) fn refund(
+        connection: &mut PgConnection,
+        user_id: &Uuid,
+        bytes: i64,
+        images: i32,
+    ) -> QueryResult<()> {
+        let account_id = users::dsl::users
+            .filter(users::dsl::id.eq(user_id))
+            .select(users::dsl::account_id)
+            .first::<Uuid>(connection)?;
+        Self::add(connection, &account_id, -bytes, -images)
+    }
+
+    fn add(
+        connection: &mut PgConnection,
+        account_id: &Uuid,
+        bytes: i64,
+        images: i32,
+    ) -> QueryResult<()> {
+        diesel::update(account::dsl::account.filter(account::dsl::id.eq(account_id)))
+            .set((
+                account::dsl::used_bytes.eq(account::dsl::used_bytes + bytes),
+                account::dsl::used_images.eq(account::dsl::used_images + images),
+            ))
+            .execute(connection)?;
+        Ok(())
+    }
+
+    fn usage_columns() -> (
+        account::used_bytes,
+        account::used_images,
+        account::quota_bytes,
+        account::quota_images,
+    ) {
+        (
+            account::dsl::used_bytes,
+            account::dsl::used_images,
+            account::dsl::quota_bytes,
+            account::dsl::quota_images,
+        )
+    }
+
+    /// account quota, sys config default for those not set
+    fn with_defaults(connection: &mut PgConnection, row: UsageRow) -> QueryResult<StorageUsage> {
+        let (bytes, images, quota_bytes, quota_images) = row;
+        let (default_bytes, default_images) = sys_config::dsl::sys_config
+            .select((sys_config::dsl::quota_bytes, sys_config::dsl::quota_images))
+            .first::<(Option<i64>, Option<i32>)>(connection)?;
+
+        Ok(StorageUsage {
+            bytes,
+            images,
+            quota: StorageQuota {
+                bytes: quota_bytes.or(default_bytes),
+                images: quota_images.or(default_images),
+            },
+        })
+    }
+}
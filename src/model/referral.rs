@@ -6,6 +6,7 @@ use uuid::Uuid;
 
 use crate::schema::referral;
 
+use super::page::{keyset_page, Cursor, HasCursor, Page, PageRequest};
 use super::{ModelManager, Result};
 
 #[derive(Debug, Clone, PartialEq, Identifiable, Queryable)]
@@ -17,6 +18,48 @@ pub struct Referral {
     pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
+    /// lowest public lvl of referrer content user may see, 1 subscriber or 2 public
+    pub public_lvl: i32,
+    /// account who granted referral, `None` when it was deleted
+    pub granted_by: Option<Uuid>,
+}
+
+impl Referral {
+    /// referral opens content of `referrer_id` to `user_id` until it expires
+    pub fn opens(
+        &self,
+        user_id: &Uuid,
+        referrer_id: &Uuid,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> bool {
+        &self.user_id == user_id
+            && &self.referrer_id == referrer_id
+            && self.expires_at.is_none_or(|expires_at| expires_at > now)
+    }
+}
+
+/// lowest public lvl of `referrer_id` content `user_id` may see by `referrals`,
+/// `None` without valid referral
+pub fn lowest_public_lvl(
+    referrals: &[Referral],
+    user_id: &Uuid,
+    referrer_id: &Uuid,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Option<i32> {
+    referrals
+        .iter()
+        .filter(|referral| referral.opens(user_id, referrer_id, now))
+        .map(|referral| referral.public_lvl)
+        .min()
+}
+
+impl HasCursor for Referral {
+    fn cursor(&self) -> Cursor {
+        Cursor {
+            created_at: self.created_at,
+            id: self.id,
+        }
+    }
 }
 
 #[derive(Insertable)]
@@ -26,19 +69,25 @@ pub struct ReferralForCreate {
     pub referrer_id: Uuid,
     pub user_id: Uuid,
     pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub public_lvl: i32,
+    pub granted_by: Option<Uuid>,
 }
 
 impl ReferralForCreate {
+    /// subscriber referral granted by `granted_by` account
     pub fn new(
         referrer_id: &Uuid,
         user_id: &Uuid,
         expires_at: Option<chrono::DateTime<chrono::Utc>>,
+        granted_by: &Uuid,
     ) -> Self {
         Self {
             id: Uuid::new_v4(),
             referrer_id: referrer_id.clone(),
             user_id: user_id.clone(),
             expires_at,
+            public_lvl: 1,
+            granted_by: Some(*granted_by),
         }
     }
 }
@@ -48,7 +97,8 @@ impl ReferralForCreate {
 pub struct ReferralForUpdate {
     pub referrer_id: Option<Uuid>,
     pub user_id: Option<Uuid>,
-    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// `Some(None)` never expires
+    pub expires_at: Option<Option<chrono::DateTime<chrono::Utc>>>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
 
@@ -58,9 +108,17 @@ impl ReferralBmc {
     pub fn create(mm: &ModelManager, new_referral: ReferralForCreate) -> Result<Referral> {
         let mut connection = mm.conn()?;
 
+        Self::insert(&mut connection, new_referral)
+    }
+
+    /// creates referral within transaction of caller
+    pub(super) fn insert(
+        connection: &mut PgConnection,
+        new_referral: ReferralForCreate,
+    ) -> Result<Referral> {
         diesel::insert_into(referral::dsl::referral)
             .values(new_referral)
-            .get_result(&mut connection)
+            .get_result(connection)
             .map_err(|e| e.into())
     }
 
@@ -88,6 +146,48 @@ impl ReferralBmc {
             .map_err(|e| e.into())
     }
 
+    /// subscribers of referrer
+    pub fn list_by_referrer(
+        mm: &ModelManager,
+        referrer_id: &Uuid,
+        page: &PageRequest,
+    ) -> Result<Page<Referral>> {
+        let mut connection = mm.conn()?;
+
+        let subscribers = || {
+            referral::dsl::referral
+                .filter(referral::dsl::referrer_id.eq(referrer_id))
+                .into_boxed()
+        };
+
+        let total_count = subscribers().count().get_result::<i64>(&mut connection)?;
+        let referrals =
+            keyset_page!(subscribers(), referral, page).load::<Referral>(&mut connection)?;
+
+        Ok(Page::new(referrals, page, total_count))
+    }
+
+    /// subscriptions of user
+    pub fn list_by_user(
+        mm: &ModelManager,
+        user_id: &Uuid,
+        page: &PageRequest,
+    ) -> Result<Page<Referral>> {
+        let mut connection = mm.conn()?;
+
+        let subscriptions = || {
+            referral::dsl::referral
+                .filter(referral::dsl::user_id.eq(user_id))
+                .into_boxed()
+        };
+
+        let total_count = subscriptions().count().get_result::<i64>(&mut connection)?;
+        let referrals =
+            keyset_page!(subscriptions(), referral, page).load::<Referral>(&mut connection)?;
+
+        Ok(Page::new(referrals, page, total_count))
+    }
+
     pub fn update(
         mm: &ModelManager,
         referral_id: &Uuid,
@@ -109,17 +209,99 @@ impl ReferralBmc {
             .map_err(|e| e.into())
     }
 
+    /// whether user has referrals to referrer, but none of them is valid anymore
     pub fn is_expired(mm: &ModelManager, user_id: &Uuid, referrer_id: &Uuid) -> Result<bool> {
         let mut connection = mm.conn()?;
         let now = chrono::Utc::now();
-        let result = referral::dsl::referral
+        let expirations = referral::dsl::referral
             .filter(referral::dsl::user_id.eq(user_id))
             .filter(referral::dsl::referrer_id.eq(referrer_id))
-            .filter(referral::dsl::expires_at.lt(now))
-            .get_result::<Referral>(&mut connection);
-        match result {
-            Ok(_) => Ok(true),
-            Err(_) => Ok(false),
+            .select(referral::dsl::expires_at)
+            .load::<Option<chrono::DateTime<chrono::Utc>>>(&mut connection)?;
+
+        Ok(!expirations.is_empty()
+            && expirations
+                .iter()
+                .all(|expires_at| expires_at.is_some_and(|expires_at| expires_at <= now)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn referral(
+        referrer_id: Uuid,
+        user_id: Uuid,
+        public_lvl: i32,
+        expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Referral {
+        let now = chrono::Utc::now();
+        Referral {
+            id: Uuid::new_v4(),
+            referrer_id,
+            user_id,
+            expires_at,
+            created_at: now,
+            updated_at: now,
+            public_lvl,
+            granted_by: None,
         }
     }
+
+    #[test]
+    fn referral_opens_referrer_content_to_user() {
+        let (creator, viewer) = (Uuid::new_v4(), Uuid::new_v4());
+        let now = chrono::Utc::now();
+        let referrals = [referral(creator, viewer, 1, None)];
+
+        assert_eq!(
+            lowest_public_lvl(&referrals, &viewer, &creator, now),
+            Some(1)
+        );
+        // reversed direction: creator doesn't see viewer's content
+        assert_eq!(lowest_public_lvl(&referrals, &creator, &viewer, now), None);
+    }
+
+    #[test]
+    fn referral_of_other_creator_doesnt_open() {
+        let (creator, other, viewer) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let referrals = [referral(other, viewer, 1, None)];
+
+        assert_eq!(
+            lowest_public_lvl(&referrals, &viewer, &creator, chrono::Utc::now()),
+            None
+        );
+    }
+
+    #[test]
+    fn expired_referral_doesnt_open() {
+        let (creator, viewer) = (Uuid::new_v4(), Uuid::new_v4());
+        let now = chrono::Utc::now();
+        let expired = referral(creator, viewer, 1, Some(now - chrono::Duration::days(1)));
+        let valid = referral(creator, viewer, 2, Some(now + chrono::Duration::days(1)));
+
+        assert_eq!(
+            lowest_public_lvl(&[expired.clone()], &viewer, &creator, now),
+            None
+        );
+        assert_eq!(
+            lowest_public_lvl(&[expired, valid], &viewer, &creator, now),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn lowest_lvl_of_valid_referrals_wins() {
+        let (creator, viewer) = (Uuid::new_v4(), Uuid::new_v4());
+        let referrals = [
+            referral(creator, viewer, 2, None),
+            referral(creator, viewer, 1, None),
+        ];
+
+        assert_eq!(
+            lowest_public_lvl(&referrals, &viewer, &creator, chrono::Utc::now()),
+            Some(1)
+        );
+    }
 }
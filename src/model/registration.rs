@@ -0,0 +1,175 @@
+use diesel::prelude::*;
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::account::{Account, AccountForCreate};
+use super::invite::InviteBmc;
+use super::sys_config::SysConfigBmc;
+use super::user::{User, UserForCreate};
+use super::{Error, ModelManager, Result};
+
+use crate::config;
+use crate::crypt::pass::encrypt_pwd;
+use crate::graphql::guard::Role;
+use crate::schema::{account, users};
+
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+pub const MAX_NICK_LENGTH: usize = 32;
+
+/// Reason why self-service registration was not accepted.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum RegistrationRejection {
+    /// `allow_registration` is off in sys config
+    RegistrationClosed,
+    InviteRequired,
+    EmailInvalid,
+    NickInvalid {
+        max_length: usize,
+    },
+    PasswordTooShort {
+        min_length: usize,
+    },
+    EmailTaken,
+    NickTaken,
+}
+
+impl std::fmt::Display for RegistrationRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::RegistrationClosed => write!(f, "Registration is closed"),
+            Self::InviteRequired => write!(f, "Invite code is required"),
+            Self::EmailInvalid => write!(f, "Email is not valid"),
+            Self::NickInvalid { max_length } => write!(
+                f,
+                "Nick can have up to {max_length} letters, digits, '-' or '_'"
+            ),
+            Self::PasswordTooShort { min_length } => {
+                write!(f, "Password must have at least {min_length} characters")
+            }
+            Self::EmailTaken => write!(f, "Email is already registered"),
+            Self::NickTaken => write!(f, "Nick is already taken"),
+        }
+    }
+}
+
+/// ### Registration
+/// Self-service sign up, creates commenter account with its user.\
+/// `fullname` of account defaults to `nick`, invite code minted by creator is redeemed on sign up.
+#[derive(Debug, Clone)]
+pub struct Registration {
+    pub email: String,
+    pub nick: String,
+    pub password: String,
+    pub fullname: Option<String>,
+    pub invite_code: Option<String>,
+}
+
+impl Registration {
+    fn check(&self) -> std::result::Result<(), RegistrationRejection> {
+        let valid_email = match self.email.split_once('@') {
+            Some((name, domain)) => !name.is_empty() && domain.contains('.'),
+            None => false,
+        };
+        if !valid_email || self.email.len() > 255 {
+            return Err(RegistrationRejection::EmailInvalid);
+        }
+
+        let valid_nick = !self.nick.is_empty()
+            && self.nick.chars().count() <= MAX_NICK_LENGTH
+            && self
+                .nick
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+        if !valid_nick {
+            return Err(RegistrationRejection::NickInvalid {
+                max_length: MAX_NICK_LENGTH,
+            });
+        }
+
+        if self.password.chars().count() < MIN_PASSWORD_LENGTH {
+            return Err(RegistrationRejection::PasswordTooShort {
+                min_length: MIN_PASSWORD_LENGTH,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Invite is required when `REGISTRATION_INVITE_CODE` is set, it's either that code
+    /// or invite code minted by creator. Returns minted invite code to redeem.
+    fn check_invite(&self) -> std::result::Result<Option<&str>, RegistrationRejection> {
+        match (&config().REGISTRATION_INVITE_CODE, &self.invite_code) {
+            (Some(expected), Some(code)) if expected == code => Ok(None),
+            (_, Some(code)) => Ok(Some(code)),
+            (Some(_), None) => Err(RegistrationRejection::InviteRequired),
+            (None, None) => Ok(None),
+        }
+    }
+}
+
+pub struct RegistrationBmc;
+
+impl RegistrationBmc {
+    pub fn register(mm: &ModelManager, registration: Registration) -> Result<(Account, User)> {
+        if !SysConfigBmc::get(mm)?.allow_registration {
+            return Err(RegistrationRejection::RegistrationClosed.into());
+        }
+        let invite_code = registration.check_invite()?;
+        registration.check()?;
+
+        let hash =
+            encrypt_pwd(&registration.password).map_err(|_| Error::FailedToEncryptPassword)?;
+
+        let mut connection = mm.conn()?;
+        connection.transaction(|conn| {
+            let email_taken = diesel::select(diesel::dsl::exists(
+                users::dsl::users.filter(users::dsl::email.eq(&registration.email)),
+            ))
+            .get_result::<bool>(conn)?;
+            let account_email_taken = diesel::select(diesel::dsl::exists(
+                account::dsl::account.filter(account::dsl::email.eq(&registration.email)),
+            ))
+            .get_result::<bool>(conn)?;
+            if email_taken || account_email_taken {
+                return Err(RegistrationRejection::EmailTaken.into());
+            }
+
+            let nick_taken = diesel::select(diesel::dsl::exists(
+                users::dsl::users.filter(users::dsl::nick.eq(&registration.nick)),
+            ))
+            .get_result::<bool>(conn)?;
+            if nick_taken {
+                return Err(RegistrationRejection::NickTaken.into());
+            }
+
+            let account = diesel::insert_into(account::dsl::account)
+                .values(AccountForCreate {
+                    id: Uuid::new_v4(),
+                    fullname: registration
+                        .fullname
+                        .clone()
+                        .unwrap_or_else(|| registration.nick.clone()),
+                    email: registration.email.clone(),
+                    kind: Some(Role::Commenter.into()),
+                    public_lvl: None,
+                })
+                .get_result::<Account>(conn)?;
+
+            let user = diesel::insert_into(users::dsl::users)
+                .values(UserForCreate {
+                    id: Uuid::new_v4(),
+                    email: registration.email.clone(),
+                    nick: registration.nick.clone(),
+                    hash,
+                    account_id: account.id,
+                })
+                .get_result::<User>(conn)?;
+
+            if let Some(code) = invite_code {
+                InviteBmc::redeem_with(conn, code, &account.id)?;
+            }
+
+            Ok((account, user))
+        })
+    }
+}
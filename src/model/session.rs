@@ -0,0 +1,275 @@
+use diesel::prelude::*;
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::{Error, ModelManager, Result};
+
+use crate::config;
+use crate::crypt::token::RefreshToken;
+use crate::schema::{account, session, users};
+
+/// longest user agent kept with session
+const MAX_USER_AGENT_LENGTH: usize = 512;
+
+/// ### Session
+/// Login of user, access tokens carry its id and are accepted only while it's active.\
+/// Session ends when its refresh token expires or it's revoked.
+#[derive(Debug, Clone, PartialEq, Identifiable, Queryable)]
+#[diesel(table_name = session)]
+pub struct Session {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub refresh_hash: String,
+    pub user_agent: Option<String>,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+    pub revoked_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// last refresh of access token
+    pub last_used_at: chrono::DateTime<chrono::Utc>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+    /// hash of refresh token replaced by the last refresh
+    pub previous_refresh_hash: Option<String>,
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = session)]
+pub struct SessionForCreate {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub refresh_hash: String,
+    pub user_agent: Option<String>,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Reason why session can't be used anymore.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum SessionRejection {
+    SessionRevoked,
+    SessionExpired,
+    /// old refresh token was presented again, session is revoked as it may be stolen
+    RefreshTokenReused,
+    AccountBanned,
+}
+
+impl std::fmt::Display for SessionRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::SessionRevoked => write!(f, "Session was revoked"),
+            Self::SessionExpired => write!(f, "Session has expired"),
+            Self::RefreshTokenReused => write!(f, "Refresh token was already used"),
+            Self::AccountBanned => write!(f, "Account is banned"),
+        }
+    }
+}
+
+fn refresh_expiration() -> chrono::DateTime<chrono::Utc> {
+    chrono::Utc::now() + chrono::Duration::seconds(config().REFRESH_TOKEN_DURATION)
+}
+
+pub struct SessionBmc;
+
+impl SessionBmc {
+    /// starts session of user, returns its first refresh token
+    pub fn create(
+        mm: &ModelManager,
+        user_id: &Uuid,
+        user_agent: Option<&str>,
+    ) -> Result<(Session, RefreshToken)> {
+        let mut connection = mm.conn()?;
+
+        let id = Uuid::new_v4();
+        let refresh_token = RefreshToken::new(&id);
+        let refresh_hash = refresh_token.hash().map_err(|_| Error::FailedToHashToken)?;
+
+        let session = diesel::insert_into(session::dsl::session)
+            .values(SessionForCreate {
+                id,
+                user_id: *user_id,
+                refresh_hash,
+                user_agent: user_agent
+                    .map(|user_agent| user_agent.chars().take(MAX_USER_AGENT_LENGTH).collect()),
+                expires_at: refresh_expiration(),
+            })
+            .get_result::<Session>(&mut connection)?;
+
+        Ok((session, refresh_token))
+    }
+
+    /// session of access token, fails when it was revoked or has expired
+    pub fn get_active(mm: &ModelManager, id: &Uuid) -> Result<Session> {
+        let mut connection = mm.conn()?;
+
+        let session = session::dsl::session
+            .filter(session::dsl::id.eq(id))
+            .first::<Session>(&mut connection)
+            .optional()?
+            .ok_or(SessionRejection::SessionRevoked)?;
+
+        if session.revoked_at.is_some() {
+            return Err(SessionRejection::SessionRevoked.into());
+        }
+        if session.expires_at <= chrono::Utc::now() {
+            return Err(SessionRejection::SessionExpired.into());
+        }
+
+        Ok(session)
+    }
+
+    /// active session of refresh token, e.g. to log out with expired access token
+    pub fn get_by_refresh(mm: &ModelManager, refresh_token: &RefreshToken) -> Result<Session> {
+        let session = Self::get_active(mm, &refresh_token.session_id)?;
+        refresh_token
+            .verify(&session.refresh_hash)
+            .map_err(|_| Error::AccessDenied)?;
+
+        Ok(session)
+    }
+
+    /// replaces refresh token of session and extends it.\
+    /// Reused (already replaced) refresh token revokes session, any other wrong secret is only rejected,
+    /// as session id is known to everyone who has seen an access token.
+    pub fn refresh(
+        mm: &ModelManager,
+        refresh_token: &RefreshToken,
+    ) -> Result<(Session, RefreshToken)> {
+        let mut connection = mm.conn()?;
+        let now = chrono::Utc::now();
+
+        let result = connection.transaction::<_, Error, _>(|conn| {
+            let stored = session::dsl::session
+                .filter(session::dsl::id.eq(refresh_token.session_id))
+                .for_update()
+                .first::<Session>(conn)
+                .optional()?
+                .ok_or(SessionRejection::SessionRevoked)?;
+
+            if stored.revoked_at.is_some() {
+                return Err(SessionRejection::SessionRevoked.into());
+            }
+            if stored.expires_at <= now {
+                return Err(SessionRejection::SessionExpired.into());
+            }
+            if refresh_token.verify(&stored.refresh_hash).is_err() {
+                let reused = stored
+                    .previous_refresh_hash
+                    .as_deref()
+                    .is_some_and(|previous| refresh_token.verify(previous).is_ok());
+                return match reused {
+                    true => Ok(Err(stored.id)),
+                    false => Err(Error::AccessDenied),
+                };
+            }
+
+            let banned = users::dsl::users
+                .inner_join(account::dsl::account)
+                .filter(users::dsl::id.eq(stored.user_id))
+                .select(account::dsl::is_banned)
+                .first::<bool>(conn)?;
+            if banned {
+                return Err(SessionRejection::AccountBanned.into());
+            }
+
+            let next_token = RefreshToken::new(&stored.id);
+            let refresh_hash = next_token.hash().map_err(|_| Error::FailedToHashToken)?;
+
+            let session = diesel::update(session::dsl::session.find(stored.id))
+                .set((
+                    session::dsl::refresh_hash.eq(refresh_hash),
+                    session::dsl::previous_refresh_hash.eq(Some(stored.refresh_hash)),
+                    session::dsl::expires_at.eq(refresh_expiration()),
+                    session::dsl::last_used_at.eq(now),
+                    session::dsl::updated_at.eq(now),
+                ))
+                .get_result::<Session>(conn)?;
+
+            Ok(Ok((session, next_token)))
+        });
+
+        match result {
+            Ok(Ok(refreshed)) => Ok(refreshed),
+            // revoked outside of transaction, rejection would roll it back
+            Ok(Err(session_id)) => {
+                diesel::update(session::dsl::session.find(session_id))
+                    .set((
+                        session::dsl::revoked_at.eq(Some(now)),
+                        session::dsl::updated_at.eq(now),
+                    ))
+                    .execute(&mut connection)?;
+                Err(SessionRejection::RefreshTokenReused.into())
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// active sessions of user, recently used first
+    pub fn list_active(mm: &ModelManager, user_id: &Uuid) -> Result<Vec<Session>> {
+        let mut connection = mm.conn()?;
+
+        session::dsl::session
+            .filter(session::dsl::user_id.eq(user_id))
+            .filter(session::dsl::revoked_at.is_null())
+            .filter(session::dsl::expires_at.gt(chrono::Utc::now()))
+            .order(session::dsl::last_used_at.desc())
+            .load::<Session>(&mut connection)
+            .map_err(|e| -> crate::model::Error { e.into() })
+    }
+
+    /// revokes session of user, returns count of revoked sessions
+    pub fn revoke(mm: &ModelManager, user_id: &Uuid, session_id: &Uuid) -> Result<usize> {
+        let mut connection = mm.conn()?;
+        let now = chrono::Utc::now();
+
+        diesel::update(session::dsl::session)
+            .filter(session::dsl::id.eq(session_id))
+            .filter(session::dsl::user_id.eq(user_id))
+            .filter(session::dsl::revoked_at.is_null())
+            .set((
+                session::dsl::revoked_at.eq(Some(now)),
+                session::dsl::updated_at.eq(now),
+            ))
+            .execute(&mut connection)
+            .map_err(|e| -> crate::model::Error { e.into() })
+    }
+
+    /// revokes all sessions of user but `keep`, e.g. the current one
+    pub fn revoke_others(mm: &ModelManager, user_id: &Uuid, keep: &Uuid) -> Result<usize> {
+        let mut connection = mm.conn()?;
+
+        Self::revoke_others_with(&mut connection, user_id, keep)
+    }
+
+    /// revokes other sessions within transaction of caller, e.g. password change
+    pub(super) fn revoke_others_with(
+        connection: &mut PgConnection,
+        user_id: &Uuid,
+        keep: &Uuid,
+    ) -> Result<usize> {
+        let now = chrono::Utc::now();
+
+        diesel::update(session::dsl::session)
+            .filter(session::dsl::user_id.eq(user_id))
+            .filter(session::dsl::id.ne(keep))
+            .filter(session::dsl::revoked_at.is_null())
+            .set((
+                session::dsl::revoked_at.eq(Some(now)),
+                session::dsl::updated_at.eq(now),
+            ))
+            .execute(connection)
+            .map_err(|e| -> crate::model::Error { e.into() })
+    }
+
+    /// revokes all sessions of users, within transaction of caller, e.g. password reset or ban
+    pub(super) fn revoke_users(connection: &mut PgConnection, user_ids: &[Uuid]) -> Result<usize> {
+        let now = chrono::Utc::now();
+
+        diesel::update(session::dsl::session)
+            .filter(session::dsl::user_id.eq_any(user_ids))
+            .filter(session::dsl::revoked_at.is_null())
+            .set((
+                session::dsl::revoked_at.eq(Some(now)),
+                session::dsl::updated_at.eq(now),
+            ))
+            .execute(connection)
+            .map_err(|e| -> crate::model::Error { e.into() })
+    }
+}
@@ -3,21 +3,67 @@ use uuid::Uuid;
 
 use super::{ModelManager, Result};
 
-use crate::schema::sys_config;
+use crate::config;
+use crate::graphql::ExifPolicy;
+use crate::schema::{sys_config, upload_limit};
+
+use super::account::Account;
 
 #[derive(Debug, Clone, PartialEq, Identifiable, Queryable)]
 #[diesel(table_name = sys_config)]
 pub struct SysConfig {
-    id: Uuid,
-    allow_registration: bool,
-    single_user_feed: Option<Uuid>,
+    pub id: Uuid,
+    pub allow_registration: bool,
+    pub single_user_feed: Option<Uuid>,
+    pub exif_policy: String,
+    pub exif_retain_private: bool,
+    pub upload_mime_types: Vec<String>,
+    pub upload_max_width: i32,
+    pub upload_max_height: i32,
+    /// `None` is unlimited
+    pub quota_bytes: Option<i64>,
+    pub quota_images: Option<i32>,
 }
 
-#[derive(AsChangeset, Insertable)]
+#[derive(AsChangeset, Insertable, Default)]
 #[diesel(table_name = sys_config)]
 pub struct SysConfigForUpdate {
-    allow_registration: Option<bool>,
-    single_user_feed: Option<Uuid>,
+    pub allow_registration: Option<bool>,
+    pub single_user_feed: Option<Uuid>,
+    pub exif_policy: Option<String>,
+    pub exif_retain_private: Option<bool>,
+    pub upload_mime_types: Option<Vec<String>>,
+    pub upload_max_width: Option<i32>,
+    pub upload_max_height: Option<i32>,
+    /// `Some(None)` removes the quota
+    pub quota_bytes: Option<Option<i64>>,
+    pub quota_images: Option<Option<i32>>,
+}
+
+/// Max original size for accounts of given kind
+#[derive(Debug, Clone, PartialEq, Queryable, Insertable, AsChangeset)]
+#[diesel(table_name = upload_limit)]
+pub struct UploadLimit {
+    pub account_kind: String,
+    pub max_size: i64,
+}
+
+/// EXIF handling of account uploads
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExifSettings {
+    pub policy: ExifPolicy,
+    /// stripped fields are kept in image metadata, visible only to the owner
+    pub retain_private: bool,
+}
+
+/// What account is allowed to upload
+#[derive(Debug, Clone, PartialEq)]
+pub struct UploadRules {
+    pub mime_types: Vec<String>,
+    pub max_width: u32,
+    pub max_height: u32,
+    /// bytes of uploaded original
+    pub max_size: u64,
 }
 
 pub struct SysConfigBmc;
@@ -31,6 +77,22 @@ impl SysConfigBmc {
             .map_err(|e| -> crate::model::Error { e.into() })
     }
 
+    /// account settings, sys config defaults for those not set
+    pub fn exif_settings(mm: &ModelManager, account: &Account) -> Result<ExifSettings> {
+        let sys_config = Self::get(mm)?;
+
+        Ok(ExifSettings {
+            policy: account
+                .exif_policy
+                .clone()
+                .unwrap_or(sys_config.exif_policy)
+                .into(),
+            retain_private: account
+                .exif_retain_private
+                .unwrap_or(sys_config.exif_retain_private),
+        })
+    }
+
     pub fn update(mm: &ModelManager, sys_config: SysConfigForUpdate) -> Result<SysConfig> {
         let mut connection = mm.conn()?;
 
@@ -39,4 +101,67 @@ impl SysConfigBmc {
             .get_result::<SysConfig>(&mut connection)
             .map_err(|e| -> crate::model::Error { e.into() })
     }
+
+    /// sys config rules, with size limit of account kind
+    pub fn upload_rules(mm: &ModelManager, account: &Account) -> Result<UploadRules> {
+        let sys_config = Self::get(mm)?;
+        let mut connection = mm.conn()?;
+
+        let max_size = upload_limit::dsl::upload_limit
+            .filter(upload_limit::dsl::account_kind.eq(&account.kind))
+            .select(upload_limit::dsl::max_size)
+            .first::<i64>(&mut connection)
+            .optional()?;
+
+        Ok(UploadRules {
+            mime_types: sys_config.upload_mime_types,
+            max_width: sys_config.upload_max_width.max(0) as u32,
+            max_height: sys_config.upload_max_height.max(0) as u32,
+            max_size: max_size
+                .map(|max_size| max_size.max(0) as u64)
+                .unwrap_or(config().UPLOAD_MAX_SIZE),
+        })
+    }
+
+    pub fn list_upload_limits(mm: &ModelManager) -> Result<Vec<UploadLimit>> {
+        let mut connection = mm.conn()?;
+
+        upload_limit::dsl::upload_limit
+            .order(upload_limit::dsl::account_kind)
+            .load::<UploadLimit>(&mut connection)
+            .map_err(|e| -> crate::model::Error { e.into() })
+    }
+
+    /// sets limit of account kind, `None` removes it
+    pub fn set_upload_limit(
+        mm: &ModelManager,
+        account_kind: &str,
+        max_size: Option<i64>,
+    ) -> Result<Option<UploadLimit>> {
+        let mut connection = mm.conn()?;
+
+        match max_size {
+            Some(max_size) => {
+                let limit = UploadLimit {
+                    account_kind: account_kind.to_string(),
+                    max_size,
+                };
+                diesel::insert_into(upload_limit::dsl::upload_limit)
+                    .values(&limit)
+                    .on_conflict(upload_limit::dsl::account_kind)
+                    .do_update()
+                    .set(&limit)
+                    .get_result::<UploadLimit>(&mut connection)
+                    .map(Some)
+                    .map_err(|e| -> crate::model::Error { e.into() })
+            }
+            None => diesel::delete(
+                upload_limit::dsl::upload_limit
+                    .filter(upload_limit::dsl::account_kind.eq(account_kind)),
+            )
+            .execute(&mut connection)
+            .map(|_| None)
+            .map_err(|e| -> crate::model::Error { e.into() }),
+        }
+    }
 }
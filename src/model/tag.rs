@@ -8,6 +8,7 @@ use uuid::Uuid;
 
 use crate::schema::{post_tag, tag};
 
+use super::page::{keyset_page, Cursor, HasCursor, Page, PageRequest};
 use super::{ModelManager, Result};
 
 #[derive(Debug, Clone, PartialEq, Identifiable, Queryable)]
@@ -19,6 +20,15 @@ pub struct Tag {
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
 
+impl HasCursor for Tag {
+    fn cursor(&self) -> Cursor {
+        Cursor {
+            created_at: self.created_at,
+            id: self.id,
+        }
+    }
+}
+
 #[derive(Insertable)]
 #[diesel(table_name = tag)]
 pub struct TagForCreate {
@@ -83,11 +93,13 @@ impl TagBmc {
             .map_err(|e| e.into())
     }
 
-    pub fn list(mm: &ModelManager) -> Result<Vec<Tag>> {
+    pub fn list(mm: &ModelManager, page: &PageRequest) -> Result<Page<Tag>> {
         let mut connection = mm.conn()?;
-        tag::dsl::tag
-            .load::<Tag>(&mut connection)
-            .map_err(|e| e.into())
+        let total_count = tag::dsl::tag.count().get_result::<i64>(&mut connection)?;
+        let tags =
+            keyset_page!(tag::dsl::tag.into_boxed(), tag, page).load::<Tag>(&mut connection)?;
+
+        Ok(Page::new(tags, page, total_count))
     }
 
     pub fn get_by_name(mm: &ModelManager, name: String) -> Result<Vec<Tag>> {
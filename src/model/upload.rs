@@ -0,0 +1,93 @@
+use diesel::prelude::*;
+use uuid::Uuid;
+
+use super::{ModelManager, Result};
+
+use crate::schema::upload;
+
+/// ### Upload
+/// Resumable upload session, chunks are assembled on disk until
+/// `upload_offset` reaches `upload_length`.
+#[derive(Debug, Clone, PartialEq, Identifiable, Queryable)]
+#[diesel(table_name = upload)]
+pub struct Upload {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub upload_length: i64,
+    pub upload_offset: i64,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = upload)]
+pub struct UploadForCreate {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub upload_length: i64,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+pub struct UploadBmc;
+
+impl UploadBmc {
+    pub fn create(mm: &ModelManager, upload: UploadForCreate) -> Result<Upload> {
+        let mut connection = mm.conn()?;
+
+        diesel::insert_into(upload::dsl::upload)
+            .values(upload)
+            .get_result::<Upload>(&mut connection)
+            .map_err(|e| -> crate::model::Error { e.into() })
+    }
+
+    pub fn get(mm: &ModelManager, search_id: &Uuid) -> Result<Upload> {
+        let mut connection = mm.conn()?;
+
+        upload::dsl::upload
+            .filter(upload::dsl::id.eq(search_id))
+            .first::<Upload>(&mut connection)
+            .map_err(|e| -> crate::model::Error { e.into() })
+    }
+
+    pub fn list_expired(mm: &ModelManager) -> Result<Vec<Upload>> {
+        let mut connection = mm.conn()?;
+
+        upload::dsl::upload
+            .filter(upload::dsl::expires_at.lt(chrono::Utc::now()))
+            .load::<Upload>(&mut connection)
+            .map_err(|e| -> crate::model::Error { e.into() })
+    }
+
+    /// moves offset only if it wasn't changed by another request in the meantime,
+    /// returns `None` when offsets didn't match
+    pub fn update_offset(
+        mm: &ModelManager,
+        search_id: &Uuid,
+        old_offset: i64,
+        new_offset: i64,
+    ) -> Result<Option<Upload>> {
+        let mut connection = mm.conn()?;
+
+        diesel::update(
+            upload::dsl::upload
+                .filter(upload::dsl::id.eq(search_id))
+                .filter(upload::dsl::upload_offset.eq(old_offset)),
+        )
+        .set((
+            upload::dsl::upload_offset.eq(new_offset),
+            upload::dsl::updated_at.eq(chrono::Utc::now()),
+        ))
+        .get_result::<Upload>(&mut connection)
+        .optional()
+        .map_err(|e| -> crate::model::Error { e.into() })
+    }
+
+    pub fn delete(mm: &ModelManager, search_id: &Uuid) -> Result<usize> {
+        let mut connection = mm.conn()?;
+
+        diesel::delete(upload::dsl::upload.filter(upload::dsl::id.eq(search_id)))
+            .execute(&mut connection)
+            .map_err(|e| -> crate::model::Error { e.into() })
+    }
+}
@@ -5,6 +5,8 @@ use uuid::Uuid;
 
 use super::{
     error::{Error, Result},
+    page::{keyset_page, Cursor, HasCursor, Page, PageRequest},
+    session::SessionBmc,
     ModelManager,
 };
 use crate::crypt::token::Token;
@@ -85,75 +87,76 @@ impl UserBmc {
             .map_err(|e| e.into())
     }
 
-    pub fn list(mm: &ModelManager, user_account_id: Option<Uuid>) -> Result<Vec<User>> {
+    /// Users whose profiles caller can list: all for admin, public creators
+    /// and creators caller has valid referral to for others, nobody for banned caller.
+    pub fn list(
+        mm: &ModelManager,
+        user_account_id: Option<Uuid>,
+        page: &PageRequest,
+    ) -> Result<Page<User>> {
         let mut connection = mm.conn()?;
 
-        match user_account_id {
-            Some(account_id) => {
-                let account = account::dsl::account
+        let caller = match user_account_id {
+            Some(account_id) => Some(
+                account::dsl::account
                     .filter(account::dsl::id.eq(account_id))
-                    .first::<crate::model::account::Account>(&mut connection)?;
-                if account.is_banned {
-                    return Ok(vec![]);
-                }
+                    .first::<crate::model::account::Account>(&mut connection)?,
+            ),
+            None => None,
+        };
+        if caller.as_ref().is_some_and(|account| account.is_banned) {
+            return Ok(Page::new(vec![], page, 0));
+        }
 
-                if account.is_admin {
-                    users::dsl::users
-                        .load::<User>(&mut connection)
-                        .map_err(|e| e.into())
-                } else {
-                    let mut public: Vec<User> = account::dsl::account
-                        .filter(account::dsl::public_lvl.eq(2))
-                        .filter(account::dsl::is_banned.eq(false))
-                        .inner_join(
-                            users::dsl::users.on(users::dsl::account_id.eq(account::dsl::id)),
-                        )
-                        .select(users::all_columns)
-                        .load::<User>(&mut connection)?;
-                    let mut users_with_referral: Vec<User> = referral::dsl::referral
-                        .filter(referral::dsl::user_id.eq(account_id))
+        let listed = || {
+            let query = users::dsl::users
+                .inner_join(account::dsl::account)
+                .select(users::all_columns)
+                .into_boxed();
+            match &caller {
+                Some(account) if account.is_admin => query,
+                Some(account) => {
+                    let referred = referral::dsl::referral
+                        .filter(referral::dsl::user_id.eq(account.id))
                         .filter(
                             referral::dsl::expires_at
                                 .is_null()
                                 .or(referral::dsl::expires_at.gt(Some(chrono::Utc::now()))),
                         )
-                        .inner_join(
-                            account::dsl::account
-                                .on(account::dsl::id.eq(referral::dsl::referrer_id)),
-                        )
-                        .filter(account::dsl::public_lvl.ge(1))
-                        .filter(account::dsl::is_banned.eq(false))
-                        .inner_join(
-                            users::dsl::users.on(users::dsl::account_id.eq(account::dsl::id)),
-                        )
-                        .select(users::all_columns)
-                        .load::<User>(&mut connection)?;
-
-                    public.append(&mut users_with_referral);
-                    public.sort_by(|a, b| a.id.cmp(&b.id));
-                    public.dedup_by(|a, b| a.id == b.id);
-                    public.sort_by(|a, b| a.nick.cmp(&b.nick));
-
-                    Ok(public)
+                        .select(referral::dsl::referrer_id);
+                    query.filter(account::dsl::is_banned.eq(false)).filter(
+                        account::dsl::public_lvl.eq(2).or(account::dsl::public_lvl
+                            .ge(1)
+                            .and(account::dsl::id.eq_any(referred))),
+                    )
                 }
+                None => query
+                    .filter(account::dsl::public_lvl.eq(2))
+                    .filter(account::dsl::is_banned.eq(false)),
             }
-            None => users::dsl::users
-                .inner_join(account::dsl::account)
-                .filter(account::dsl::public_lvl.eq(2))
-                .filter(account::dsl::is_banned.eq(false))
-                .select(users::all_columns)
-                .load::<User>(&mut connection)
-                .map_err(|e| e.into()),
-        }
+        };
+
+        let total_count = listed().count().get_result::<i64>(&mut connection)?;
+        let users = keyset_page!(listed(), users, page).load::<User>(&mut connection)?;
+
+        Ok(Page::new(users, page, total_count))
     }
 
+    /// password change revokes all sessions of user
     pub fn update(mm: &ModelManager, user_id: &Uuid, new_user: UserForUpdate) -> Result<User> {
         let mut connection = mm.conn()?;
-        diesel::update(users::dsl::users)
-            .filter(users::dsl::id.eq(user_id))
-            .set(&new_user)
-            .get_result::<User>(&mut connection)
-            .map_err(|e| e.into())
+        connection.transaction(|conn| {
+            let user = diesel::update(users::dsl::users)
+                .filter(users::dsl::id.eq(user_id))
+                .set(&new_user)
+                .get_result::<User>(conn)?;
+
+            if new_user.hash.is_some() {
+                SessionBmc::revoke_users(conn, &[user.id])?;
+            }
+
+            Ok(user)
+        })
     }
 
     pub fn delete(mm: &ModelManager, user_id: &Uuid) -> Result<()> {
@@ -177,6 +180,17 @@ impl UserBmc {
         Ok(())
     }
 
+    /// users having image as their profile picture
+    pub fn list_by_picture(mm: &ModelManager, image_id: &Uuid) -> Result<Vec<User>> {
+        let mut connection = mm.conn()?;
+        users::dsl::users
+            .inner_join(user_picture::dsl::user_picture)
+            .filter(user_picture::dsl::image_id.eq(image_id))
+            .select(users::all_columns)
+            .load::<User>(&mut connection)
+            .map_err(|e| e.into())
+    }
+
     pub fn delete_picture(mm: &ModelManager, user_id: &Uuid, picture_id: &Uuid) -> Result<()> {
         let mut connection = mm.conn()?;
         diesel::delete(
@@ -190,6 +204,15 @@ impl UserBmc {
     }
 }
 
+impl HasCursor for User {
+    fn cursor(&self) -> Cursor {
+        Cursor {
+            created_at: self.created_at,
+            id: self.id,
+        }
+    }
+}
+
 impl User {
     pub fn validate_pwd(&self, pwd: &str) -> crate::crypt::Result<()> {
         crate::crypt::pass::validate_pwd(pwd, &self.hash)
@@ -197,7 +220,8 @@ impl User {
 }
 
 impl User {
-    pub fn into_token(self) -> crate::crypt::Result<Token> {
-        Token::new(&self.id, &self.nick)
+    /// access token of user session
+    pub fn into_token(self, session_id: &Uuid) -> crate::crypt::Result<Token> {
+        Token::new(session_id, &self.id, &self.nick)
     }
 }
@@ -14,6 +14,13 @@ diesel::table! {
         is_banned -> Bool,
         created_at -> Timestamptz,
         updated_at -> Timestamptz,
+        #[max_length = 255]
+        exif_policy -> Nullable<Varchar>,
+        exif_retain_private -> Nullable<Bool>,
+        quota_bytes -> Nullable<Int8>,
+        quota_images -> Nullable<Int4>,
+        used_bytes -> Int8,
+        used_images -> Int4,
     }
 }
 
@@ -41,6 +48,32 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    api_token (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        #[max_length = 128]
+        name -> Varchar,
+        #[max_length = 128]
+        token_hash -> Varchar,
+        scopes -> Array<Nullable<Text>>,
+        expires_at -> Nullable<Timestamptz>,
+        last_used_at -> Nullable<Timestamptz>,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    blob (path) {
+        path -> Uuid,
+        hash -> Nullable<Text>,
+        user_id -> Nullable<Uuid>,
+        size -> Nullable<Int8>,
+        ref_count -> Int4,
+        created_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     comment (id) {
         id -> Uuid,
@@ -49,6 +82,7 @@ diesel::table! {
         body -> Text,
         created_at -> Timestamptz,
         updated_at -> Timestamptz,
+        hidden_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -71,6 +105,55 @@ diesel::table! {
         path -> Uuid,
         created_at -> Timestamptz,
         updated_at -> Timestamptz,
+        hash -> Nullable<Text>,
+        blurhash -> Nullable<Text>,
+        preview -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    image_metadata (image_id) {
+        image_id -> Uuid,
+        width -> Nullable<Int4>,
+        height -> Nullable<Int4>,
+        mime_type -> Nullable<Text>,
+        byte_size -> Int8,
+        captured_at -> Nullable<Timestamptz>,
+        camera_model -> Nullable<Text>,
+        orientation -> Nullable<Int4>,
+        gps_latitude -> Nullable<Float8>,
+        gps_longitude -> Nullable<Float8>,
+        gps_altitude -> Nullable<Float8>,
+        created_at -> Timestamptz,
+        #[max_length = 255]
+        exif_policy -> Varchar,
+    }
+}
+
+diesel::table! {
+    invite (id) {
+        id -> Uuid,
+        #[max_length = 64]
+        code -> Varchar,
+        referrer_id -> Uuid,
+        public_lvl -> Int4,
+        max_uses -> Int4,
+        uses -> Int4,
+        expires_at -> Nullable<Timestamptz>,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    password_reset (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        #[max_length = 128]
+        token_hash -> Varchar,
+        expires_at -> Timestamptz,
+        used_at -> Nullable<Timestamptz>,
+        created_at -> Timestamptz,
     }
 }
 
@@ -113,6 +196,26 @@ diesel::table! {
         expires_at -> Nullable<Timestamptz>,
         created_at -> Timestamptz,
         updated_at -> Timestamptz,
+        public_lvl -> Int4,
+        granted_by -> Nullable<Uuid>,
+    }
+}
+
+diesel::table! {
+    session (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        #[max_length = 128]
+        refresh_hash -> Varchar,
+        #[max_length = 512]
+        user_agent -> Nullable<Varchar>,
+        expires_at -> Timestamptz,
+        revoked_at -> Nullable<Timestamptz>,
+        last_used_at -> Timestamptz,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+        #[max_length = 128]
+        previous_refresh_hash -> Nullable<Varchar>,
     }
 }
 
@@ -121,6 +224,14 @@ diesel::table! {
         id -> Uuid,
         allow_registration -> Bool,
         single_user_feed -> Nullable<Uuid>,
+        #[max_length = 255]
+        exif_policy -> Varchar,
+        exif_retain_private -> Bool,
+        upload_mime_types -> Array<Text>,
+        upload_max_width -> Int4,
+        upload_max_height -> Int4,
+        quota_bytes -> Nullable<Int8>,
+        quota_images -> Nullable<Int4>,
     }
 }
 
@@ -134,6 +245,26 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    upload (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        upload_length -> Int8,
+        upload_offset -> Int8,
+        expires_at -> Timestamptz,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    upload_limit (account_kind) {
+        #[max_length = 255]
+        account_kind -> Varchar,
+        max_size -> Int8,
+    }
+}
+
 diesel::table! {
     user_picture (id) {
         id -> Uuid,
@@ -163,16 +294,22 @@ diesel::joinable!(album -> account (user_id));
 diesel::joinable!(album -> image (picture));
 diesel::joinable!(album_post -> album (album_id));
 diesel::joinable!(album_post -> post (post_id));
+diesel::joinable!(api_token -> users (user_id));
 diesel::joinable!(comment -> post (post_id));
 diesel::joinable!(comment -> users (user_id));
 diesel::joinable!(fav -> post (post_id));
 diesel::joinable!(fav -> users (user_id));
 diesel::joinable!(image -> users (user_id));
+diesel::joinable!(image_metadata -> image (image_id));
+diesel::joinable!(invite -> account (referrer_id));
+diesel::joinable!(password_reset -> users (user_id));
 diesel::joinable!(post -> users (user_id));
 diesel::joinable!(post_image -> image (image_id));
 diesel::joinable!(post_image -> post (post_id));
 diesel::joinable!(post_tag -> post (post_id));
 diesel::joinable!(post_tag -> tag (tag_id));
+diesel::joinable!(session -> users (user_id));
+diesel::joinable!(upload -> users (user_id));
 diesel::joinable!(user_picture -> image (image_id));
 diesel::joinable!(user_picture -> users (user_id));
 diesel::joinable!(users -> account (account_id));
@@ -181,15 +318,23 @@ diesel::allow_tables_to_appear_in_same_query!(
     account,
     album,
     album_post,
+    api_token,
+    blob,
     comment,
     fav,
     image,
+    image_metadata,
+    invite,
+    password_reset,
     post,
     post_image,
     post_tag,
     referral,
+    session,
     sys_config,
     tag,
+    upload,
+    upload_limit,
     user_picture,
     users,
 );
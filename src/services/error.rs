@@ -13,6 +13,16 @@ pub enum Error {
     LustError(LustError),
 
     UrlParseFailed(String),
+
+    // local filesystem store errors
+    Io(String),
+    BadImageId(String),
+    ImageNotFound(String, String),
+
+    // in-process image processing errors
+    ProcessorConfig(String),
+    UnknownPreset(String),
+    ImageProcessing(String),
 }
 
 impl From<reqwest::Error> for Error {
@@ -27,6 +37,12 @@ impl From<LustError> for Error {
     }
 }
 
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e.to_string())
+    }
+}
+
 impl Into<async_graphql::Error> for Error {
     fn into(self) -> async_graphql::Error {
         debug!("{:<12} - {:?}", "SERVICE", self);
@@ -36,6 +52,21 @@ impl Into<async_graphql::Error> for Error {
                 async_graphql::Error::new(format!("Url parse error: {}", s))
             }
             Error::LustError(e) => async_graphql::Error::new(e.to_string()),
+            Error::Io(e) => async_graphql::Error::new(format!("Io error: {}", e)),
+            Error::BadImageId(id) => async_graphql::Error::new(format!("Bad image id: {}", id)),
+            Error::ImageNotFound(bucket, image) => async_graphql::Error::new(format!(
+                "Not found, bucket: {}, image: {}",
+                bucket, image
+            )),
+            Error::ProcessorConfig(e) => {
+                async_graphql::Error::new(format!("Processor config error: {}", e))
+            }
+            Error::UnknownPreset(preset) => {
+                async_graphql::Error::new(format!("Unknown size preset: {}", preset))
+            }
+            Error::ImageProcessing(e) => {
+                async_graphql::Error::new(format!("Image processing error: {}", e))
+            }
         }
     }
 }
@@ -46,6 +77,14 @@ impl ToString for Error {
             Error::ReqwestFailed(e) => format!("Reqwest error: {}", e),
             Error::UrlParseFailed(s) => format!("Url parse error: {}", s),
             Error::LustError(e) => e.to_string(),
+            Error::Io(e) => format!("Io error: {}", e),
+            Error::BadImageId(id) => format!("Bad image id: {}", id),
+            Error::ImageNotFound(bucket, image) => {
+                format!("Not found, bucket: {}, image: {}", bucket, image)
+            }
+            Error::ProcessorConfig(e) => format!("Processor config error: {}", e),
+            Error::UnknownPreset(preset) => format!("Unknown size preset: {}", preset),
+            Error::ImageProcessing(e) => format!("Image processing error: {}", e),
         }
     }
 }
@@ -0,0 +1,279 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use axum::http::{
+    header::{CONTENT_LENGTH, CONTENT_TYPE},
+    HeaderMap, HeaderValue,
+};
+use bytes::Bytes;
+use tokio::{fs, io::AsyncWriteExt};
+use tokio_stream::StreamExt;
+use tokio_util::io::ReaderStream;
+use tracing::debug;
+use uuid::Uuid;
+
+use crate::graphql::ExifPolicy;
+
+use super::error::{Error, Result};
+use super::processor::{ImageFormat, ProcessingMode, Processor};
+use super::store::{ByteStream, ImageFile, ImageStore, StoredImage, UploadInspector};
+use super::transform::Transform;
+
+const ORIGINAL: &str = "original";
+// variant name used when image is served without size preset
+const FULL_SIZE: &str = "full";
+
+/// ### LocalStore
+/// Keeps originals and their processed variants on local filesystem:
+/// ```
+/// {LOCAL_STORE_DIR}/{bucket}/{image_id}/original
+/// {LOCAL_STORE_DIR}/{bucket}/{image_id}/{preset}.{format}
+/// {LOCAL_STORE_DIR}/{bucket}/{image_id}/t_{transform key}.{format}
+/// ```
+pub struct LocalStore {
+    root: PathBuf,
+    processor: Arc<Processor>,
+}
+
+impl LocalStore {
+    pub fn new(root: &str, processor: Processor) -> Result<Self> {
+        std::fs::create_dir_all(root)?;
+        Ok(Self {
+            root: PathBuf::from(root),
+            processor: Arc::new(processor),
+        })
+    }
+
+    fn image_dir(&self, bucket: &str, image_id: &str) -> Result<PathBuf> {
+        // only uuids are accepted so image id can't escape bucket directory
+        let image_id =
+            Uuid::parse_str(image_id).map_err(|_| Error::BadImageId(image_id.to_string()))?;
+        Ok(self.root.join(bucket).join(image_id.to_string()))
+    }
+
+    async fn write_stream(path: &Path, mut body: ByteStream) -> Result<u64> {
+        let mut file = fs::File::create(path).await?;
+        let mut written = 0;
+        while let Some(chunk) = body.next().await {
+            let chunk = chunk?;
+            file.write_all(&chunk).await?;
+            written += chunk.len() as u64;
+        }
+        file.flush().await?;
+        Ok(written)
+    }
+
+    /// writes into temp file next to `path` and renames it into place,
+    /// so concurrent readers never see partially written variant
+    async fn write_atomic(path: &Path, bytes: &[u8]) -> Result<()> {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let tmp = path.with_file_name(format!(".{}.{}.tmp", name, Uuid::new_v4()));
+        if let Err(e) = fs::write(&tmp, bytes).await {
+            let _ = fs::remove_file(&tmp).await;
+            return Err(e.into());
+        }
+        if let Err(e) = fs::rename(&tmp, path).await {
+            let _ = fs::remove_file(&tmp).await;
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    /// validates stored original and, for `aot` buckets, renders all presets
+    async fn process_original(&self, bucket: &str, dir: &Path) -> Result<()> {
+        let bucket_config = self.processor.bucket(bucket)?.clone();
+        let original = fs::read(dir.join(ORIGINAL)).await?;
+        let processor = self.processor.clone();
+        let bucket_name = bucket.to_string();
+
+        let variants = tokio::task::spawn_blocking(move || -> Result<Vec<(String, Bytes)>> {
+            let image = Processor::decode(&original)?;
+            let mut variants = Vec::new();
+            if bucket_config.mode != ProcessingMode::Aot {
+                return Ok(variants);
+            }
+            for (name, preset) in bucket_config.presets.iter() {
+                let resized = Processor::resize(&image, *preset);
+                for format in bucket_config.formats.enabled() {
+                    let bytes = processor.encode(&bucket_name, &resized, format)?;
+                    variants.push((format!("{}.{}", name, format.extension()), bytes));
+                }
+            }
+            Ok(variants)
+        })
+        .await
+        .map_err(|e| Error::ImageProcessing(e.to_string()))??;
+
+        for (name, bytes) in variants {
+            Self::write_atomic(&dir.join(name), &bytes).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn get_variant(
+        &self,
+        bucket: &str,
+        image_id: &str,
+        preset: Option<&str>,
+        transform: Option<&Transform>,
+        format: Option<ImageFormat>,
+    ) -> Result<ImageFile> {
+        let bucket_config = self.processor.bucket(bucket)?;
+        let format = format
+            .filter(|format| bucket_config.formats.is_enabled(*format))
+            .unwrap_or(bucket_config.default_serving_format);
+        let mode = bucket_config.mode;
+        if let Some(preset) = preset {
+            self.processor.preset(bucket, preset)?;
+        }
+
+        let dir = self.image_dir(bucket, image_id)?;
+        let original = dir.join(ORIGINAL);
+        if !fs::try_exists(&original).await? {
+            return Err(Error::ImageNotFound(
+                bucket.to_string(),
+                image_id.to_string(),
+            ));
+        }
+
+        let name = match transform {
+            Some(transform) => format!("t_{}", transform.key()),
+            None => preset.unwrap_or(FULL_SIZE).to_string(),
+        };
+        let variant = dir.join(format!("{}.{}", name, format.extension()));
+        debug!("{:<12} - LOCAL getting file {:?}", "LOCAL", variant);
+
+        if mode != ProcessingMode::Realtime && fs::try_exists(&variant).await? {
+            let file = fs::File::open(&variant).await?;
+            let len = file.metadata().await?.len();
+            return Ok(ImageFile {
+                headers: Self::headers(format, len),
+                body: Box::pin(ReaderStream::new(file)),
+            });
+        }
+
+        let original = fs::read(original).await?;
+        let processor = self.processor.clone();
+        let (bucket_name, preset_name) = (bucket.to_string(), preset.map(|p| p.to_string()));
+        let transform = transform.cloned();
+        let bytes = tokio::task::spawn_blocking(move || {
+            processor.process(
+                &bucket_name,
+                &original,
+                preset_name.as_deref(),
+                transform.as_ref(),
+                format,
+            )
+        })
+        .await
+        .map_err(|e| Error::ImageProcessing(e.to_string()))??;
+
+        if mode != ProcessingMode::Realtime {
+            Self::write_atomic(&variant, &bytes).await?;
+        }
+
+        Ok(ImageFile {
+            headers: Self::headers(format, bytes.len() as u64),
+            body: Box::pin(tokio_stream::once(Ok(bytes))),
+        })
+    }
+
+    fn headers(format: ImageFormat, len: u64) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static(format.content_type()),
+        );
+        headers.insert(CONTENT_LENGTH, HeaderValue::from(len));
+        headers
+    }
+}
+
+#[async_trait]
+impl ImageStore for LocalStore {
+    async fn put(
+        &self,
+        bucket: &str,
+        _size: Option<u64>,
+        policy: ExifPolicy,
+        body: ByteStream,
+    ) -> Result<StoredImage> {
+        let image_id = Uuid::new_v4();
+        let dir = self.image_dir(bucket, &image_id.to_string())?;
+        fs::create_dir_all(&dir).await?;
+        debug!("{:<12} - LOCAL creating file {:?}", "LOCAL", dir);
+
+        let inspector = UploadInspector::default();
+        let result =
+            match Self::write_stream(&dir.join(ORIGINAL), inspector.wrap(body, policy)).await {
+                Ok(_) => self.process_original(bucket, &dir).await,
+                Err(e) => Err(e),
+            };
+
+        match result {
+            Ok(_) => Ok(StoredImage::new(image_id, &inspector)),
+            Err(e) => {
+                // don't leave partially written or undecodable originals behind
+                let _ = fs::remove_dir_all(&dir).await;
+                Err(e)
+            }
+        }
+    }
+
+    async fn get(
+        &self,
+        bucket: &str,
+        image_id: &str,
+        format: Option<ImageFormat>,
+    ) -> Result<ImageFile> {
+        let preset = self
+            .processor
+            .bucket(bucket)?
+            .default_serving_preset
+            .clone();
+        self.get_variant(bucket, image_id, preset.as_deref(), None, format)
+            .await
+    }
+
+    async fn get_resized(
+        &self,
+        bucket: &str,
+        image_id: &str,
+        preset: &str,
+        format: Option<ImageFormat>,
+    ) -> Result<ImageFile> {
+        self.get_variant(bucket, image_id, Some(preset), None, format)
+            .await
+    }
+
+    async fn get_transformed(
+        &self,
+        bucket: &str,
+        image_id: &str,
+        transform: &Transform,
+        format: ImageFormat,
+    ) -> Result<ImageFile> {
+        self.get_variant(bucket, image_id, None, Some(transform), Some(format))
+            .await
+    }
+
+    async fn delete(&self, bucket: &str, image_id: &str) -> Result<()> {
+        let dir = self.image_dir(bucket, image_id)?;
+        debug!("{:<12} - LOCAL deleting file {:?}", "LOCAL", dir);
+
+        match fs::remove_dir_all(&dir).await {
+            Ok(_) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(Error::ImageNotFound(
+                bucket.to_string(),
+                image_id.to_string(),
+            )),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn has_preset(&self, bucket: &str, preset: &str) -> bool {
+        self.processor.preset(bucket, preset).is_ok()
+    }
+}
@@ -1,16 +1,25 @@
-use axum::{body::StreamBody, extract::BodyStream, http::HeaderMap, response::IntoResponse};
+use async_trait::async_trait;
+use axum::response::IntoResponse;
 use derive_more::Display;
 use reqwest::{
-    header::{CONTENT_LENGTH, CONTENT_TYPE},
+    header::{HeaderMap, HeaderValue, CONTENT_LENGTH, CONTENT_TYPE},
     Body, Client, StatusCode, Url,
 };
 use serde::{Deserialize, Serialize};
+use tokio_stream::StreamExt;
 use tracing::debug;
 
-use crate::config;
+use crate::graphql::ExifPolicy;
 
 use super::error::{Error, Result};
+use super::processor::{ImageFormat, Processor, ProcessorConfig};
 use super::req_client;
+use super::store::{ByteStream, ImageFile, ImageStore, StoredImage, UploadInspector};
+use super::transform::Transform;
+
+const TRANSFORM_JPEG_QUALITY: u8 = 85;
+/// Lust size of uploaded image without resizing
+const ORIGINAL_PRESET: &str = "original";
 
 #[derive(Debug, Display, Serialize)]
 pub enum LustError {
@@ -70,72 +79,53 @@ impl IntoResponse for LustResponse {
     }
 }
 
-pub struct Lust;
+/// `config` is the same config Lust server runs with, used only to know its buckets and presets
+pub struct Lust {
+    client: Client,
+    url: String,
+    config: ProcessorConfig,
+}
 
 impl Lust {
-    pub fn build_post_url(bucket: &str, params: Option<Vec<(String, String)>>) -> Result<Url> {
-        let url = format!("{}/{}", &config().LUST_URL, bucket);
+    pub fn new(client: Client, url: String, config: ProcessorConfig) -> Self {
+        Self {
+            client,
+            url,
+            config,
+        }
+    }
+
+    pub fn build_post_url(
+        &self,
+        bucket: &str,
+        params: Option<Vec<(String, String)>>,
+    ) -> Result<Url> {
+        let url = format!("{}/{}", &self.url, bucket);
         req_client::build_url(&url, params)
     }
 
     pub fn build_get_url(
+        &self,
         bucket: &str,
         params: Option<Vec<(String, String)>>,
         image: &str,
     ) -> Result<Url> {
-        let url = format!("{}/{}/{}", &config().LUST_URL, bucket, image);
+        let url = format!("{}/{}/{}", &self.url, bucket, image);
         req_client::build_url(&url, params)
     }
 
-    pub async fn post_stream(
-        client: &Client,
-        bucket: &str,
-        size: u64,
-        file: BodyStream,
-    ) -> Result<LustResponse> {
-        let url = Self::build_post_url(&bucket, None)?;
-        debug!("{:<12} - LUST creating file", "LUST");
-        let body = Body::wrap_stream(file);
-        let res = client
-            .post(url)
-            .header(CONTENT_TYPE, "application/octet-stream")
-            .header(CONTENT_LENGTH, size.to_string())
-            .body(body)
-            .send()
-            .await
-            .map_err(|e| e.into());
-
-        match res {
-            Ok(res) => match res.status().into() {
-                LustResult::Success => res.json::<LustResponse>().await.map_err(|e| e.into()),
-                LustResult::Error(LustError::Undefined) => {
-                    debug!(
-                        "{:<12} - LUST undefined error: {} for bucket: {}",
-                        "LUST",
-                        res.status(),
-                        bucket
-                    );
-                    Err(LustError::Undefined.into())
-                }
-                LustResult::Error(e) => Err(e.into()),
-            },
-            Err(e) => Err(e),
-        }
+    fn format_param(format: Option<ImageFormat>) -> Option<(String, String)> {
+        format.map(|format| ("format".to_string(), format.extension().to_string()))
     }
 
-    pub async fn post_file(client: &Client, bucket: &str, file: Vec<u8>) -> Result<LustResponse> {
-        let url = Self::build_post_url(
-            &bucket,
-            Some(vec![("format".to_string(), "jpeg".to_string())]),
-        )?;
+    async fn post(&self, bucket: &str, url: Url, size: u64, body: Body) -> Result<LustResponse> {
         debug!("{:<12} - LUST creating file", "LUST");
-        let size = file.len();
-        println!("url: {}, size: {}", url, size);
-        let res = client
+        let res = self
+            .client
             .post(url)
             .header(CONTENT_TYPE, "application/octet-stream")
             .header(CONTENT_LENGTH, size.to_string())
-            .body(file)
+            .body(body)
             .send()
             .await
             .map_err(|e| e.into());
@@ -158,15 +148,16 @@ impl Lust {
         }
     }
 
-    pub async fn get_file(
-        client: &Client,
+    async fn get_file(
+        &self,
         bucket: &str,
         image_id: &str,
         params: Option<Vec<(String, String)>>,
-    ) -> Result<(impl IntoResponse, HeaderMap)> {
-        let url = Self::build_get_url(&bucket, params, &image_id)?;
+    ) -> Result<ImageFile> {
+        let url = self.build_get_url(bucket, params, image_id)?;
         debug!("{:<12} - LUST getting file - {}", "LUST", &url);
-        let res = client
+        let res = self
+            .client
             .get(url)
             .send()
             .await
@@ -175,8 +166,13 @@ impl Lust {
         match res.status() {
             StatusCode::OK => {
                 let headers = res.headers().clone();
-                let file_stream = StreamBody::new(res.bytes_stream());
-                Ok((file_stream, headers))
+                let body = res
+                    .bytes_stream()
+                    .map(|chunk| chunk.map_err(std::io::Error::other));
+                Ok(ImageFile {
+                    headers,
+                    body: Box::pin(body),
+                })
             }
             StatusCode::NOT_FOUND => {
                 Err(LustError::NotFound(bucket.to_string(), image_id.to_string()).into())
@@ -195,11 +191,115 @@ impl Lust {
             }
         }
     }
+}
 
-    pub async fn delete_file(client: &Client, bucket: &str, image_id: String) -> Result<()> {
-        let url = Self::build_get_url(&bucket, None, &image_id)?;
+impl LustResponse {
+    /// Lust checksum is not a content hash usable for deduplication,
+    /// so hash computed while uploading is kept instead
+    fn into_stored(self, inspector: &UploadInspector) -> Result<StoredImage> {
+        let image_id = self
+            .image_id
+            .parse()
+            .map_err(|_| Error::BadImageId(self.image_id.clone()))?;
+        Ok(StoredImage::new(image_id, inspector))
+    }
+}
+
+#[async_trait]
+impl ImageStore for Lust {
+    async fn put(
+        &self,
+        bucket: &str,
+        size: Option<u64>,
+        policy: ExifPolicy,
+        body: ByteStream,
+    ) -> Result<StoredImage> {
+        let url = self.build_post_url(bucket, None)?;
+        let inspector = UploadInspector::default();
+        let mut body = inspector.wrap(body, policy);
+        // stripping changes body length
+        match size.filter(|_| policy == ExifPolicy::Keep) {
+            Some(size) => self
+                .post(bucket, url, size, Body::wrap_stream(body))
+                .await?
+                .into_stored(&inspector),
+            None => {
+                // Lust requires Content-Length, so body of unknown size has to be buffered
+                let mut buffer = Vec::new();
+                while let Some(chunk) = body.next().await {
+                    buffer.extend_from_slice(&chunk?);
+                }
+                let size = buffer.len() as u64;
+                self.post(bucket, url, size, Body::from(buffer))
+                    .await?
+                    .into_stored(&inspector)
+            }
+        }
+    }
+
+    async fn get(
+        &self,
+        bucket: &str,
+        image_id: &str,
+        format: Option<ImageFormat>,
+    ) -> Result<ImageFile> {
+        let params = Self::format_param(format).map(|param| vec![param]);
+        self.get_file(bucket, image_id, params).await
+    }
+
+    async fn get_resized(
+        &self,
+        bucket: &str,
+        image_id: &str,
+        preset: &str,
+        format: Option<ImageFormat>,
+    ) -> Result<ImageFile> {
+        let mut params = vec![("size".to_string(), preset.to_string())];
+        params.extend(Self::format_param(format));
+        self.get_file(bucket, image_id, Some(params)).await
+    }
+
+    /// Lust has no transformations, original (not the default preset) is transformed in-process
+    /// and its caching is left to http caches
+    async fn get_transformed(
+        &self,
+        bucket: &str,
+        image_id: &str,
+        transform: &Transform,
+        format: ImageFormat,
+    ) -> Result<ImageFile> {
+        let params = vec![("size".to_string(), ORIGINAL_PRESET.to_string())];
+        let mut body = self.get_file(bucket, image_id, Some(params)).await?.body;
+        let mut buffer = Vec::new();
+        while let Some(chunk) = body.next().await {
+            buffer.extend_from_slice(&chunk?);
+        }
+
+        let transform = transform.clone();
+        let bytes = tokio::task::spawn_blocking(move || {
+            let image = transform.apply(Processor::decode(&buffer)?);
+            Processor::encode_with_quality(&image, format, TRANSFORM_JPEG_QUALITY)
+        })
+        .await
+        .map_err(|e| Error::ImageProcessing(e.to_string()))??;
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static(format.content_type()),
+        );
+        headers.insert(CONTENT_LENGTH, HeaderValue::from(bytes.len()));
+        Ok(ImageFile {
+            headers,
+            body: Box::pin(tokio_stream::once(Ok(bytes))),
+        })
+    }
+
+    async fn delete(&self, bucket: &str, image_id: &str) -> Result<()> {
+        let url = self.build_get_url(bucket, None, image_id)?;
         debug!("{:<12} - LUST deleting file", "LUST");
-        let res = client
+        let res = self
+            .client
             .delete(url)
             .send()
             .await
@@ -210,9 +310,11 @@ impl Lust {
                 debug!("{:<12} - LUST file deleted", "REDIRECT");
                 Ok(())
             }
-            StatusCode::NOT_FOUND => Err(LustError::NotFound(bucket.to_string(), image_id).into()),
+            StatusCode::NOT_FOUND => {
+                Err(LustError::NotFound(bucket.to_string(), image_id.to_string()).into())
+            }
             StatusCode::BAD_REQUEST => {
-                Err(LustError::BadRequest(bucket.to_string(), image_id).into())
+                Err(LustError::BadRequest(bucket.to_string(), image_id.to_string()).into())
             }
             _ => {
                 debug!(
@@ -225,4 +327,11 @@ impl Lust {
             }
         }
     }
+
+    fn has_preset(&self, bucket: &str, preset: &str) -> bool {
+        self.config
+            .buckets
+            .get(bucket)
+            .is_some_and(|bucket| bucket.presets.contains_key(preset))
+    }
 }
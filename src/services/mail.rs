@@ -0,0 +1,123 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use tokio::fs;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::config::config;
+
+use super::error::Result;
+
+pub type SharedMailTransport = Arc<dyn MailTransport>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MailKind {
+    Log,
+    File,
+}
+
+impl FromStr for MailKind {
+    type Err = ();
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "log" => Ok(MailKind::Log),
+            "file" => Ok(MailKind::File),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Mail {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+impl Mail {
+    /// link to reset password, token is appended to `PASSWORD_RESET_URL`
+    pub fn password_reset(to: &str, token: &str) -> Self {
+        let minutes = config().PASSWORD_RESET_DURATION / 60;
+        Self {
+            to: to.to_string(),
+            subject: "Reset your password".to_string(),
+            body: format!(
+                "Somebody asked to reset password of your account.\n\
+                 Open {}{token} within {minutes} minutes to set new password.\n\
+                 If it wasn't you, ignore this mail.\n",
+                config().PASSWORD_RESET_URL
+            ),
+        }
+    }
+
+    fn to_message(&self) -> String {
+        format!(
+            "From: {}\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\n\r\n{}",
+            config().MAIL_FROM,
+            self.to,
+            self.subject,
+            chrono::Utc::now().to_rfc2822(),
+            self.body
+        )
+    }
+}
+
+/// ### MailTransport
+/// Delivers mails of the app, selected with `MAIL_TRANSPORT` env variable.
+#[async_trait]
+pub trait MailTransport: Send + Sync {
+    async fn send(&self, mail: &Mail) -> Result<()>;
+}
+
+/// Only logs mails, for local development.
+pub struct LogTransport;
+
+#[async_trait]
+impl MailTransport for LogTransport {
+    async fn send(&self, mail: &Mail) -> Result<()> {
+        info!("{:<12} - {}", "MAIL", mail.to_message());
+        Ok(())
+    }
+}
+
+/// ### FileTransport
+/// Writes every mail as `{MAIL_DIR}/{timestamp}_{id}.eml`, for local testing.
+pub struct FileTransport {
+    dir: PathBuf,
+}
+
+impl FileTransport {
+    pub fn new(dir: &str) -> Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        Ok(Self {
+            dir: PathBuf::from(dir),
+        })
+    }
+}
+
+#[async_trait]
+impl MailTransport for FileTransport {
+    async fn send(&self, mail: &Mail) -> Result<()> {
+        let name = format!(
+            "{}_{}.eml",
+            chrono::Utc::now().format("%Y%m%d%H%M%S"),
+            Uuid::new_v4()
+        );
+        fs::write(self.dir.join(name), mail.to_message()).await?;
+        Ok(())
+    }
+}
+
+pub fn new_mail_transport() -> Result<SharedMailTransport> {
+    let config = config();
+    let transport: SharedMailTransport = match config.MAIL_TRANSPORT {
+        MailKind::Log => Arc::new(LogTransport),
+        MailKind::File => Arc::new(FileTransport::new(&config.MAIL_DIR)?),
+    };
+
+    Ok(transport)
+}
@@ -0,0 +1,153 @@
+use std::io::Cursor;
+
+use chrono::{NaiveDate, TimeZone, Utc};
+use exif::{Exif, In, Tag, Value};
+use image::ImageReader;
+
+use crate::graphql::ExifPolicy;
+
+/// Bytes kept from the beginning of an upload.\
+/// Enough for image headers and EXIF segment (which is limited to 64 KiB in JPEG).
+pub const HEAD_LEN: usize = 256 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GpsPosition {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub altitude: Option<f64>,
+}
+
+/// ### ImageMetadata
+/// Properties of uploaded original, everything except `byte_size`
+/// is best effort and left empty when it can't be read.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImageMetadata {
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub mime_type: Option<String>,
+    pub byte_size: u64,
+    /// EXIF `DateTimeOriginal`, camera local time is taken as UTC
+    pub captured_at: Option<chrono::DateTime<Utc>>,
+    pub camera_model: Option<String>,
+    /// EXIF orientation 1-8
+    pub orientation: Option<u32>,
+    pub gps: Option<GpsPosition>,
+}
+
+impl ImageMetadata {
+    /// reads metadata from first `HEAD_LEN` bytes of the original
+    pub fn extract(head: &[u8], byte_size: u64) -> Self {
+        let mut metadata = ImageMetadata {
+            byte_size,
+            mime_type: image::guess_format(head)
+                .ok()
+                .map(|format| format.to_mime_type().to_string()),
+            ..Default::default()
+        };
+
+        let dimensions = ImageReader::new(Cursor::new(head))
+            .with_guessed_format()
+            .ok()
+            .and_then(|reader| reader.into_dimensions().ok());
+        if let Some((width, height)) = dimensions {
+            metadata.width = Some(width);
+            metadata.height = Some(height);
+        }
+
+        if let Ok(exif) = exif::Reader::new().read_from_container(&mut Cursor::new(head)) {
+            metadata.captured_at = captured_at(&exif);
+            metadata.camera_model = ascii(&exif, Tag::Model);
+            metadata.orientation = exif
+                .get_field(Tag::Orientation, In::PRIMARY)
+                .and_then(|field| field.value.get_uint(0));
+            metadata.gps = gps(&exif);
+        }
+
+        metadata
+    }
+
+    /// clears fields which `policy` strips from original
+    pub fn strip(&mut self, policy: ExifPolicy) {
+        if policy != ExifPolicy::Keep {
+            self.gps = None;
+        }
+        if policy == ExifPolicy::StripAll {
+            self.captured_at = None;
+            self.camera_model = None;
+        }
+    }
+}
+
+fn ascii(exif: &Exif, tag: Tag) -> Option<String> {
+    match &exif.get_field(tag, In::PRIMARY)?.value {
+        Value::Ascii(values) => values
+            .first()
+            .map(|value| String::from_utf8_lossy(value).trim().to_string())
+            .filter(|value| !value.is_empty()),
+        _ => None,
+    }
+}
+
+fn captured_at(exif: &Exif) -> Option<chrono::DateTime<Utc>> {
+    let field = exif
+        .get_field(Tag::DateTimeOriginal, In::PRIMARY)
+        .or_else(|| exif.get_field(Tag::DateTime, In::PRIMARY))?;
+    let value = match &field.value {
+        Value::Ascii(values) => values.first()?,
+        _ => return None,
+    };
+    let dt = exif::DateTime::from_ascii(value).ok()?;
+
+    let naive = NaiveDate::from_ymd_opt(dt.year as i32, dt.month as u32, dt.day as u32)?
+        .and_hms_opt(dt.hour as u32, dt.minute as u32, dt.second as u32)?;
+    Some(Utc.from_utc_datetime(&naive))
+}
+
+fn gps(exif: &Exif) -> Option<GpsPosition> {
+    let latitude = coordinate(exif, Tag::GPSLatitude, Tag::GPSLatitudeRef, b'S')?;
+    let longitude = coordinate(exif, Tag::GPSLongitude, Tag::GPSLongitudeRef, b'W')?;
+    let altitude = exif
+        .get_field(Tag::GPSAltitude, In::PRIMARY)
+        .and_then(|field| match &field.value {
+            Value::Rational(values) => values.first().map(|value| value.to_f64()),
+            _ => None,
+        })
+        .map(|altitude| {
+            // altitude ref 1 means below sea level
+            let below = exif
+                .get_field(Tag::GPSAltitudeRef, In::PRIMARY)
+                .and_then(|field| field.value.get_uint(0))
+                == Some(1);
+            if below {
+                -altitude
+            } else {
+                altitude
+            }
+        });
+
+    Some(GpsPosition {
+        latitude,
+        longitude,
+        altitude,
+    })
+}
+
+/// degrees, minutes and seconds converted to signed decimal degrees
+fn coordinate(exif: &Exif, tag: Tag, ref_tag: Tag, negative_ref: u8) -> Option<f64> {
+    let degrees = match &exif.get_field(tag, In::PRIMARY)?.value {
+        Value::Rational(values) if values.len() >= 3 => {
+            values[0].to_f64() + values[1].to_f64() / 60.0 + values[2].to_f64() / 3600.0
+        }
+        _ => return None,
+    };
+
+    let negative = exif
+        .get_field(ref_tag, In::PRIMARY)
+        .map(|field| match &field.value {
+            Value::Ascii(values) => values.first().and_then(|v| v.first()) == Some(&negative_ref),
+            _ => false,
+        })
+        .unwrap_or(false);
+
+    Some(if negative { -degrees } else { degrees })
+}
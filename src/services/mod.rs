@@ -1,3 +1,12 @@
 pub mod error;
+pub mod local;
 pub mod lust;
+pub mod mail;
+pub mod metadata;
+pub mod placeholder;
+pub mod processor;
 pub mod req_client;
+pub mod store;
+pub mod strip;
+pub mod transform;
+pub mod validate;
\ No newline at end of file
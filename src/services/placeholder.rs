@@ -0,0 +1,69 @@
+use data_encoding::BASE64;
+use image::DynamicImage;
+use tokio_stream::StreamExt;
+use uuid::Uuid;
+
+use super::error::{Error, Result};
+use super::processor::{ImageFormat, Processor};
+use super::store::ImageStore;
+use super::transform::{Fit, Transform};
+
+/// Longer side of image placeholders are computed from
+const SAMPLE_SIZE: u32 = 32;
+const COMPONENTS_X: u32 = 4;
+const COMPONENTS_Y: u32 = 3;
+const PREVIEW_BLUR: f32 = 2.0;
+const PREVIEW_QUALITY: u8 = 40;
+
+/// ### Placeholder
+/// Stand-in for image which viewer may not see, e.g. in subscriber-only post.\
+/// `blurhash` is rendered by client, `preview` is heavily blurred low-res JPEG as data url,
+/// neither reveals more than colors and rough shapes of the original.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Placeholder {
+    pub blurhash: String,
+    pub preview: String,
+}
+
+impl Placeholder {
+    /// computes placeholder from downscaled stored original
+    pub async fn from_store(store: &dyn ImageStore, bucket: &str, image_id: &Uuid) -> Result<Self> {
+        let sample = Transform {
+            width: Some(SAMPLE_SIZE),
+            height: Some(SAMPLE_SIZE),
+            fit: Fit::Contain,
+            ..Default::default()
+        };
+        let mut body = store
+            .get_transformed(bucket, &image_id.to_string(), &sample, ImageFormat::Png)
+            .await?
+            .body;
+        let mut buffer = Vec::new();
+        while let Some(chunk) = body.next().await {
+            buffer.extend_from_slice(&chunk?);
+        }
+
+        tokio::task::spawn_blocking(move || Self::from_image(&Processor::decode(&buffer)?))
+            .await
+            .map_err(|e| Error::ImageProcessing(e.to_string()))?
+    }
+
+    pub fn from_image(image: &DynamicImage) -> Result<Self> {
+        let sample = image.thumbnail(SAMPLE_SIZE, SAMPLE_SIZE);
+        let preview = Processor::encode_with_quality(
+            &sample.blur(PREVIEW_BLUR),
+            ImageFormat::Jpeg,
+            PREVIEW_QUALITY,
+        )?;
+
+        let rgba = sample.to_rgba8();
+        let (width, height) = rgba.dimensions();
+        let blurhash = blurhash::encode(COMPONENTS_X, COMPONENTS_Y, width, height, &rgba)
+            .map_err(|e| Error::ImageProcessing(e.to_string()))?;
+
+        Ok(Self {
+            blurhash,
+            preview: format!("data:image/jpeg;base64,{}", BASE64.encode(&preview)),
+        })
+    }
+}
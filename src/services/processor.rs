@@ -0,0 +1,265 @@
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::str::FromStr;
+
+use bytes::Bytes;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::webp::WebPEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageReader};
+use serde::Deserialize;
+
+use super::error::{Error, Result};
+use super::transform::Transform;
+
+const DEFAULT_JPEG_QUALITY: u8 = 90;
+
+/// Output formats supported by processor, named as in Lust config.\
+/// AVIF can be served by Lust only, processor has no AVIF encoder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    Webp,
+    Gif,
+    Avif,
+}
+
+impl FromStr for ImageFormat {
+    type Err = ();
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "png" => Ok(ImageFormat::Png),
+            "jpeg" | "jpg" => Ok(ImageFormat::Jpeg),
+            "webp" => Ok(ImageFormat::Webp),
+            "gif" => Ok(ImageFormat::Gif),
+            "avif" => Ok(ImageFormat::Avif),
+            _ => Err(()),
+        }
+    }
+}
+
+impl ImageFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Jpeg => "jpeg",
+            ImageFormat::Webp => "webp",
+            ImageFormat::Gif => "gif",
+            ImageFormat::Avif => "avif",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ImageFormat::Png => "image/png",
+            ImageFormat::Jpeg => "image/jpeg",
+            ImageFormat::Webp => "image/webp",
+            ImageFormat::Gif => "image/gif",
+            ImageFormat::Avif => "image/avif",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProcessingMode {
+    /// presets are generated on first request and stored
+    #[default]
+    Jit,
+    /// presets are generated on upload
+    Aot,
+    /// presets are generated on every request and never stored
+    Realtime,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct JpegConfig {
+    pub quality: u8,
+}
+
+/// WebP is always encoded lossless, `quality` and `method` are kept only
+/// for compatibility with Lust config.
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebpConfig {
+    pub quality: Option<f32>,
+    pub method: Option<u8>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FormatsConfig {
+    #[serde(default)]
+    pub png: bool,
+    #[serde(default)]
+    pub jpeg: bool,
+    #[serde(default)]
+    pub webp: bool,
+    #[serde(default)]
+    pub gif: bool,
+    #[serde(default)]
+    pub avif: bool,
+    pub jpeg_config: Option<JpegConfig>,
+    pub webp_config: Option<WebpConfig>,
+}
+
+impl FormatsConfig {
+    pub fn enabled(&self) -> Vec<ImageFormat> {
+        [
+            (self.png, ImageFormat::Png),
+            (self.jpeg, ImageFormat::Jpeg),
+            (self.webp, ImageFormat::Webp),
+            (self.gif, ImageFormat::Gif),
+            (self.avif, ImageFormat::Avif),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, format)| enabled.then_some(format))
+        .collect()
+    }
+
+    pub fn is_enabled(&self, format: ImageFormat) -> bool {
+        self.enabled().contains(&format)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct PresetConfig {
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BucketConfig {
+    #[serde(default)]
+    pub mode: ProcessingMode,
+    pub formats: FormatsConfig,
+    pub default_serving_format: ImageFormat,
+    pub default_serving_preset: Option<String>,
+    #[serde(default)]
+    pub presets: HashMap<String, PresetConfig>,
+}
+
+/// ### ProcessorConfig
+/// Bucket and preset definitions in the same shape as Lust `config.yaml`,
+/// keys used only by Lust (backend, cache...) are ignored.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProcessorConfig {
+    pub buckets: HashMap<String, BucketConfig>,
+}
+
+impl ProcessorConfig {
+    pub fn from_file(path: &str) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| Error::ProcessorConfig(format!("{}: {}", path, e)))?;
+        serde_yaml::from_str(&content).map_err(|e| Error::ProcessorConfig(e.to_string()))
+    }
+}
+
+/// ### Processor
+/// In-process replacement for Lust image pipeline: decodes originals,
+/// resizes them to bucket presets and encodes them to configured formats.
+pub struct Processor {
+    config: ProcessorConfig,
+}
+
+impl Processor {
+    pub fn new(config: ProcessorConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn bucket(&self, bucket: &str) -> Result<&BucketConfig> {
+        self.config
+            .buckets
+            .get(bucket)
+            .ok_or_else(|| Error::ProcessorConfig(format!("bucket {} not configured", bucket)))
+    }
+
+    pub fn preset(&self, bucket: &str, preset: &str) -> Result<PresetConfig> {
+        self.bucket(bucket)?
+            .presets
+            .get(preset)
+            .copied()
+            .ok_or_else(|| Error::UnknownPreset(preset.to_string()))
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<DynamicImage> {
+        ImageReader::new(Cursor::new(bytes))
+            .with_guessed_format()
+            .map_err(|e| Error::ImageProcessing(e.to_string()))?
+            .decode()
+            .map_err(|e| Error::ImageProcessing(e.to_string()))
+    }
+
+    /// fits image into preset box keeping aspect ratio, never upscales
+    pub fn resize(image: &DynamicImage, preset: PresetConfig) -> DynamicImage {
+        if image.width() <= preset.width && image.height() <= preset.height {
+            return image.clone();
+        }
+        image.resize(preset.width, preset.height, FilterType::Lanczos3)
+    }
+
+    pub fn encode(&self, bucket: &str, image: &DynamicImage, format: ImageFormat) -> Result<Bytes> {
+        let formats = &self.bucket(bucket)?.formats;
+        let quality = formats
+            .jpeg_config
+            .as_ref()
+            .map_or(DEFAULT_JPEG_QUALITY, |c| c.quality);
+        Self::encode_with_quality(image, format, quality)
+    }
+
+    /// encodes image without bucket config, `quality` is used for JPEG only
+    pub fn encode_with_quality(
+        image: &DynamicImage,
+        format: ImageFormat,
+        quality: u8,
+    ) -> Result<Bytes> {
+        let mut buffer = Vec::new();
+
+        let result = match format {
+            ImageFormat::Jpeg => {
+                // jpeg has no alpha channel
+                let rgb = DynamicImage::ImageRgb8(image.to_rgb8());
+                rgb.write_with_encoder(JpegEncoder::new_with_quality(&mut buffer, quality))
+            }
+            ImageFormat::Webp => {
+                let rgba = DynamicImage::ImageRgba8(image.to_rgba8());
+                rgba.write_with_encoder(WebPEncoder::new_lossless(&mut buffer))
+            }
+            ImageFormat::Png => {
+                image.write_to(&mut Cursor::new(&mut buffer), image::ImageFormat::Png)
+            }
+            ImageFormat::Gif => {
+                let rgba = DynamicImage::ImageRgba8(image.to_rgba8());
+                rgba.write_to(&mut Cursor::new(&mut buffer), image::ImageFormat::Gif)
+            }
+            ImageFormat::Avif => {
+                return Err(Error::ImageProcessing(
+                    "AVIF encoding is not supported".to_string(),
+                ))
+            }
+        };
+
+        result.map_err(|e| Error::ImageProcessing(e.to_string()))?;
+        Ok(Bytes::from(buffer))
+    }
+
+    /// decodes original and encodes it in given preset (or original size) and format,
+    /// `transform` is applied instead of preset when given
+    pub fn process(
+        &self,
+        bucket: &str,
+        original: &[u8],
+        preset: Option<&str>,
+        transform: Option<&Transform>,
+        format: ImageFormat,
+    ) -> Result<Bytes> {
+        let image = Self::decode(original)?;
+        let image = match (transform, preset) {
+            (Some(transform), _) => transform.apply(image),
+            (None, Some(preset)) => Self::resize(&image, self.preset(bucket, preset)?),
+            (None, None) => image,
+        };
+        self.encode(bucket, &image, format)
+    }
+}
@@ -0,0 +1,164 @@
+use std::{
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use axum::http::HeaderMap;
+use bytes::Bytes;
+use data_encoding::HEXLOWER;
+use reqwest::Client;
+use sha2::{Digest, Sha256};
+use tokio_stream::{Stream, StreamExt};
+use uuid::Uuid;
+
+use crate::config::{config, StoreKind};
+use crate::graphql::ExifPolicy;
+
+use super::error::Result;
+use super::local::LocalStore;
+use super::lust::Lust;
+use super::metadata::{ImageMetadata, HEAD_LEN};
+use super::placeholder::Placeholder;
+use super::processor::{ImageFormat, Processor, ProcessorConfig};
+use super::strip::strip_metadata;
+use super::transform::Transform;
+
+pub type ByteStream = Pin<Box<dyn Stream<Item = std::io::Result<Bytes>> + Send + Sync>>;
+
+pub type SharedImageStore = Arc<dyn ImageStore>;
+
+/// Result of persisting a new original in the store.
+pub struct StoredImage {
+    pub image_id: Uuid,
+    /// hex encoded sha256 of stored bytes
+    pub hash: String,
+    /// stored size, differs from uploaded one when metadata was stripped
+    pub size: u64,
+    /// read from original as uploaded, before stripping
+    pub metadata: ImageMetadata,
+    /// computed after original is stored, `None` when it couldn't be
+    pub placeholder: Option<Placeholder>,
+}
+
+impl StoredImage {
+    pub fn new(image_id: Uuid, inspector: &UploadInspector) -> Self {
+        let state = inspector.0.lock().unwrap();
+        StoredImage {
+            image_id,
+            hash: HEXLOWER.encode(&state.hasher.clone().finalize()),
+            size: state.size,
+            metadata: ImageMetadata::extract(&state.head, state.size),
+            placeholder: None,
+        }
+    }
+}
+
+#[derive(Default)]
+struct InspectorState {
+    hasher: Sha256,
+    size: u64,
+    head: Vec<u8>,
+}
+
+/// ### UploadInspector
+/// Keeps beginning of uploaded body for metadata, strips metadata by EXIF policy
+/// and computes sha256 of what is left while it is streamed to the store,
+/// so the original doesn't have to be read again.
+#[derive(Clone, Default)]
+pub struct UploadInspector(Arc<Mutex<InspectorState>>);
+
+impl UploadInspector {
+    pub fn wrap(&self, body: ByteStream, policy: ExifPolicy) -> ByteStream {
+        let uploaded = self.0.clone();
+        let body: ByteStream = Box::pin(body.map(move |chunk| {
+            if let Ok(bytes) = &chunk {
+                let mut state = uploaded.lock().unwrap();
+                let missing = HEAD_LEN.saturating_sub(state.head.len()).min(bytes.len());
+                state.head.extend_from_slice(&bytes[..missing]);
+            }
+            chunk
+        }));
+
+        let stored = self.0.clone();
+        Box::pin(strip_metadata(body, policy).map(move |chunk| {
+            if let Ok(bytes) = &chunk {
+                let mut state = stored.lock().unwrap();
+                state.hasher.update(bytes);
+                state.size += bytes.len() as u64;
+            }
+            chunk
+        }))
+    }
+}
+
+/// Stored image ready to be streamed back to the client.
+pub struct ImageFile {
+    pub headers: HeaderMap,
+    pub body: ByteStream,
+}
+
+/// ### ImageStore
+/// Backend that keeps image originals and serves their size presets.\
+/// Implemented by the Lust proxy and by the local filesystem store,
+/// selected by `IMAGE_STORE` env variable.
+#[async_trait]
+pub trait ImageStore: Send + Sync {
+    /// `size` is body length when known upfront (e.g. from `Content-Length`),
+    /// metadata is stripped from body by `policy` before it's persisted
+    async fn put(
+        &self,
+        bucket: &str,
+        size: Option<u64>,
+        policy: ExifPolicy,
+        body: ByteStream,
+    ) -> Result<StoredImage>;
+
+    /// `format` overrides bucket default serving format,
+    /// bucket default is served when requested format is not enabled
+    async fn get(
+        &self,
+        bucket: &str,
+        image_id: &str,
+        format: Option<ImageFormat>,
+    ) -> Result<ImageFile>;
+
+    async fn get_resized(
+        &self,
+        bucket: &str,
+        image_id: &str,
+        preset: &str,
+        format: Option<ImageFormat>,
+    ) -> Result<ImageFile>;
+
+    /// original processed by checked `transform`
+    async fn get_transformed(
+        &self,
+        bucket: &str,
+        image_id: &str,
+        transform: &Transform,
+        format: ImageFormat,
+    ) -> Result<ImageFile>;
+
+    async fn delete(&self, bucket: &str, image_id: &str) -> Result<()>;
+
+    /// whether size `preset` is configured for `bucket`
+    fn has_preset(&self, bucket: &str, preset: &str) -> bool;
+}
+
+pub fn new_image_store() -> Result<SharedImageStore> {
+    let config = config();
+    let store: SharedImageStore = match config.IMAGE_STORE {
+        StoreKind::Lust => Arc::new(Lust::new(
+            Client::new(),
+            config.LUST_URL.clone().unwrap_or_default(),
+            ProcessorConfig::from_file(&config.PROCESSOR_CONFIG)?,
+        )),
+        StoreKind::Local => {
+            let processor = Processor::new(ProcessorConfig::from_file(&config.PROCESSOR_CONFIG)?);
+            Arc::new(LocalStore::new(&config.LOCAL_STORE_DIR, processor)?)
+        }
+    };
+
+    Ok(store)
+}
@@ -0,0 +1,813 @@
+use std::io::Cursor;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use bytes::Bytes;
+use exif::experimental::Writer;
+use exif::{In, Tag};
+use tokio_stream::Stream;
+
+use crate::graphql::ExifPolicy;
+
+use super::store::ByteStream;
+
+const JPEG_SOI: [u8; 2] = [0xFF, 0xD8];
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+const EXIF_HEADER: &[u8] = b"Exif\0\0";
+const XMP_HEADER: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+const XMP_EXTENSION_HEADER: &[u8] = b"http://ns.adobe.com/xmp/extension/\0";
+const PNG_XMP_KEYWORD: &[u8] = b"XML:com.adobe.xmp";
+// ImageMagick and exiftool keep hex-encoded metadata in text chunks
+const PNG_RAW_PROFILES: [&[u8]; 3] = [
+    b"Raw profile type exif",
+    b"Raw profile type APP1",
+    b"Raw profile type xmp",
+];
+const MPF_HEADER: &[u8] = b"MPF\0";
+// VP8X flags
+const WEBP_EXIF_FLAG: u8 = 0x08;
+const WEBP_XMP_FLAG: u8 = 0x04;
+
+/// Removes EXIF and XMP from image body according to `policy`.\
+/// JPEG and PNG are rewritten while streaming, only metadata segments are buffered.
+/// Anything after the end of the first JPEG image (e.g. MPF secondary images
+/// with their own EXIF) is dropped.
+/// WebP keeps metadata after image data, so whole body is buffered.
+/// Other formats are passed unchanged.
+pub fn strip_metadata(body: ByteStream, policy: ExifPolicy) -> ByteStream {
+    match policy {
+        ExifPolicy::Keep => body,
+        _ => Box::pin(StripStream {
+            body,
+            stripper: Stripper::new(policy),
+            finished: false,
+        }),
+    }
+}
+
+struct StripStream {
+    body: ByteStream,
+    stripper: Stripper,
+    finished: bool,
+}
+
+impl Stream for StripStream {
+    type Item = std::io::Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        loop {
+            if this.finished {
+                return Poll::Ready(None);
+            }
+
+            let output = match ready!(this.body.as_mut().poll_next(cx)) {
+                Some(Ok(chunk)) => this.stripper.push(&chunk),
+                Some(Err(e)) => {
+                    this.finished = true;
+                    return Poll::Ready(Some(Err(e)));
+                }
+                None => {
+                    this.finished = true;
+                    this.stripper.finish()
+                }
+            };
+
+            if !output.is_empty() {
+                return Poll::Ready(Some(Ok(output.into())));
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Unknown,
+    Jpeg,
+    // entropy-coded data after start of scan
+    JpegScan,
+    // data after end of image
+    JpegTrailer,
+    Png,
+    Webp,
+    Other,
+}
+
+/// Incremental metadata remover, `pending` holds bytes of not yet complete segment.
+struct Stripper {
+    policy: ExifPolicy,
+    format: Format,
+    pending: Vec<u8>,
+    // bytes of PNG chunk which are passed without inspection
+    skip: usize,
+}
+
+impl Stripper {
+    fn new(policy: ExifPolicy) -> Self {
+        Self {
+            policy,
+            format: Format::Unknown,
+            pending: Vec::new(),
+            skip: 0,
+        }
+    }
+
+    fn push(&mut self, chunk: &[u8]) -> Vec<u8> {
+        if self.format == Format::Other {
+            return chunk.to_vec();
+        }
+
+        let mut output = Vec::new();
+        self.pending.extend_from_slice(chunk);
+        self.process(&mut output, false);
+        output
+    }
+
+    fn finish(&mut self) -> Vec<u8> {
+        let mut output = Vec::new();
+        self.process(&mut output, true);
+        output.append(&mut self.pending);
+        output
+    }
+
+    fn process(&mut self, output: &mut Vec<u8>, eof: bool) {
+        loop {
+            let progress = match self.format {
+                Format::Unknown => self.detect(output, eof),
+                Format::Jpeg => self.jpeg_segment(output),
+                Format::JpegScan => self.jpeg_scan(output),
+                Format::JpegTrailer => {
+                    self.pending.clear();
+                    false
+                }
+                Format::Png => self.png_chunk(output),
+                Format::Webp => {
+                    if eof {
+                        *output = strip_webp(&self.pending, self.policy);
+                        self.pending.clear();
+                        self.format = Format::Other;
+                    }
+                    false
+                }
+                Format::Other => {
+                    output.append(&mut self.pending);
+                    false
+                }
+            };
+
+            if !progress {
+                return;
+            }
+        }
+    }
+
+    fn detect(&mut self, output: &mut Vec<u8>, eof: bool) -> bool {
+        if self.pending.len() < 12 && !eof {
+            return false;
+        }
+
+        if self.pending.starts_with(&JPEG_SOI) {
+            self.format = Format::Jpeg;
+            output.extend(self.pending.drain(..JPEG_SOI.len()));
+        } else if self.pending.starts_with(&PNG_SIGNATURE) {
+            self.format = Format::Png;
+            output.extend(self.pending.drain(..PNG_SIGNATURE.len()));
+        } else if self.pending.len() >= 12
+            && &self.pending[..4] == b"RIFF"
+            && &self.pending[8..12] == b"WEBP"
+        {
+            self.format = Format::Webp;
+        } else {
+            self.format = Format::Other;
+        }
+        true
+    }
+
+    /// handles single segment between start and end of image
+    fn jpeg_segment(&mut self, output: &mut Vec<u8>) -> bool {
+        if self.pending.len() < 2 {
+            return false;
+        }
+        if self.pending[0] != 0xFF {
+            self.format = Format::Other;
+            return true;
+        }
+
+        let marker = self.pending[1];
+        match marker {
+            // fill byte
+            0xFF => {
+                output.push(0xFF);
+                self.pending.remove(0);
+                return true;
+            }
+            // markers without length
+            0x01 | 0xD0..=0xD7 => {
+                output.extend(self.pending.drain(..2));
+                return true;
+            }
+            // end of image
+            0xD9 => {
+                output.extend(self.pending.drain(..2));
+                self.format = Format::JpegTrailer;
+                return true;
+            }
+            0xD8 => {
+                self.format = Format::Other;
+                return true;
+            }
+            _ => (),
+        }
+
+        if self.pending.len() < 4 {
+            return false;
+        }
+        let length = u16::from_be_bytes([self.pending[2], self.pending[3]]) as usize;
+        // length includes its own two bytes, anything shorter is not a segment we can parse
+        if length < 2 {
+            self.format = Format::Other;
+            return true;
+        }
+        if self.pending.len() < 2 + length {
+            return false;
+        }
+
+        let segment: Vec<u8> = self.pending.drain(..2 + length).collect();
+        let payload = &segment[4..];
+
+        let rewritten = match marker {
+            // APP1
+            0xE1 if payload.starts_with(EXIF_HEADER) => {
+                rewrite_exif(&payload[EXIF_HEADER.len()..], self.policy)
+                    .map(|tiff| [EXIF_HEADER, &tiff].concat())
+            }
+            0xE1 if payload.starts_with(XMP_HEADER)
+                || payload.starts_with(XMP_EXTENSION_HEADER) =>
+            {
+                None
+            }
+            // APP2 MPF, its offsets point to dropped secondary images
+            0xE2 if payload.starts_with(MPF_HEADER) => None,
+            // APP13 (IPTC) and comment
+            0xED | 0xFE if self.policy == ExifPolicy::StripAll => None,
+            _ => Some(payload.to_vec()),
+        };
+
+        if let Some(payload) = rewritten {
+            output.extend_from_slice(&[0xFF, marker]);
+            output.extend_from_slice(&((payload.len() + 2) as u16).to_be_bytes());
+            output.extend_from_slice(&payload);
+        }
+        // start of scan
+        if marker == 0xDA {
+            self.format = Format::JpegScan;
+        }
+        true
+    }
+
+    /// passes entropy-coded data up to the next marker, stuffed bytes and restarts included
+    fn jpeg_scan(&mut self, output: &mut Vec<u8>) -> bool {
+        let marker_at = self
+            .pending
+            .windows(2)
+            .position(|bytes| bytes[0] == 0xFF && !matches!(bytes[1], 0x00 | 0xD0..=0xD7 | 0xFF));
+        match marker_at {
+            Some(at) => {
+                output.extend(self.pending.drain(..at));
+                self.format = Format::Jpeg;
+                true
+            }
+            None => {
+                // last byte might be the start of a marker
+                let passed = match self.pending.last() {
+                    Some(0xFF) => self.pending.len() - 1,
+                    _ => self.pending.len(),
+                };
+                output.extend(self.pending.drain(..passed));
+                false
+            }
+        }
+    }
+
+    /// inspects metadata chunks, other chunks (image data) are passed as they come
+    fn png_chunk(&mut self, output: &mut Vec<u8>) -> bool {
+        if self.skip > 0 {
+            let passed = self.skip.min(self.pending.len());
+            output.extend(self.pending.drain(..passed));
+            self.skip -= passed;
+            return self.skip == 0;
+        }
+        if self.pending.len() < 8 {
+            return false;
+        }
+
+        let length = u32::from_be_bytes([
+            self.pending[0],
+            self.pending[1],
+            self.pending[2],
+            self.pending[3],
+        ]) as usize;
+        let kind: [u8; 4] = [
+            self.pending[4],
+            self.pending[5],
+            self.pending[6],
+            self.pending[7],
+        ];
+
+        if !matches!(&kind, b"eXIf" | b"iTXt" | b"tEXt" | b"zTXt") {
+            // length, type, data and crc
+            self.skip = length + 12;
+            return true;
+        }
+        if self.pending.len() < length + 12 {
+            return false;
+        }
+
+        let chunk: Vec<u8> = self.pending.drain(..length + 12).collect();
+        let data = &chunk[8..8 + length];
+
+        if &kind == b"eXIf" {
+            if let Some(tiff) = rewrite_exif(data, self.policy) {
+                output.extend_from_slice(&(tiff.len() as u32).to_be_bytes());
+                output.extend_from_slice(&kind);
+                output.extend_from_slice(&tiff);
+                let mut crc = crc32fast::Hasher::new();
+                crc.update(&kind);
+                crc.update(&tiff);
+                output.extend_from_slice(&crc.finalize().to_be_bytes());
+            }
+        } else if !self.drops_text(data) {
+            output.extend_from_slice(&chunk);
+        }
+        true
+    }
+
+    /// text chunks carrying EXIF or XMP are dropped, with `StripAll` every text chunk is
+    fn drops_text(&self, data: &[u8]) -> bool {
+        let keyword = data.split(|byte| *byte == 0).next().unwrap_or_default();
+        self.policy == ExifPolicy::StripAll
+            || keyword == PNG_XMP_KEYWORD
+            || PNG_RAW_PROFILES
+                .iter()
+                .any(|profile| keyword.eq_ignore_ascii_case(profile))
+    }
+}
+
+/// rewrites TIFF structure of EXIF, returns `None` when nothing is left to keep
+fn rewrite_exif(tiff: &[u8], policy: ExifPolicy) -> Option<Vec<u8>> {
+    // unreadable EXIF is dropped, it might still contain location
+    let (fields, little_endian) = exif::parse_exif(tiff).ok()?;
+
+    // thumbnail IFD is dropped, maker notes can't be moved because of their internal offsets
+    let mut writer = Writer::new();
+    let mut kept = false;
+    for field in fields.iter().filter(|field| field.ifd_num == In::PRIMARY) {
+        let keep = match policy {
+            ExifPolicy::Keep => true,
+            ExifPolicy::StripGps => {
+                field.tag.context() != exif::Context::Gps && field.tag != Tag::MakerNote
+            }
+            ExifPolicy::StripAll => field.tag == Tag::Orientation,
+        };
+        if keep {
+            writer.push_field(field);
+            kept = true;
+        }
+    }
+    if !kept {
+        return None;
+    }
+
+    let mut buffer = Cursor::new(Vec::new());
+    writer.write(&mut buffer, little_endian).ok()?;
+    Some(buffer.into_inner())
+}
+
+fn strip_webp(data: &[u8], policy: ExifPolicy) -> Vec<u8> {
+    let mut output = data[..12].to_vec();
+    let mut flags_at = None;
+    let mut has_exif = false;
+    let mut position = 12;
+
+    while position + 8 <= data.len() {
+        let kind = &data[position..position + 4];
+        let size = u32::from_le_bytes([
+            data[position + 4],
+            data[position + 5],
+            data[position + 6],
+            data[position + 7],
+        ]) as usize;
+        // chunks are padded to even size
+        let end = (position + 8 + size + (size & 1)).min(data.len());
+        let payload = &data[position + 8..(position + 8 + size).min(data.len())];
+
+        match kind {
+            b"EXIF" => {
+                let tiff = payload.strip_prefix(EXIF_HEADER).unwrap_or(payload);
+                if let Some(tiff) = rewrite_exif(tiff, policy) {
+                    output.extend_from_slice(b"EXIF");
+                    output.extend_from_slice(&(tiff.len() as u32).to_le_bytes());
+                    output.extend_from_slice(&tiff);
+                    if tiff.len() % 2 == 1 {
+                        output.push(0);
+                    }
+                    has_exif = true;
+                }
+            }
+            b"XMP " => (),
+            b"VP8X" => {
+                flags_at = Some(output.len() + 8);
+                output.extend_from_slice(&data[position..end]);
+            }
+            _ => output.extend_from_slice(&data[position..end]),
+        }
+        position = end;
+    }
+    output.extend_from_slice(&data[position..]);
+
+    if let Some(flags_at) = flags_at.filter(|at| *at < output.len()) {
+        output[flags_at] &= !WEBP_XMP_FLAG;
+        if !has_exif {
+            output[flags_at] &= !WEBP_EXIF_FLAG;
+        }
+    }
+    let riff_size = (output.len() - 8) as u32;
+    output[4..8].copy_from_slice(&riff_size.to_le_bytes());
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use exif::{Field, Rational, Value};
+    use tokio_stream::StreamExt;
+
+    use super::*;
+
+    /// TIFF with orientation and GPS latitude
+    fn tiff() -> Vec<u8> {
+        let orientation = Field {
+            tag: Tag::Orientation,
+            ifd_num: In::PRIMARY,
+            value: Value::Short(vec![6]),
+        };
+        let latitude = Field {
+            tag: Tag::GPSLatitude,
+            ifd_num: In::PRIMARY,
+            value: Value::Rational(vec![
+                Rational::from((50, 1)),
+                Rational::from((3, 1)),
+                Rational::from((0, 1)),
+            ]),
+        };
+        let mut writer = Writer::new();
+        writer.push_field(&orientation);
+        writer.push_field(&latitude);
+        let mut buffer = Cursor::new(Vec::new());
+        writer.write(&mut buffer, false).unwrap();
+        buffer.into_inner()
+    }
+
+    fn tags(tiff: &[u8]) -> Vec<Tag> {
+        let (fields, _) = exif::parse_exif(tiff).unwrap();
+        fields.iter().map(|field| field.tag).collect()
+    }
+
+    fn strip(input: &[u8], policy: ExifPolicy, chunk_size: usize) -> Vec<u8> {
+        let mut stripper = Stripper::new(policy);
+        let mut output = Vec::new();
+        for chunk in input.chunks(chunk_size) {
+            output.extend(stripper.push(chunk));
+        }
+        output.extend(stripper.finish());
+        output
+    }
+
+    fn jpeg_segment(marker: u8, payload: &[u8]) -> Vec<u8> {
+        let mut segment = vec![0xFF, marker];
+        segment.extend_from_slice(&((payload.len() + 2) as u16).to_be_bytes());
+        segment.extend_from_slice(payload);
+        segment
+    }
+
+    fn jpeg() -> Vec<u8> {
+        [
+            JPEG_SOI.to_vec(),
+            jpeg_segment(0xE1, &[EXIF_HEADER, &tiff()].concat()),
+            jpeg_segment(0xE1, &[XMP_HEADER, b"<x:xmpmeta/>"].concat()),
+            jpeg_segment(0xFE, b"comment"),
+            jpeg_segment(0xDA, b"scan"),
+            b"image data \xFF\x00 stuffed \xFF\xD3 restart".to_vec(),
+            vec![0xFF, 0xD9],
+        ]
+        .concat()
+    }
+
+    /// segments of `jpeg` up to start of scan
+    fn jpeg_segments(jpeg: &[u8]) -> Vec<(u8, Vec<u8>)> {
+        let mut segments = Vec::new();
+        let mut position = 2;
+        while jpeg[position + 1] != 0xDA {
+            let length = u16::from_be_bytes([jpeg[position + 2], jpeg[position + 3]]) as usize;
+            segments.push((
+                jpeg[position + 1],
+                jpeg[position + 4..position + 2 + length].to_vec(),
+            ));
+            position += 2 + length;
+        }
+        segments
+    }
+
+    fn png_chunk(kind: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut crc = crc32fast::Hasher::new();
+        crc.update(kind);
+        crc.update(data);
+        [
+            (data.len() as u32).to_be_bytes().as_slice(),
+            kind,
+            data,
+            &crc.finalize().to_be_bytes(),
+        ]
+        .concat()
+    }
+
+    /// chunks of `png` as (type, data, crc is valid)
+    fn png_chunks(png: &[u8]) -> Vec<([u8; 4], Vec<u8>, bool)> {
+        let mut chunks = Vec::new();
+        let mut position = PNG_SIGNATURE.len();
+        while position < png.len() {
+            let length = u32::from_be_bytes(png[position..position + 4].try_into().unwrap());
+            let kind: [u8; 4] = png[position + 4..position + 8].try_into().unwrap();
+            let data = png[position + 8..position + 8 + length as usize].to_vec();
+            let crc = &png[position + 8 + length as usize..position + 12 + length as usize];
+            let valid = png_chunk(&kind, &data)[8 + length as usize..] == *crc;
+            chunks.push((kind, data, valid));
+            position += 12 + length as usize;
+        }
+        chunks
+    }
+
+    fn webp_chunk(kind: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut chunk = [kind, (data.len() as u32).to_le_bytes().as_slice(), data].concat();
+        if data.len() % 2 == 1 {
+            chunk.push(0);
+        }
+        chunk
+    }
+
+    fn webp(chunks: &[Vec<u8>]) -> Vec<u8> {
+        let body = chunks.concat();
+        [
+            b"RIFF".as_slice(),
+            &((body.len() + 4) as u32).to_le_bytes(),
+            b"WEBP",
+            &body,
+        ]
+        .concat()
+    }
+
+    #[tokio::test]
+    async fn stream_strips_chunked_body() {
+        let chunks: Vec<std::io::Result<Bytes>> = jpeg()
+            .chunks(5)
+            .map(|chunk| Ok(Bytes::copy_from_slice(chunk)))
+            .collect();
+        let body: ByteStream = Box::pin(tokio_stream::iter(chunks));
+
+        let output = strip_metadata(body, ExifPolicy::StripAll)
+            .map(|chunk| chunk.unwrap().to_vec())
+            .collect::<Vec<_>>()
+            .await
+            .concat();
+        assert_eq!(output, strip(&jpeg(), ExifPolicy::StripAll, 4096));
+    }
+
+    #[test]
+    fn jpeg_strip_gps_removes_location_and_xmp() {
+        assert_eq!(tags(&tiff()), vec![Tag::Orientation, Tag::GPSLatitude]);
+        for chunk_size in [1, 7, 4096] {
+            let output = strip(&jpeg(), ExifPolicy::StripGps, chunk_size);
+            let segments = jpeg_segments(&output);
+
+            assert_eq!(segments.len(), 2);
+            let (marker, exif) = &segments[0];
+            assert_eq!(*marker, 0xE1);
+            assert!(exif.starts_with(EXIF_HEADER));
+            assert_eq!(tags(&exif[EXIF_HEADER.len()..]), vec![Tag::Orientation]);
+            assert_eq!(segments[1], (0xFE, b"comment".to_vec()));
+            assert!(output.ends_with(b"image data \xFF\x00 stuffed \xFF\xD3 restart\xFF\xD9"));
+        }
+    }
+
+    #[test]
+    fn jpeg_strip_all_removes_comment() {
+        let output = strip(&jpeg(), ExifPolicy::StripAll, 4096);
+        let segments = jpeg_segments(&output);
+
+        assert_eq!(segments.len(), 1);
+        assert_eq!(
+            tags(&segments[0].1[EXIF_HEADER.len()..]),
+            vec![Tag::Orientation]
+        );
+    }
+
+    #[test]
+    fn jpeg_segment_with_invalid_length_is_passed_unchanged() {
+        for length in [0u8, 1] {
+            let input = [
+                JPEG_SOI.as_slice(),
+                &[0xFF, 0xE1, 0x00, length],
+                b"rest of the file",
+            ]
+            .concat();
+            for chunk_size in [1, 4096] {
+                assert_eq!(strip(&input, ExifPolicy::StripAll, chunk_size), input);
+            }
+        }
+    }
+
+    #[test]
+    fn truncated_jpeg_segment_is_passed_unchanged() {
+        let input = [JPEG_SOI.as_slice(), &[0xFF, 0xE1, 0x01, 0x00], b"Exif\0\0"].concat();
+        assert_eq!(strip(&input, ExifPolicy::StripAll, 3), input);
+    }
+
+    #[test]
+    fn jpeg_drops_mpf_secondary_images() {
+        let first = [
+            JPEG_SOI.to_vec(),
+            jpeg_segment(0xE2, &[MPF_HEADER, b"offsets"].concat()),
+            jpeg_segment(0xDA, b"scan"),
+            b"first".to_vec(),
+            vec![0xFF, 0xD9],
+        ]
+        .concat();
+        let second = [
+            JPEG_SOI.to_vec(),
+            jpeg_segment(0xE1, &[EXIF_HEADER, &tiff()].concat()),
+            jpeg_segment(0xDA, b"scan"),
+            b"second".to_vec(),
+            vec![0xFF, 0xD9],
+        ]
+        .concat();
+        let input = [first, second].concat();
+
+        for chunk_size in [1, 4096] {
+            let output = strip(&input, ExifPolicy::StripGps, chunk_size);
+            let expected = [
+                JPEG_SOI.to_vec(),
+                jpeg_segment(0xDA, b"scan"),
+                b"first".to_vec(),
+                vec![0xFF, 0xD9],
+            ]
+            .concat();
+            assert_eq!(output, expected);
+        }
+    }
+
+    #[test]
+    fn jpeg_strips_exif_between_scans() {
+        let input = [
+            JPEG_SOI.to_vec(),
+            jpeg_segment(0xDA, b"scan 1"),
+            b"coarse \xFF\x00".to_vec(),
+            jpeg_segment(0xE1, &[EXIF_HEADER, &tiff()].concat()),
+            jpeg_segment(0xC4, b"huffman"),
+            jpeg_segment(0xDA, b"scan 2"),
+            b"fine".to_vec(),
+            vec![0xFF, 0xD9],
+        ]
+        .concat();
+
+        for chunk_size in [1, 3, 4096] {
+            let output = strip(&input, ExifPolicy::StripAll, chunk_size);
+            let orientation = [
+                EXIF_HEADER,
+                &rewrite_exif(&tiff(), ExifPolicy::StripAll).unwrap(),
+            ]
+            .concat();
+            let expected = [
+                JPEG_SOI.to_vec(),
+                jpeg_segment(0xDA, b"scan 1"),
+                b"coarse \xFF\x00".to_vec(),
+                jpeg_segment(0xE1, &orientation),
+                jpeg_segment(0xC4, b"huffman"),
+                jpeg_segment(0xDA, b"scan 2"),
+                b"fine".to_vec(),
+                vec![0xFF, 0xD9],
+            ]
+            .concat();
+            assert_eq!(output, expected);
+        }
+    }
+
+    #[test]
+    fn png_raw_profiles_are_removed() {
+        let comment = png_chunk(b"tEXt", b"Comment\0hello");
+        let input = [
+            PNG_SIGNATURE.to_vec(),
+            png_chunk(b"IHDR", &[0; 13]),
+            png_chunk(
+                b"tEXt",
+                b"Raw profile type exif\0\nexif\n  20\n4578696600004d4d",
+            ),
+            png_chunk(b"zTXt", b"Raw profile type APP1\0\0compressed"),
+            png_chunk(b"iTXt", b"Raw profile type xmp\0\0\0\0\0<x:xmpmeta/>"),
+            comment.clone(),
+            png_chunk(b"IEND", b""),
+        ]
+        .concat();
+
+        let output = strip(&input, ExifPolicy::StripGps, 4096);
+        let kinds: Vec<[u8; 4]> = png_chunks(&output)
+            .into_iter()
+            .map(|(kind, _, _)| kind)
+            .collect();
+        assert_eq!(kinds, [*b"IHDR", *b"tEXt", *b"IEND"]);
+        assert!(output
+            .windows(comment.len())
+            .any(|window| window == comment));
+
+        let output = strip(&input, ExifPolicy::StripAll, 4096);
+        let kinds: Vec<[u8; 4]> = png_chunks(&output)
+            .into_iter()
+            .map(|(kind, _, _)| kind)
+            .collect();
+        assert_eq!(kinds, [*b"IHDR", *b"IEND"]);
+    }
+
+    #[test]
+    fn png_exif_is_rewritten_and_xmp_removed() {
+        let ihdr = png_chunk(b"IHDR", &[0; 13]);
+        let text = png_chunk(b"iTXt", b"Comment\0\0\0\0\0hello");
+        let input = [
+            PNG_SIGNATURE.to_vec(),
+            ihdr.clone(),
+            png_chunk(b"eXIf", &tiff()),
+            png_chunk(
+                b"iTXt",
+                &[PNG_XMP_KEYWORD, b"\0\0\0\0\0<x:xmpmeta/>"].concat(),
+            ),
+            text.clone(),
+            png_chunk(b"IDAT", b"data"),
+            png_chunk(b"IEND", b""),
+        ]
+        .concat();
+
+        for chunk_size in [1, 4096] {
+            let output = strip(&input, ExifPolicy::StripGps, chunk_size);
+            assert!(output.starts_with(&PNG_SIGNATURE));
+
+            let chunks = png_chunks(&output);
+            let kinds: Vec<&[u8; 4]> = chunks.iter().map(|(kind, _, _)| kind).collect();
+            assert_eq!(kinds, [b"IHDR", b"eXIf", b"iTXt", b"IDAT", b"IEND"]);
+            assert!(chunks.iter().all(|(_, _, valid)| *valid));
+            assert_eq!(tags(&chunks[1].1), vec![Tag::Orientation]);
+            assert_eq!(png_chunk(b"iTXt", &chunks[2].1), text);
+        }
+    }
+
+    #[test]
+    fn webp_drops_xmp_and_rewrites_flags_and_size() {
+        let vp8x = webp_chunk(
+            b"VP8X",
+            &[WEBP_EXIF_FLAG | WEBP_XMP_FLAG, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+        );
+        let vp8 = webp_chunk(b"VP8 ", b"odd");
+        let input = webp(&[
+            vp8x,
+            vp8.clone(),
+            webp_chunk(b"EXIF", &tiff()),
+            webp_chunk(b"XMP ", b"<x:xmpmeta/>"),
+        ]);
+
+        let output = strip(&input, ExifPolicy::StripGps, 5);
+
+        assert_eq!(&output[..4], b"RIFF");
+        assert_eq!(
+            u32::from_le_bytes(output[4..8].try_into().unwrap()) as usize,
+            output.len() - 8
+        );
+        assert_eq!(output[20], WEBP_EXIF_FLAG);
+        assert_eq!(&output[30..30 + vp8.len()], vp8.as_slice());
+
+        let exif = &output[30 + vp8.len()..];
+        assert_eq!(&exif[..4], b"EXIF");
+        let size = u32::from_le_bytes(exif[4..8].try_into().unwrap()) as usize;
+        assert_eq!(tags(&exif[8..8 + size]), vec![Tag::Orientation]);
+        assert_eq!(exif.len(), 8 + size + size % 2);
+    }
+
+    #[test]
+    fn webp_without_kept_exif_clears_exif_flag() {
+        let vp8x = webp_chunk(b"VP8X", &[WEBP_EXIF_FLAG, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        let vp8 = webp_chunk(b"VP8 ", b"data");
+        let input = webp(&[vp8x.clone(), vp8.clone(), webp_chunk(b"EXIF", b"broken")]);
+
+        let output = strip(&input, ExifPolicy::StripAll, 4096);
+
+        let mut expected_vp8x = vp8x;
+        expected_vp8x[8] = 0;
+        assert_eq!(output, webp(&[expected_vp8x, vp8]));
+    }
+}
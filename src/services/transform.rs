@@ -0,0 +1,474 @@
+use std::str::FromStr;
+
+use image::imageops::FilterType;
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+
+use crate::config::config;
+
+/// Operations which can be enabled by `TRANSFORM_OPERATIONS`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Operation {
+    Resize,
+    Crop,
+    Rotate,
+    Blur,
+}
+
+impl FromStr for Operation {
+    type Err = ();
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "resize" => Ok(Operation::Resize),
+            "crop" => Ok(Operation::Crop),
+            "rotate" => Ok(Operation::Rotate),
+            "blur" => Ok(Operation::Blur),
+            _ => Err(()),
+        }
+    }
+}
+
+/// How image is resized when both width and height are given
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Fit {
+    /// fits into the box keeping aspect ratio
+    #[default]
+    Contain,
+    /// covers the box keeping aspect ratio, overflow is cropped
+    Cover,
+    /// stretches to the box
+    Fill,
+}
+
+impl Fit {
+    fn name(&self) -> &'static str {
+        match self {
+            Fit::Contain => "contain",
+            Fit::Cover => "cover",
+            Fit::Fill => "fill",
+        }
+    }
+}
+
+/// Aspect ratio `width:height` of the largest rectangle centered in the image,
+/// it's relative to the image, so it's the same for the original and its downscaled copy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Crop {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Crop {
+    /// `(x, y, width, height)` of cropped rectangle, at least 1x1
+    fn rectangle(&self, image_width: u32, image_height: u32) -> (u32, u32, u32, u32) {
+        let (image_w, image_h) = (image_width as u64, image_height as u64);
+        let (ratio_w, ratio_h) = (self.width as u64, self.height as u64);
+        let (width, height) = match image_w * ratio_h >= image_h * ratio_w {
+            // image is wider than the ratio
+            true => (image_h * ratio_w / ratio_h, image_h),
+            false => (image_w, image_w * ratio_h / ratio_w),
+        };
+        let (width, height) = (width.max(1) as u32, height.max(1) as u32);
+        (
+            (image_width - width) / 2,
+            (image_height - height) / 2,
+            width,
+            height,
+        )
+    }
+}
+
+impl FromStr for Crop {
+    type Err = ();
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let (width, height) = s.split_once(':').ok_or(())?;
+        let width = width.trim().parse::<u32>().map_err(|_| ())?;
+        let height = height.trim().parse::<u32>().map_err(|_| ())?;
+        match width > 0 && height > 0 {
+            true => Ok(Crop { width, height }),
+            false => Err(()),
+        }
+    }
+}
+
+impl std::fmt::Display for Crop {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.width, self.height)
+    }
+}
+
+impl Serialize for Crop {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Reason why requested transformation is not served.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum TransformRejection {
+    OperationNotAllowed { operation: Operation },
+    SizeNotAllowed { size: u32, allowed: Vec<u32> },
+    BlurNotAllowed { sigma: u32, allowed: Vec<u32> },
+    CropNotAllowed { crop: Crop, allowed: Vec<Crop> },
+    RotationNotAllowed { degrees: u32 },
+}
+
+/// Allow-list of transformations, every distinct transformation is processed and cached separately,
+/// so clients can't request unbounded number of variants.
+#[derive(Debug, Clone)]
+pub struct TransformRules {
+    pub operations: Vec<Operation>,
+    pub sizes: Vec<u32>,
+    pub blur: Vec<u32>,
+    pub crops: Vec<Crop>,
+}
+
+impl TransformRules {
+    pub fn from_config() -> Self {
+        let config = config();
+        Self {
+            operations: config.TRANSFORM_OPERATIONS.clone(),
+            sizes: config.TRANSFORM_SIZES.clone(),
+            blur: config.TRANSFORM_BLUR.clone(),
+            crops: config.TRANSFORM_CROPS.clone(),
+        }
+    }
+}
+
+/// ### Transform
+/// Operations applied to the original in fixed order: crop, rotate, resize, blur.\
+/// Same transformation always has the same `key`, whatever order of query parameters.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Transform {
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub fit: Fit,
+    pub crop: Option<Crop>,
+    /// clockwise, 90, 180 or 270
+    pub rotate: Option<u32>,
+    /// gaussian blur sigma
+    pub blur: Option<u32>,
+}
+
+impl Transform {
+    pub fn is_empty(&self) -> bool {
+        self.width.is_none()
+            && self.height.is_none()
+            && self.crop.is_none()
+            && self.rotate.is_none()
+            && self.blur.is_none()
+    }
+
+    pub fn check(&self, rules: &TransformRules) -> Result<(), TransformRejection> {
+        let allow = |operation: Operation| match rules.operations.contains(&operation) {
+            true => Ok(()),
+            false => Err(TransformRejection::OperationNotAllowed { operation }),
+        };
+
+        if self.width.is_some() || self.height.is_some() {
+            allow(Operation::Resize)?;
+        }
+        for size in [self.width, self.height].into_iter().flatten() {
+            if !rules.sizes.contains(&size) {
+                return Err(TransformRejection::SizeNotAllowed {
+                    size,
+                    allowed: rules.sizes.clone(),
+                });
+            }
+        }
+
+        if let Some(crop) = self.crop {
+            allow(Operation::Crop)?;
+            if !rules.crops.contains(&crop) {
+                return Err(TransformRejection::CropNotAllowed {
+                    crop,
+                    allowed: rules.crops.clone(),
+                });
+            }
+        }
+
+        if let Some(degrees) = self.rotate {
+            allow(Operation::Rotate)?;
+            if ![90, 180, 270].contains(&degrees) {
+                return Err(TransformRejection::RotationNotAllowed { degrees });
+            }
+        }
+
+        if let Some(sigma) = self.blur {
+            allow(Operation::Blur)?;
+            if !rules.blur.contains(&sigma) {
+                return Err(TransformRejection::BlurNotAllowed {
+                    sigma,
+                    allowed: rules.blur.clone(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// canonical name of transformation, usable in file names
+    pub fn key(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(crop) = self.crop {
+            parts.push(format!("c{}x{}", crop.width, crop.height));
+        }
+        if let Some(degrees) = self.rotate {
+            parts.push(format!("r{}", degrees));
+        }
+        if let Some(width) = self.width {
+            parts.push(format!("w{}", width));
+        }
+        if let Some(height) = self.height {
+            parts.push(format!("h{}", height));
+        }
+        // fit matters only when box is given
+        if self.width.is_some() && self.height.is_some() {
+            parts.push(self.fit.name().to_string());
+        }
+        if let Some(sigma) = self.blur {
+            parts.push(format!("b{}", sigma));
+        }
+        parts.join("_")
+    }
+
+    /// resized image is never upscaled, except `fill` which stretches to the box
+    pub fn apply(&self, mut image: DynamicImage) -> DynamicImage {
+        if let Some(crop) = self.crop {
+            let (x, y, width, height) = crop.rectangle(image.width(), image.height());
+            image = image.crop_imm(x, y, width, height);
+        }
+
+        image = match self.rotate {
+            Some(90) => image.rotate90(),
+            Some(180) => image.rotate180(),
+            Some(270) => image.rotate270(),
+            _ => image,
+        };
+
+        image = match (self.width, self.height) {
+            (Some(width), Some(height)) => match self.fit {
+                Fit::Contain if image.width() <= width && image.height() <= height => image,
+                Fit::Contain => image.resize(width, height, FilterType::Lanczos3),
+                Fit::Cover => image.resize_to_fill(width, height, FilterType::Lanczos3),
+                Fit::Fill => image.resize_exact(width, height, FilterType::Lanczos3),
+            },
+            (Some(width), None) if image.width() > width => {
+                image.resize(width, u32::MAX, FilterType::Lanczos3)
+            }
+            (None, Some(height)) if image.height() > height => {
+                image.resize(u32::MAX, height, FilterType::Lanczos3)
+            }
+            _ => image,
+        };
+
+        match self.blur {
+            Some(sigma) => image.blur(sigma as f32),
+            None => image,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::RgbImage;
+
+    use super::*;
+
+    fn rules() -> TransformRules {
+        TransformRules {
+            operations: vec![
+                Operation::Resize,
+                Operation::Crop,
+                Operation::Rotate,
+                Operation::Blur,
+            ],
+            sizes: vec![100, 200],
+            blur: vec![5],
+            crops: vec![
+                Crop {
+                    width: 1,
+                    height: 1,
+                },
+                Crop {
+                    width: 16,
+                    height: 9,
+                },
+            ],
+        }
+    }
+
+    fn image(width: u32, height: u32) -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::new(width, height))
+    }
+
+    #[test]
+    fn crop_parses_aspect_ratio() {
+        assert_eq!(
+            "16: 9".parse::<Crop>(),
+            Ok(Crop {
+                width: 16,
+                height: 9
+            })
+        );
+        assert!("0:9".parse::<Crop>().is_err());
+        assert!("16,9".parse::<Crop>().is_err());
+        assert!("a:9".parse::<Crop>().is_err());
+    }
+
+    #[test]
+    fn key_is_in_fixed_order() {
+        let transform = Transform {
+            width: Some(100),
+            height: Some(200),
+            fit: Fit::Cover,
+            crop: Some(Crop {
+                width: 16,
+                height: 9,
+            }),
+            rotate: Some(90),
+            blur: Some(5),
+        };
+        assert_eq!(transform.key(), "c16x9_r90_w100_h200_cover_b5");
+    }
+
+    #[test]
+    fn key_skips_fit_without_box() {
+        let transform = Transform {
+            width: Some(100),
+            fit: Fit::Cover,
+            ..Default::default()
+        };
+        assert_eq!(transform.key(), "w100");
+    }
+
+    #[test]
+    fn check_allows_listed_transform() {
+        let transform = Transform {
+            width: Some(100),
+            crop: Some(Crop {
+                width: 1,
+                height: 1,
+            }),
+            rotate: Some(270),
+            blur: Some(5),
+            ..Default::default()
+        };
+        assert_eq!(transform.check(&rules()), Ok(()));
+    }
+
+    #[test]
+    fn check_rejects_disabled_operation() {
+        let rules = TransformRules {
+            operations: vec![Operation::Resize],
+            ..rules()
+        };
+        let transform = Transform {
+            rotate: Some(90),
+            ..Default::default()
+        };
+        assert_eq!(
+            transform.check(&rules),
+            Err(TransformRejection::OperationNotAllowed {
+                operation: Operation::Rotate
+            })
+        );
+    }
+
+    #[test]
+    fn check_rejects_values_off_the_list() {
+        let transform = Transform {
+            height: Some(150),
+            ..Default::default()
+        };
+        assert_eq!(
+            transform.check(&rules()),
+            Err(TransformRejection::SizeNotAllowed {
+                size: 150,
+                allowed: vec![100, 200]
+            })
+        );
+
+        let transform = Transform {
+            blur: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(
+            transform.check(&rules()),
+            Err(TransformRejection::BlurNotAllowed {
+                sigma: 2,
+                allowed: vec![5]
+            })
+        );
+
+        let transform = Transform {
+            rotate: Some(45),
+            ..Default::default()
+        };
+        assert_eq!(
+            transform.check(&rules()),
+            Err(TransformRejection::RotationNotAllowed { degrees: 45 })
+        );
+    }
+
+    #[test]
+    fn check_rejects_crop_off_the_list() {
+        let crop = Crop {
+            width: 7,
+            height: 5,
+        };
+        let transform = Transform {
+            crop: Some(crop),
+            ..Default::default()
+        };
+        assert_eq!(
+            transform.check(&rules()),
+            Err(TransformRejection::CropNotAllowed {
+                crop,
+                allowed: rules().crops
+            })
+        );
+    }
+
+    #[test]
+    fn apply_crops_and_resizes() {
+        let transform = Transform {
+            width: Some(10),
+            crop: Some(Crop {
+                width: 1,
+                height: 1,
+            }),
+            rotate: Some(90),
+            ..Default::default()
+        };
+        let image = transform.apply(image(100, 50));
+        assert_eq!((image.width(), image.height()), (10, 10));
+    }
+
+    #[test]
+    fn apply_doesnt_upscale() {
+        let transform = Transform {
+            width: Some(200),
+            height: Some(200),
+            ..Default::default()
+        };
+        let image = transform.apply(image(100, 50));
+        assert_eq!((image.width(), image.height()), (100, 50));
+    }
+
+    #[test]
+    fn crop_rectangle_is_centered() {
+        let crop = Crop {
+            width: 16,
+            height: 9,
+        };
+        assert_eq!(crop.rectangle(1600, 1600), (0, 350, 1600, 900));
+        assert_eq!(crop.rectangle(3200, 900), (800, 0, 1600, 900));
+        assert_eq!(crop.rectangle(1, 1), (0, 0, 1, 1));
+        assert_eq!(crop.rectangle(2, 100), (0, 49, 2, 1));
+    }
+}
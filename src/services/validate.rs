@@ -0,0 +1,178 @@
+use std::io::Cursor;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{ready, Context, Poll};
+
+use bytes::Bytes;
+use image::ImageReader;
+use serde::Serialize;
+use tokio_stream::Stream;
+
+use crate::model::sys_config::UploadRules;
+
+use super::metadata::HEAD_LEN;
+use super::store::ByteStream;
+
+/// Reason why uploaded original was not accepted.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum Rejection {
+    UnsupportedType {
+        mime_type: Option<String>,
+    },
+    DimensionsTooLarge {
+        width: u32,
+        height: u32,
+        max_width: u32,
+        max_height: u32,
+    },
+    UnreadableImage,
+    TooLarge {
+        max_size: u64,
+    },
+}
+
+/// ### UploadValidator
+/// Checks upload against `UploadRules` while it is streamed to the store.\
+/// Beginning of the body is held back until its type is sniffed from magic bytes
+/// and dimensions are read from image header, so nothing is stored before the checks pass.
+/// Rejected body ends with error, reason is kept for the caller.
+#[derive(Clone)]
+pub struct UploadValidator {
+    rules: Arc<UploadRules>,
+    rejection: Arc<Mutex<Option<Rejection>>>,
+}
+
+impl UploadValidator {
+    pub fn new(rules: UploadRules) -> Self {
+        Self {
+            rules: Arc::new(rules),
+            rejection: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// checks size known before upload (e.g. from `Content-Length`)
+    pub fn check_size(&self, size: u64) -> Result<(), Rejection> {
+        if size > self.rules.max_size {
+            return Err(Rejection::TooLarge {
+                max_size: self.rules.max_size,
+            });
+        }
+        Ok(())
+    }
+
+    /// sniffs type and reads dimensions from first `HEAD_LEN` bytes
+    pub fn check_head(&self, head: &[u8]) -> Result<(), Rejection> {
+        let mime_type = image::guess_format(head)
+            .ok()
+            .map(|format| format.to_mime_type().to_string());
+        match &mime_type {
+            Some(mime_type) if self.rules.mime_types.contains(mime_type) => (),
+            _ => return Err(Rejection::UnsupportedType { mime_type }),
+        }
+
+        // image which header can't be read is not decoded later either
+        let (width, height) = ImageReader::new(Cursor::new(head))
+            .with_guessed_format()
+            .ok()
+            .and_then(|reader| reader.into_dimensions().ok())
+            .ok_or(Rejection::UnreadableImage)?;
+        if width > self.rules.max_width || height > self.rules.max_height {
+            return Err(Rejection::DimensionsTooLarge {
+                width,
+                height,
+                max_width: self.rules.max_width,
+                max_height: self.rules.max_height,
+            });
+        }
+        Ok(())
+    }
+
+    pub fn wrap(&self, body: ByteStream) -> ByteStream {
+        Box::pin(ValidateStream {
+            body,
+            validator: self.clone(),
+            head: Vec::new(),
+            checked: false,
+            size: 0,
+            finished: false,
+        })
+    }
+
+    /// reason of rejection when wrapped body failed validation
+    pub fn rejection(&self) -> Option<Rejection> {
+        self.rejection.lock().unwrap().clone()
+    }
+
+    fn reject(&self, rejection: Rejection) -> std::io::Error {
+        let error = std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Upload rejected: {:?}", rejection),
+        );
+        *self.rejection.lock().unwrap() = Some(rejection);
+        error
+    }
+}
+
+struct ValidateStream {
+    body: ByteStream,
+    validator: UploadValidator,
+    head: Vec<u8>,
+    checked: bool,
+    size: u64,
+    finished: bool,
+}
+
+impl ValidateStream {
+    /// releases held back beginning once it passes the checks
+    fn release_head(&mut self) -> Option<std::io::Result<Bytes>> {
+        self.checked = true;
+        if let Err(rejection) = self.validator.check_head(&self.head) {
+            self.finished = true;
+            return Some(Err(self.validator.reject(rejection)));
+        }
+        let head = std::mem::take(&mut self.head);
+        (!head.is_empty()).then(|| Ok(head.into()))
+    }
+}
+
+impl Stream for ValidateStream {
+    type Item = std::io::Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        loop {
+            if this.finished {
+                return Poll::Ready(None);
+            }
+
+            match ready!(this.body.as_mut().poll_next(cx)) {
+                Some(Ok(chunk)) => {
+                    this.size += chunk.len() as u64;
+                    if let Err(rejection) = this.validator.check_size(this.size) {
+                        this.finished = true;
+                        return Poll::Ready(Some(Err(this.validator.reject(rejection))));
+                    }
+                    if this.checked {
+                        return Poll::Ready(Some(Ok(chunk)));
+                    }
+                    this.head.extend_from_slice(&chunk);
+                    if this.head.len() >= HEAD_LEN {
+                        return Poll::Ready(this.release_head());
+                    }
+                }
+                Some(Err(e)) => {
+                    this.finished = true;
+                    return Poll::Ready(Some(Err(e)));
+                }
+                None => {
+                    let head = match this.checked {
+                        true => None,
+                        false => this.release_head(),
+                    };
+                    this.finished = true;
+                    return Poll::Ready(head);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,263 @@
+use std::ops::Bound;
+use std::time::{Duration, SystemTime};
+
+use axum::headers::{
+    AcceptRanges, CacheControl, ContentLength, ContentRange, ETag, HeaderMapExt, IfModifiedSince,
+    IfNoneMatch, IfRange, LastModified, Range,
+};
+use axum::http::{HeaderMap, StatusCode};
+use tokio_stream::StreamExt;
+
+use crate::services::processor::ImageFormat;
+use crate::services::store::ByteStream;
+
+/// Images are stored under immutable paths, so they can be cached for long
+pub const IMAGE_MAX_AGE: Duration = Duration::from_secs(60 * 60 * 24);
+
+/// ### Validators
+/// Conditional request validators of stored image.\
+/// ETag is strong, it's content hash of the original with variant (size preset or transformation key)
+/// and served format.
+pub struct Validators {
+    pub etag: Option<ETag>,
+    pub last_modified: Option<LastModified>,
+}
+
+impl Validators {
+    pub fn new(
+        hash: Option<&str>,
+        variant: Option<&str>,
+        format: ImageFormat,
+        created_at: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Self {
+        let etag = hash.and_then(|hash| {
+            let tag = match variant {
+                Some(variant) => format!("\"{}-{}-{}\"", hash, variant, format.extension()),
+                None => format!("\"{}-{}\"", hash, format.extension()),
+            };
+            tag.parse::<ETag>().ok()
+        });
+        let last_modified =
+            created_at.map(|created_at| LastModified::from(SystemTime::from(created_at)));
+
+        Self {
+            etag,
+            last_modified,
+        }
+    }
+
+    /// `If-None-Match` takes precedence over `If-Modified-Since`
+    pub fn is_not_modified(&self, request: &HeaderMap) -> bool {
+        if let Some(if_none_match) = request.typed_get::<IfNoneMatch>() {
+            return match &self.etag {
+                Some(etag) => !if_none_match.precondition_passes(etag),
+                None => false,
+            };
+        }
+        match (request.typed_get::<IfModifiedSince>(), &self.last_modified) {
+            (Some(if_modified_since), Some(last_modified)) => {
+                !if_modified_since.is_modified(SystemTime::from(*last_modified))
+            }
+            _ => false,
+        }
+    }
+
+    pub fn insert_into(&self, headers: &mut HeaderMap) {
+        if let Some(etag) = &self.etag {
+            headers.typed_insert(etag.clone());
+        }
+        if let Some(last_modified) = self.last_modified {
+            headers.typed_insert(last_modified);
+        }
+    }
+}
+
+/// `public` is cacheable by shared caches, other images only by the browser
+pub fn cache_control(public: bool, max_age: Duration) -> CacheControl {
+    let cache_control = CacheControl::new().with_max_age(max_age);
+    match public {
+        true => cache_control.with_public(),
+        false => cache_control.with_private(),
+    }
+}
+
+/// Applies single byte `Range` of request to the body of `length` bytes.\
+/// Multiple ranges, stale `If-Range` and body of unknown length are answered with whole body.
+pub fn apply_range(
+    request: &HeaderMap,
+    validators: &Validators,
+    headers: &mut HeaderMap,
+    body: ByteStream,
+) -> (StatusCode, ByteStream) {
+    headers.typed_insert(AcceptRanges::bytes());
+
+    let length = match headers.typed_get::<ContentLength>() {
+        Some(ContentLength(length)) => length,
+        None => return (StatusCode::OK, body),
+    };
+    let range = match request.typed_get::<Range>() {
+        Some(range) => range,
+        None => return (StatusCode::OK, body),
+    };
+    if let Some(if_range) = request.typed_get::<IfRange>() {
+        if if_range.is_modified(validators.etag.as_ref(), validators.last_modified.as_ref()) {
+            return (StatusCode::OK, body);
+        }
+    }
+
+    let mut ranges = range.iter();
+    let (start, end) = match (ranges.next(), ranges.next()) {
+        (Some(bounds), None) => match satisfiable(bounds, length) {
+            Some(range) => range,
+            None => {
+                headers.typed_insert(ContentRange::unsatisfied_bytes(length));
+                headers.typed_insert(ContentLength(0));
+                return (
+                    StatusCode::RANGE_NOT_SATISFIABLE,
+                    Box::pin(tokio_stream::empty()),
+                );
+            }
+        },
+        _ => return (StatusCode::OK, body),
+    };
+
+    match ContentRange::bytes(start..=end, length) {
+        Ok(content_range) => headers.typed_insert(content_range),
+        Err(_) => return (StatusCode::OK, body),
+    }
+    headers.typed_insert(ContentLength(end - start + 1));
+
+    (StatusCode::PARTIAL_CONTENT, slice(body, start, end))
+}
+
+/// inclusive byte range within body of `length` bytes
+fn satisfiable(bounds: (Bound<u64>, Bound<u64>), length: u64) -> Option<(u64, u64)> {
+    if length == 0 {
+        return None;
+    }
+    let (start, end) = match bounds {
+        (Bound::Included(start), Bound::Included(end)) => (start, end.min(length - 1)),
+        (Bound::Included(start), Bound::Unbounded) => (start, length - 1),
+        // suffix range, last `n` bytes
+        (Bound::Unbounded, Bound::Included(n)) if n > 0 => (length.saturating_sub(n), length - 1),
+        _ => return None,
+    };
+    (start <= end).then_some((start, end))
+}
+
+/// skips body bytes before `start` and stops reading it after `end`
+fn slice(body: ByteStream, start: u64, end: u64) -> ByteStream {
+    let mut position: u64 = 0;
+    Box::pin(
+        body.map(move |chunk| {
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(e) => return Some(Err(e)),
+            };
+            let chunk_start = position;
+            position += chunk.len() as u64;
+            if chunk_start > end {
+                return None;
+            }
+
+            let from = start.saturating_sub(chunk_start).min(chunk.len() as u64) as usize;
+            let to = (end + 1 - chunk_start).min(chunk.len() as u64) as usize;
+            Some(Ok(chunk.slice(from..to.max(from))))
+        })
+        .take_while(|chunk| chunk.is_some())
+        .filter_map(|chunk| chunk)
+        .filter(|chunk| !matches!(chunk, Ok(bytes) if bytes.is_empty())),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use super::*;
+
+    async fn collect(body: ByteStream) -> Vec<u8> {
+        body.map(|chunk| chunk.unwrap().to_vec())
+            .collect::<Vec<_>>()
+            .await
+            .concat()
+    }
+
+    fn chunks(chunks: &[&'static [u8]]) -> ByteStream {
+        let chunks: Vec<std::io::Result<Bytes>> = chunks
+            .iter()
+            .map(|chunk| Ok(Bytes::from_static(chunk)))
+            .collect();
+        Box::pin(tokio_stream::iter(chunks))
+    }
+
+    #[test]
+    fn satisfiable_closed_range() {
+        let range = satisfiable((Bound::Included(0), Bound::Included(99)), 1000);
+        assert_eq!(range, Some((0, 99)));
+    }
+
+    #[test]
+    fn satisfiable_end_past_length_is_clamped() {
+        let range = satisfiable((Bound::Included(900), Bound::Included(5000)), 1000);
+        assert_eq!(range, Some((900, 999)));
+    }
+
+    #[test]
+    fn satisfiable_open_range() {
+        let range = satisfiable((Bound::Included(10), Bound::Unbounded), 1000);
+        assert_eq!(range, Some((10, 999)));
+    }
+
+    #[test]
+    fn satisfiable_suffix_range() {
+        let range = satisfiable((Bound::Unbounded, Bound::Included(100)), 1000);
+        assert_eq!(range, Some((900, 999)));
+
+        // suffix longer than body is whole body
+        let range = satisfiable((Bound::Unbounded, Bound::Included(5000)), 1000);
+        assert_eq!(range, Some((0, 999)));
+
+        let range = satisfiable((Bound::Unbounded, Bound::Included(0)), 1000);
+        assert_eq!(range, None);
+    }
+
+    #[test]
+    fn satisfiable_start_past_length() {
+        let range = satisfiable((Bound::Included(1000), Bound::Unbounded), 1000);
+        assert_eq!(range, None);
+
+        let range = satisfiable((Bound::Included(1000), Bound::Included(1200)), 1000);
+        assert_eq!(range, None);
+    }
+
+    #[test]
+    fn satisfiable_zero_length() {
+        assert_eq!(satisfiable((Bound::Included(0), Bound::Unbounded), 0), None);
+        assert_eq!(satisfiable((Bound::Unbounded, Bound::Included(1)), 0), None);
+    }
+
+    #[tokio::test]
+    async fn slice_within_single_chunk() {
+        let body = slice(chunks(&[b"0123456789"]), 2, 5);
+        assert_eq!(collect(body).await, b"2345");
+    }
+
+    #[tokio::test]
+    async fn slice_across_chunks() {
+        let body = slice(chunks(&[b"0123", b"4567", b"89"]), 3, 8);
+        assert_eq!(collect(body).await, b"345678");
+    }
+
+    #[tokio::test]
+    async fn slice_stops_after_end() {
+        let body = slice(chunks(&[b"0123", b"4567", b"89"]), 0, 3);
+        assert_eq!(collect(body).await, b"0123");
+    }
+
+    #[tokio::test]
+    async fn slice_last_byte() {
+        let body = slice(chunks(&[b"0123", b"4567", b"89"]), 9, 9);
+        assert_eq!(collect(body).await, b"9");
+    }
+}
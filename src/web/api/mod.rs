@@ -2,35 +2,51 @@ use axum::{
     extract::DefaultBodyLimit,
     middleware,
     response::IntoResponse,
-    routing::{get, post},
+    routing::{get, patch, post},
     Router,
 };
-use reqwest::Client;
 
+mod http_cache;
+mod negotiate;
 mod routes_image;
+mod routes_upload;
 
 use crate::model::ModelManager;
+use crate::services::store::SharedImageStore;
 
-use self::routes_image::{get_image, post_image, post_images};
+use self::routes_image::{get_image, get_signed_image, post_image, post_images};
+use self::routes_upload::{create_upload, delete_upload, head_upload, patch_upload};
 
-use super::middleware::mw_auth::mw_ctx_require;
+use super::middleware::mw_auth::{mw_ctx_require, mw_images_scope};
 
 #[derive(Clone)]
 pub struct ApiState {
-    pub reqwest_client: Client,
+    pub store: SharedImageStore,
     pub mm: ModelManager,
 }
 
-pub fn routes(mm: ModelManager) -> Router {
-    let reqwest_client = Client::new();
+pub fn routes(mm: ModelManager, store: SharedImageStore) -> Router {
     Router::new()
         .route("/test", get(test))
         .route("/image/:image_id", get(get_image))
         .route("/image", post(post_image))
-        // .route("/images", post(post_images))
+        .route("/images", post(post_images))
+        .route("/uploads", post(create_upload))
+        .route(
+            "/uploads/:upload_id",
+            patch(patch_upload).head(head_upload).delete(delete_upload),
+        )
+        .layer(middleware::from_fn(mw_images_scope))
         .layer(middleware::from_fn_with_state(mm.clone(), mw_ctx_require))
         .layer(DefaultBodyLimit::max(1024 * 1024 * 10))
-        .with_state(ApiState { reqwest_client, mm })
+        .with_state(ApiState { store, mm })
+}
+
+/// Routes which don't require logged user, requests are authorized by signed url
+pub fn public_routes(mm: ModelManager, store: SharedImageStore) -> Router {
+    Router::new()
+        .route("/image/:image_id", get(get_signed_image))
+        .with_state(ApiState { store, mm })
 }
 
 async fn test() -> impl IntoResponse {
@@ -0,0 +1,144 @@
+use axum::http::header::ACCEPT;
+use axum::http::HeaderMap;
+
+use crate::services::processor::ImageFormat;
+
+/// Format which can be served to any client, even when it isn't accepted
+const FALLBACK: ImageFormat = ImageFormat::Jpeg;
+
+/// Picks format with the highest `q` in `Accept` header among `formats` (server preference order),
+/// ties are won by the earlier format.\
+/// AVIF and WebP are picked only when listed explicitly, older clients send wildcards
+/// (`image/*`, `*/*`) without being able to decode them. JPEG is served when nothing matches.
+pub fn negotiate_format(request: &HeaderMap, formats: &[ImageFormat]) -> ImageFormat {
+    let accept = request
+        .get_all(ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(parse_media_range)
+        .collect::<Vec<_>>();
+
+    let mut best: Option<(ImageFormat, f32)> = None;
+    for format in formats {
+        let q = quality(&accept, *format);
+        if q > 0.0 && best.is_none_or(|(_, best_q)| q > best_q) {
+            best = Some((*format, q));
+        }
+    }
+
+    best.map(|(format, _)| format).unwrap_or(FALLBACK)
+}
+
+/// `q` of the most specific media range matching format, 0 when not accepted
+fn quality(accept: &[(&str, f32)], format: ImageFormat) -> f32 {
+    let content_type = format.content_type();
+    if let Some((_, q)) = accept.iter().find(|(range, _)| *range == content_type) {
+        return *q;
+    }
+    if matches!(format, ImageFormat::Avif | ImageFormat::Webp) {
+        return 0.0;
+    }
+
+    ["image/*", "*/*"]
+        .iter()
+        .find_map(|wildcard| accept.iter().find(|(range, _)| range == wildcard))
+        .map_or(0.0, |(_, q)| *q)
+}
+
+/// `image/webp;q=0.8` -> (`image/webp`, 0.8)
+fn parse_media_range(value: &str) -> Option<(&str, f32)> {
+    let mut parts = value.split(';').map(str::trim);
+    let range = parts.next().filter(|range| !range.is_empty())?;
+    let q = parts
+        .filter_map(|param| param.strip_prefix("q="))
+        .find_map(|q| q.parse::<f32>().ok())
+        .unwrap_or(1.0);
+
+    Some((range, q))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+
+    use super::*;
+
+    const FORMATS: [ImageFormat; 4] = [
+        ImageFormat::Avif,
+        ImageFormat::Webp,
+        ImageFormat::Jpeg,
+        ImageFormat::Png,
+    ];
+
+    fn accept(value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT, HeaderValue::from_static(value));
+        headers
+    }
+
+    #[test]
+    fn no_accept_header_falls_back_to_jpeg() {
+        assert_eq!(
+            negotiate_format(&HeaderMap::new(), &FORMATS),
+            ImageFormat::Jpeg
+        );
+    }
+
+    #[test]
+    fn explicit_formats_win_in_server_order() {
+        let headers = accept("image/avif,image/webp,image/*,*/*;q=0.8");
+        assert_eq!(negotiate_format(&headers, &FORMATS), ImageFormat::Avif);
+
+        let headers = accept("image/webp,image/*,*/*;q=0.8");
+        assert_eq!(negotiate_format(&headers, &FORMATS), ImageFormat::Webp);
+    }
+
+    #[test]
+    fn higher_q_wins() {
+        let headers = accept("image/avif;q=0.5,image/webp;q=0.9");
+        assert_eq!(negotiate_format(&headers, &FORMATS), ImageFormat::Webp);
+
+        let headers = accept("image/webp;q=0.5, image/png");
+        assert_eq!(negotiate_format(&headers, &FORMATS), ImageFormat::Png);
+    }
+
+    #[test]
+    fn zero_q_is_not_accepted() {
+        let headers = accept("image/avif;q=0,image/webp;q=0,image/png;q=0");
+        assert_eq!(negotiate_format(&headers, &FORMATS), ImageFormat::Jpeg);
+    }
+
+    #[test]
+    fn wildcards_dont_match_avif_and_webp() {
+        let headers = accept("image/*");
+        assert_eq!(negotiate_format(&headers, &FORMATS), ImageFormat::Jpeg);
+
+        let headers = accept("*/*");
+        let formats = [ImageFormat::Avif, ImageFormat::Webp, ImageFormat::Png];
+        assert_eq!(negotiate_format(&headers, &formats), ImageFormat::Png);
+    }
+
+    #[test]
+    fn specific_range_overrides_wildcard() {
+        let headers = accept("image/*;q=0.9,image/jpeg;q=0.1");
+        assert_eq!(negotiate_format(&headers, &FORMATS), ImageFormat::Png);
+    }
+
+    #[test]
+    fn format_not_served_is_skipped() {
+        let headers = accept("image/avif,image/webp;q=0.5");
+        let formats = [ImageFormat::Webp, ImageFormat::Jpeg];
+        assert_eq!(negotiate_format(&headers, &formats), ImageFormat::Webp);
+    }
+
+    #[test]
+    fn parse_media_range_reads_q() {
+        assert_eq!(
+            parse_media_range(" image/webp ; q=0.8"),
+            Some(("image/webp", 0.8))
+        );
+        assert_eq!(parse_media_range("image/png"), Some(("image/png", 1.0)));
+        assert_eq!(parse_media_range(""), None);
+    }
+}
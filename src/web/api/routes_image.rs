@@ -1,57 +1,239 @@
+use std::time::Duration;
+
+use axum::body::StreamBody;
+use axum::extract::multipart::Field;
 use axum::extract::{BodyStream, Multipart, Path, Query, State};
-use axum::headers::ContentLength;
-use axum::http::HeaderMap;
-use axum::response::IntoResponse;
+use axum::headers::{CacheControl, ContentLength, HeaderMapExt};
+use axum::http::header::{CACHE_CONTROL, CONTENT_LENGTH, ETAG, EXPIRES, LAST_MODIFIED, VARY};
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
 use axum::{Json, TypedHeader};
+use bytes::Bytes;
 use regex::Regex;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::StreamExt;
+use tracing::debug;
 
 use crate::config;
+use crate::crypt::signed_url::SignedImage;
 use crate::ctx::Ctx;
+use crate::graphql::ExifPolicy;
 use crate::graphql::ImageKind;
-use crate::model::account::AccountBmc;
-use crate::model::image::{Image as ModelImage, ImageBmc, ImageForCreate};
-use crate::services::lust::Lust;
+use crate::model::account::{Account, AccountBmc};
+use crate::model::blob::{BlobBmc, BlobForCreate};
+use crate::model::image::{Image as ModelImage, ImageBmc, ImageForCreate, ImageForUpload};
+use crate::model::image_metadata::ImageMetadataForCreate;
+use crate::model::quota::QuotaBmc;
+use crate::model::sys_config::{ExifSettings, SysConfigBmc};
+use crate::model::ModelManager;
+use crate::services::placeholder::Placeholder;
+use crate::services::processor::ImageFormat;
+use crate::services::store::{ByteStream, SharedImageStore, StoredImage};
+use crate::services::transform::{Crop, Fit, Transform, TransformRules};
+use crate::services::validate::UploadValidator;
 
 use crate::web::error::Error;
 
+use super::http_cache::{self, Validators};
+use super::negotiate::negotiate_format;
 use super::ApiState;
 
 #[derive(Deserialize)]
 pub struct Image {
     size: Option<String>,
+    /// overrides format negotiated from `Accept` header
+    format: Option<ImageFormat>,
+    #[serde(rename = "w")]
+    width: Option<u32>,
+    #[serde(rename = "h")]
+    height: Option<u32>,
+    fit: Option<Fit>,
+    /// `width:height`
+    crop: Option<String>,
+    rotate: Option<u32>,
+    blur: Option<u32>,
+}
+
+impl Image {
+    /// transformation checked against allow-list, `None` when none was requested
+    fn transform(&self) -> Result<Option<Transform>, Error> {
+        let crop = match &self.crop {
+            Some(crop) => Some(
+                crop.parse::<Crop>()
+                    .map_err(|_| Error::BadRequest(format!("Bad crop aspect ratio: {}", crop)))?,
+            ),
+            None => None,
+        };
+        let transform = Transform {
+            width: self.width,
+            height: self.height,
+            fit: self.fit.unwrap_or_default(),
+            crop,
+            rotate: self.rotate,
+            blur: self.blur,
+        };
+        if transform.is_empty() {
+            return Ok(None);
+        }
+        if self.size.is_some() {
+            return Err(Error::BadRequest(
+                "Size preset can't be combined with transformation".to_string(),
+            ));
+        }
+
+        transform.check(&TransformRules::from_config())?;
+        Ok(Some(transform))
+    }
+}
+
+#[derive(Deserialize)]
+pub struct SignedImageQuery {
+    size: Option<String>,
+    format: Option<ImageFormat>,
+    exp: i64,
+    sig: String,
+}
+
+/// Image created from multipart field `file_N`
+#[derive(Serialize)]
+pub struct UploadedImage {
+    field: String,
+    image: ModelImage,
 }
 
+/// Serves stored original, its size preset or transformation (`w`, `h`, `fit`, `crop`, `rotate`, `blur`).\
+/// `image_id` is path of stored blob, images which caller may not see are reported as not found.
 pub async fn get_image(
     State(context): State<ApiState>,
+    ctx: Ctx,
     Path(image_id): Path<String>,
     Query(payload): Query<Image>,
-) -> Result<impl IntoResponse, Error> {
-    let client = context.reqwest_client;
-    let mut params = vec![];
+    headers: HeaderMap,
+) -> Result<Response, Error> {
+    let path: uuid::Uuid = image_id.parse().map_err(|_| Error::ImageNotFound)?;
+    let transform = payload.transform()?;
+    let images = ImageBmc::list_by_path(&context.mm, &path)?;
+    check_viewer(&context.mm, &ctx, &images)?;
 
-    match payload.size {
-        Some(size) => params.push(("size".to_string(), size)),
-        None => (),
-    }
+    let cache_control =
+        http_cache::cache_control(is_public(&context.mm, &images)?, http_cache::IMAGE_MAX_AGE);
+    let format = payload
+        .format
+        .unwrap_or_else(|| negotiate_format(&headers, &config().IMAGE_FORMATS));
+    let variant = match &transform {
+        Some(transform) => Variant::Transform(transform),
+        None => Variant::Preset(payload.size),
+    };
+    image_response(&context, path, variant, format, &headers, cache_control).await
+}
 
-    let (response, headers) = Lust::get_file(
-        &client,
-        &config().LUST_IMAGE_BUCKET,
-        &image_id,
-        Some(params),
+/// Serves image by url signed with `SignedImage`, no auth cookie is required.\
+/// Response can be cached until the url expires.
+pub async fn get_signed_image(
+    State(context): State<ApiState>,
+    Path(image_id): Path<uuid::Uuid>,
+    Query(payload): Query<SignedImageQuery>,
+    headers: HeaderMap,
+) -> Result<Response, Error> {
+    let signed = SignedImage {
+        path: image_id,
+        size: payload.size,
+        exp: payload.exp,
+    };
+    signed.verify(&payload.sig)?;
+
+    let images = ImageBmc::list_by_path(&context.mm, &image_id)?;
+    let max_age = (signed.exp - chrono::Utc::now().timestamp()).max(0) as u64;
+    let cache_control = http_cache::cache_control(
+        is_public(&context.mm, &images)?,
+        Duration::from_secs(max_age),
+    );
+    let format = payload
+        .format
+        .unwrap_or_else(|| negotiate_format(&headers, &config().IMAGE_FORMATS));
+    image_response(
+        &context,
+        image_id,
+        Variant::Preset(signed.size),
+        format,
+        &headers,
+        cache_control,
     )
-    .await?;
+    .await
+}
+
+/// Served variant of stored original
+enum Variant<'a> {
+    Preset(Option<String>),
+    Transform(&'a Transform),
+}
+
+impl Variant<'_> {
+    /// distinguishes variants in `ETag`
+    fn key(&self) -> Option<String> {
+        match self {
+            Variant::Preset(size) => size.clone(),
+            Variant::Transform(transform) => Some(transform.key()),
+        }
+    }
+}
+
+/// streams image from store, answers conditional and range requests
+async fn image_response(
+    context: &ApiState,
+    path: uuid::Uuid,
+    variant: Variant<'_>,
+    format: ImageFormat,
+    request: &HeaderMap,
+    cache_control: CacheControl,
+) -> Result<Response, Error> {
+    let bucket = &config().LUST_IMAGE_BUCKET;
+
+    let blob = BlobBmc::get(&context.mm, &path)?;
+    let validators = Validators::new(
+        blob.as_ref().and_then(|blob| blob.hash.as_deref()),
+        variant.key().as_deref(),
+        format,
+        blob.as_ref().map(|blob| blob.created_at),
+    );
 
-    let mut file = response.into_response();
-    let new_headers: &mut HeaderMap = file.headers_mut();
-    headers.into_iter().for_each(|(k, v)| {
-        if let Some(header_name) = k {
-            new_headers.insert(header_name, v);
+    let mut headers = HeaderMap::new();
+    validators.insert_into(&mut headers);
+    headers.typed_insert(cache_control);
+    headers.insert(VARY, HeaderValue::from_static("Accept"));
+    if validators.is_not_modified(request) {
+        return Ok((StatusCode::NOT_MODIFIED, headers).into_response());
+    }
+
+    let image_id = path.to_string();
+    let image_file = match variant {
+        Variant::Preset(Some(size)) => {
+            context
+                .store
+                .get_resized(bucket, &image_id, &size, Some(format))
+                .await?
+        }
+        Variant::Preset(None) => context.store.get(bucket, &image_id, Some(format)).await?,
+        Variant::Transform(transform) => {
+            context
+                .store
+                .get_transformed(bucket, &image_id, transform, format)
+                .await?
         }
-    });
+    };
+
+    // store headers describe the content, caching ones are ours
+    let mut response_headers = image_file.headers;
+    for name in [ETAG, LAST_MODIFIED, CACHE_CONTROL, EXPIRES, VARY] {
+        response_headers.remove(name);
+    }
+    response_headers.extend(headers);
 
-    Ok(file)
+    let (status, body) =
+        http_cache::apply_range(request, &validators, &mut response_headers, image_file.body);
+
+    Ok((status, response_headers, StreamBody::new(body)).into_response())
 }
 
 pub async fn post_image(
@@ -61,110 +243,276 @@ pub async fn post_image(
     file: BodyStream,
 ) -> Result<Json<ModelImage>, Error> {
     let mm = context.mm;
-    let client = context.reqwest_client;
-    let user = ctx.user_id;
-    let user_account = ctx.account_id;
+    let store = context.store;
+
+    let account = check_uploader(&mm, &ctx)?;
+    let exif = SysConfigBmc::exif_settings(&mm, &account)?;
+    let validator = UploadValidator::new(SysConfigBmc::upload_rules(&mm, &account)?);
+
+    let size = content_length.0;
+    if size == 0 {
+        return Err(Error::BadRequestReturn("File size is 0".to_string()));
+    }
+    validator.check_size(size)?;
+    // stored size is checked again when image is created, stripping can only shrink it
+    QuotaBmc::usage(&mm, &account.id)?.check(size as i64, 1)?;
+    let body: ByteStream = Box::pin(file.map(|chunk| chunk.map_err(std::io::Error::other)));
+    let stored = put_validated(&store, &validator, Some(size), exif.policy, body).await?;
+
+    let image = ImageBmc::create(
+        &mm,
+        image_for_create(&ctx, &stored, exif),
+        config().DEDUP_SCOPE,
+    );
+
+    match image {
+        Ok(image) => {
+            remove_duplicates(&store, &[stored], std::slice::from_ref(&image)).await;
+            Ok(image.into())
+        }
+        Err(e) => {
+            remove_stored(&store, &[stored]).await;
+            Err(e.into())
+        }
+    }
+}
+
+/// Uploads all `file_N` multipart fields.\
+/// Each field is streamed to image store, if any upload or image creation fails
+/// already stored files are removed and no image is created.
+pub async fn post_images(
+    State(context): State<ApiState>,
+    ctx: Ctx,
+    mut stream: Multipart,
+) -> Result<Json<Vec<UploadedImage>>, Error> {
+    let mm = context.mm;
+    let store = context.store;
+
+    let account = check_uploader(&mm, &ctx)?;
+    let exif = SysConfigBmc::exif_settings(&mm, &account)?;
+    let validator = UploadValidator::new(SysConfigBmc::upload_rules(&mm, &account)?);
+    QuotaBmc::usage(&mm, &account.id)?.check(0, 1)?;
+
+    let file_regex = Regex::new(r"^file_(\d+)$").unwrap();
+
+    let mut fields: Vec<String> = Vec::new();
+    let mut stored: Vec<StoredImage> = Vec::new();
+
+    loop {
+        let field = match stream.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(e) => {
+                remove_stored(&store, &stored).await;
+                return Err(Error::BadRequest(e.to_string()));
+            }
+        };
+
+        let name = match field.name() {
+            Some(name) if file_regex.is_match(name) => name.to_string(),
+            _ => continue,
+        };
+
+        match store_field(&store, &validator, exif, field).await {
+            Ok(image) => {
+                fields.push(name);
+                stored.push(image);
+            }
+            Err(e) => {
+                remove_stored(&store, &stored).await;
+                return Err(e);
+            }
+        }
+    }
+
+    if stored.is_empty() {
+        return Err(Error::BadRequestReturn("No files to upload".to_string()));
+    }
+
+    let images_for_create = stored
+        .iter()
+        .map(|image| image_for_create(&ctx, image, exif))
+        .collect();
+
+    let images = match ImageBmc::create_many(&mm, images_for_create, config().DEDUP_SCOPE) {
+        Ok(images) => images,
+        Err(e) => {
+            remove_stored(&store, &stored).await;
+            return Err(e.into());
+        }
+    };
+    remove_duplicates(&store, &stored, &images).await;
+
+    let uploaded = fields
+        .into_iter()
+        .zip(images)
+        .map(|(field, image)| UploadedImage { field, image })
+        .collect();
+
+    Ok(Json(uploaded))
+}
+
+/// image is visible when caller can see any of images sharing the stored blob
+fn check_viewer(mm: &ModelManager, ctx: &Ctx, images: &[ModelImage]) -> Result<(), Error> {
+    for image in images {
+        if image.is_visible(mm, &ctx.user_id, &ctx.account_id)? {
+            return Ok(());
+        }
+    }
+
+    Err(Error::ImageNotFound)
+}
+
+fn is_public(mm: &ModelManager, images: &[ModelImage]) -> Result<bool, Error> {
+    for image in images {
+        if image.is_public(mm)? {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
 
-    let account = AccountBmc::get(&mm, &user_account)?;
+pub(super) fn check_uploader(mm: &ModelManager, ctx: &Ctx) -> Result<Account, Error> {
+    let account = AccountBmc::get(mm, &ctx.account_id)?;
 
     if account.is_banned {
         return Err(Error::AuthError);
     }
 
     match account.kind.as_str() {
-        "creator" => (),
-        _ => {
-            return Err(Error::BadRequestReturn(
-                "You must be a creator to upload images".to_string(),
-            ))
-        }
+        "creator" => Ok(account),
+        _ => Err(Error::BadRequestReturn(
+            "You must be a creator to upload images".to_string(),
+        )),
     }
+}
 
-    let size = content_length.0;
-    if size == 0 {
-        return Err(Error::BadRequestReturn("File size is 0".to_string()));
+pub(super) fn image_for_create(
+    ctx: &Ctx,
+    stored: &StoredImage,
+    exif: ExifSettings,
+) -> ImageForUpload {
+    let image_id = uuid::Uuid::new_v4();
+    ImageForUpload {
+        image: ImageForCreate {
+            id: image_id,
+            user_id: ctx.user_id,
+            name: None,
+            kind: ImageKind::Image.to_string(),
+            path: stored.image_id,
+            hash: Some(stored.hash.clone()),
+            blurhash: stored
+                .placeholder
+                .as_ref()
+                .map(|placeholder| placeholder.blurhash.clone()),
+            preview: stored
+                .placeholder
+                .as_ref()
+                .map(|placeholder| placeholder.preview.clone()),
+        },
+        blob: BlobForCreate {
+            path: stored.image_id,
+            hash: Some(stored.hash.clone()),
+            user_id: Some(ctx.user_id),
+            size: Some(stored.size as i64),
+        },
+        metadata: ImageMetadataForCreate::new(image_id, &stored.metadata, exif),
     }
-    let response = Lust::post_stream(&client, &config().LUST_IMAGE_BUCKET, size, file).await?;
+}
 
-    let image_id = response.image_id.parse()?;
+/// streams multipart field to image store without buffering whole file,
+/// field length is taken from its own `Content-Length` header when client sends one
+async fn store_field(
+    store: &SharedImageStore,
+    validator: &UploadValidator,
+    exif: ExifSettings,
+    mut field: Field<'_>,
+) -> Result<StoredImage, Error> {
+    let size = field
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok());
+    let (tx, rx) = tokio::sync::mpsc::channel::<std::io::Result<Bytes>>(4);
+    let body: ByteStream = Box::pin(ReceiverStream::new(rx));
 
-    let image_for_create = ImageForCreate {
-        id: uuid::Uuid::new_v4(),
-        user_id: user.into(),
-        name: None,
-        kind: ImageKind::Image.to_string(),
-        path: image_id,
+    let forward = async move {
+        while let Some(chunk) = field.next().await {
+            let chunk = chunk.map_err(std::io::Error::other);
+            let failed = chunk.is_err();
+            // store stops reading on error, so there is no one to send rest of field to
+            if tx.send(chunk).await.is_err() || failed {
+                break;
+            }
+        }
     };
 
-    let image = ImageBmc::create(&mm, image_for_create)?;
+    let put = put_validated(store, validator, size, exif.policy, body);
+    let (stored, _) = tokio::join!(put, forward);
 
-    Ok(image.into())
+    stored
 }
 
-// TODO: Requires correct return type and backup transaction fallback for failed uploads
-pub async fn post_images(
-    State(context): State<ApiState>,
-    ctx: Ctx,
-    mut stream: Multipart,
-) -> Result<String, Error> {
-    let mm = context.mm;
-    let client = context.reqwest_client;
-    let user = ctx.user_id;
+/// stores original checked by `validator`, rejected upload returns reason of rejection.\
+/// Placeholder is computed from stored original, upload doesn't fail without it.
+pub(super) async fn put_validated(
+    store: &SharedImageStore,
+    validator: &UploadValidator,
+    size: Option<u64>,
+    policy: ExifPolicy,
+    body: ByteStream,
+) -> Result<StoredImage, Error> {
+    let bucket = &config().LUST_IMAGE_BUCKET;
+    let mut stored = store
+        .put(bucket, size, policy, validator.wrap(body))
+        .await
+        .map_err(|e| -> Error {
+            match validator.rejection() {
+                Some(rejection) => rejection.into(),
+                None => e.into(),
+            }
+        })?;
 
-    let file_regex = Regex::new(r"file_(\d+)").unwrap();
+    match Placeholder::from_store(store.as_ref(), bucket, &stored.image_id).await {
+        Ok(placeholder) => stored.placeholder = Some(placeholder),
+        Err(e) => debug!(
+            "{:<12} - failed to compute placeholder of {}: {:?}",
+            "UPLOAD", stored.image_id, e
+        ),
+    }
 
-    let mut images: Vec<(String, String)> = Vec::new();
+    Ok(stored)
+}
 
-    while let Some(field) = stream
-        .next_field()
-        .await
-        .map_err(|e| Error::BadRequest(e.to_string()))?
-    {
-        match field.name() {
-            Some(name) => match file_regex.find(name) {
-                Some(_) => {
-                    // TODO: would be better to pass stream to not buffer all files on server
-                    // requires some work to retrive image size
-                    let name = name.to_string();
-                    let bytes = field
-                        .bytes()
-                        .await
-                        .map_err(|e| Error::BadRequest(e.to_string()))?;
-
-                    let response =
-                        Lust::post_file(&client, &config().LUST_IMAGE_BUCKET, bytes.to_vec())
-                            .await?;
-
-                    let image_id = match response.image_id.parse() {
-                        Ok(image_id) => image_id,
-                        Err(_) => {
-                            break;
-                        }
-                    };
-
-                    let image = ImageBmc::create(
-                        &mm,
-                        crate::model::image::ImageForCreate {
-                            id: uuid::Uuid::new_v4(),
-                            user_id: user.into(),
-                            name: None,
-                            kind: ImageKind::Image.to_string(),
-                            path: image_id,
-                        },
-                    );
-
-                    match image {
-                        Ok(image) => images.push((name, image.path.to_string())),
-                        Err(_) => {
-                            break;
-                        }
-                    }
-                }
-                None => (),
-            },
-            None => (),
+/// best effort cleanup of files stored before upload failed
+pub(super) async fn remove_stored(store: &SharedImageStore, stored: &[StoredImage]) {
+    for image in stored {
+        remove_blob(store, image).await;
+    }
+}
+
+/// removes uploaded copies replaced by already stored blob with the same hash
+pub(super) async fn remove_duplicates(
+    store: &SharedImageStore,
+    stored: &[StoredImage],
+    images: &[ModelImage],
+) {
+    for image in stored {
+        if !images.iter().any(|created| created.path == image.image_id) {
+            remove_blob(store, image).await;
         }
     }
+}
 
-    Ok("".to_string())
+async fn remove_blob(store: &SharedImageStore, image: &StoredImage) {
+    let result = store
+        .delete(&config().LUST_IMAGE_BUCKET, &image.image_id.to_string())
+        .await;
+    if let Err(e) = result {
+        debug!(
+            "{:<12} - failed to remove stored image {}: {:?}",
+            "UPLOAD", image.image_id, e
+        );
+    }
 }
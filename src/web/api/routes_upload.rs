@@ -0,0 +1,364 @@
+use std::io::SeekFrom;
+use std::path::PathBuf;
+
+use axum::extract::{BodyStream, Path, State};
+use axum::http::{HeaderMap, HeaderName, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use tokio::fs;
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+use tokio_stream::StreamExt;
+use tokio_util::io::ReaderStream;
+use tracing::debug;
+use uuid::Uuid;
+
+use crate::config;
+use crate::ctx::Ctx;
+use crate::model::account::Account;
+use crate::model::image::ImageBmc;
+use crate::model::quota::QuotaBmc;
+use crate::model::sys_config::SysConfigBmc;
+use crate::model::upload::{Upload, UploadBmc, UploadForCreate};
+use crate::model::ModelManager;
+use crate::services::store::{ByteStream, SharedImageStore};
+use crate::services::validate::UploadValidator;
+use crate::web::error::Error;
+
+use super::routes_image::{
+    check_uploader, image_for_create, put_validated, remove_duplicates, remove_stored,
+};
+use super::ApiState;
+
+// region:    --- Tus headers
+const TUS_RESUMABLE: HeaderName = HeaderName::from_static("tus-resumable");
+const UPLOAD_LENGTH: HeaderName = HeaderName::from_static("upload-length");
+const UPLOAD_OFFSET: HeaderName = HeaderName::from_static("upload-offset");
+const UPLOAD_EXPIRES: HeaderName = HeaderName::from_static("upload-expires");
+const TUS_VERSION: &str = "1.0.0";
+// endregion: --- Tus headers
+
+/// Creates upload session for file of `Upload-Length` bytes.\
+/// Returns session url in `Location` header.
+pub async fn create_upload(
+    State(context): State<ApiState>,
+    ctx: Ctx,
+    headers: HeaderMap,
+) -> Result<Response, Error> {
+    let mm = context.mm;
+
+    let account = check_uploader(&mm, &ctx)?;
+
+    let length = header_u64(&headers, &UPLOAD_LENGTH)?;
+    if length == 0 {
+        return Err(Error::BadRequestReturn("File size is 0".to_string()));
+    }
+    if length > config().UPLOAD_MAX_SIZE {
+        return Err(Error::UploadTooLarge {
+            max_size: config().UPLOAD_MAX_SIZE,
+        });
+    }
+    // fail before client sends whole file, content is checked when upload is finished
+    UploadValidator::new(SysConfigBmc::upload_rules(&mm, &account)?).check_size(length)?;
+    QuotaBmc::usage(&mm, &account.id)?.check(length as i64, 1)?;
+
+    remove_expired(&mm).await;
+
+    let upload = UploadBmc::create(
+        &mm,
+        UploadForCreate {
+            id: Uuid::new_v4(),
+            user_id: ctx.user_id,
+            upload_length: length as i64,
+            expires_at: chrono::Utc::now() + chrono::Duration::seconds(config().UPLOAD_DURATION),
+        },
+    )?;
+
+    let created = async {
+        fs::create_dir_all(&config().UPLOAD_DIR).await?;
+        fs::File::create(upload_path(&upload.id)).await
+    }
+    .await;
+    if let Err(e) = created {
+        let _ = UploadBmc::delete(&mm, &upload.id);
+        return Err(Error::BadRequest(e.to_string()));
+    }
+
+    let mut headers = upload_headers(&upload);
+    headers.insert(
+        axum::http::header::LOCATION,
+        header_value(format!("/api/uploads/{}", upload.id))?,
+    );
+
+    Ok((StatusCode::CREATED, headers).into_response())
+}
+
+/// Returns current offset so client knows where to resume from.
+pub async fn head_upload(
+    State(context): State<ApiState>,
+    ctx: Ctx,
+    Path(upload_id): Path<Uuid>,
+) -> Result<Response, Error> {
+    let upload = get_owned(&context.mm, &ctx, &upload_id)?;
+
+    let mut headers = upload_headers(&upload);
+    headers.insert(
+        axum::http::header::CACHE_CONTROL,
+        HeaderValue::from_static("no-store"),
+    );
+
+    Ok((StatusCode::OK, headers).into_response())
+}
+
+/// Appends chunk at `Upload-Offset`.\
+/// Bytes received before connection drop are kept, so client can resume from new offset.
+/// Chunk is received into its own part file and written to the upload only after the offset is moved,
+/// so concurrent requests at the same offset can't overwrite each other.
+/// When last chunk arrives file is passed to image store and created image is returned.
+pub async fn patch_upload(
+    State(context): State<ApiState>,
+    ctx: Ctx,
+    Path(upload_id): Path<Uuid>,
+    headers: HeaderMap,
+    mut body: BodyStream,
+) -> Result<Response, Error> {
+    let mm = context.mm;
+
+    let account = check_uploader(&mm, &ctx)?;
+    let upload = get_owned(&mm, &ctx, &upload_id)?;
+
+    let offset = header_u64(&headers, &UPLOAD_OFFSET)? as i64;
+    if offset != upload.upload_offset {
+        return Err(Error::UploadOffsetMismatch {
+            expected: upload.upload_offset,
+        });
+    }
+
+    let part = part_path(&upload.id);
+    let received = receive_part(&part, &upload, offset, &mut body).await;
+    let written = match received {
+        Ok(written) => written,
+        Err(e) => {
+            let _ = fs::remove_file(&part).await;
+            return Err(e);
+        }
+    };
+
+    let new_offset = offset + written as i64;
+    let moved = UploadBmc::update_offset(&mm, &upload.id, offset, new_offset);
+    let upload = match moved {
+        Ok(Some(moved)) => moved,
+        Ok(None) => {
+            let _ = fs::remove_file(&part).await;
+            return Err(Error::UploadOffsetMismatch {
+                expected: upload.upload_offset,
+            });
+        }
+        Err(e) => {
+            let _ = fs::remove_file(&part).await;
+            return Err(e.into());
+        }
+    };
+
+    let appended = append_part(&part, &upload.id, offset).await;
+    let _ = fs::remove_file(&part).await;
+    if let Err(e) = appended {
+        // give the range back, so client can send it again
+        UploadBmc::update_offset(&mm, &upload.id, new_offset, offset)?;
+        return Err(e);
+    }
+
+    if upload.upload_offset < upload.upload_length {
+        return Ok((StatusCode::NO_CONTENT, upload_headers(&upload)).into_response());
+    }
+
+    let headers = upload_headers(&upload);
+    let result = finish_upload(&mm, &context.store, &ctx, &account, &upload).await;
+    remove_upload(&mm, &upload.id).await;
+
+    let image = result?;
+    Ok((StatusCode::OK, headers, Json(image)).into_response())
+}
+
+/// receives request body into part file, returns count of received bytes
+async fn receive_part(
+    part: &std::path::Path,
+    upload: &Upload,
+    offset: i64,
+    body: &mut BodyStream,
+) -> Result<u64, Error> {
+    let remaining = (upload.upload_length - offset) as u64;
+    let mut written: u64 = 0;
+    let mut file = fs::File::create(part)
+        .await
+        .map_err(|e| Error::BadRequest(e.to_string()))?;
+
+    while let Some(chunk) = body.next().await {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                // keep what we have, client resumes from stored offset
+                debug!("{:<12} - upload {} interrupted: {}", "UPLOAD", upload.id, e);
+                break;
+            }
+        };
+        if written + chunk.len() as u64 > remaining {
+            return Err(Error::UploadTooLarge {
+                max_size: upload.upload_length as u64,
+            });
+        }
+        file.write_all(&chunk)
+            .await
+            .map_err(|e| Error::BadRequest(e.to_string()))?;
+        written += chunk.len() as u64;
+    }
+
+    file.flush()
+        .await
+        .map_err(|e| Error::BadRequest(e.to_string()))?;
+    Ok(written)
+}
+
+/// writes received part into upload file at `offset`
+async fn append_part(part: &std::path::Path, upload_id: &Uuid, offset: i64) -> Result<(), Error> {
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .open(upload_path(upload_id))
+        .await
+        .map_err(|_| Error::UploadNotFound)?;
+    file.seek(SeekFrom::Start(offset as u64))
+        .await
+        .map_err(|e| Error::BadRequest(e.to_string()))?;
+
+    let mut part = fs::File::open(part)
+        .await
+        .map_err(|e| Error::BadRequest(e.to_string()))?;
+    tokio::io::copy(&mut part, &mut file)
+        .await
+        .map_err(|e| Error::BadRequest(e.to_string()))?;
+    file.flush()
+        .await
+        .map_err(|e| Error::BadRequest(e.to_string()))
+}
+
+/// Terminates upload session and removes uploaded chunks.
+pub async fn delete_upload(
+    State(context): State<ApiState>,
+    ctx: Ctx,
+    Path(upload_id): Path<Uuid>,
+) -> Result<Response, Error> {
+    let upload = get_owned(&context.mm, &ctx, &upload_id)?;
+    remove_upload(&context.mm, &upload.id).await;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(TUS_RESUMABLE, HeaderValue::from_static(TUS_VERSION));
+    Ok((StatusCode::NO_CONTENT, headers).into_response())
+}
+
+/// hands assembled file to image store, the same way as single image upload
+async fn finish_upload(
+    mm: &ModelManager,
+    store: &SharedImageStore,
+    ctx: &Ctx,
+    account: &Account,
+    upload: &Upload,
+) -> Result<crate::model::image::Image, Error> {
+    let exif = SysConfigBmc::exif_settings(mm, account)?;
+    let validator = UploadValidator::new(SysConfigBmc::upload_rules(mm, account)?);
+
+    let file = fs::File::open(upload_path(&upload.id))
+        .await
+        .map_err(|_| Error::UploadNotFound)?;
+    let body: ByteStream = Box::pin(ReaderStream::new(file));
+
+    let stored = put_validated(
+        store,
+        &validator,
+        Some(upload.upload_length as u64),
+        exif.policy,
+        body,
+    )
+    .await?;
+
+    match ImageBmc::create(
+        mm,
+        image_for_create(ctx, &stored, exif),
+        config().DEDUP_SCOPE,
+    ) {
+        Ok(image) => {
+            remove_duplicates(store, &[stored], std::slice::from_ref(&image)).await;
+            Ok(image)
+        }
+        Err(e) => {
+            remove_stored(store, &[stored]).await;
+            Err(e.into())
+        }
+    }
+}
+
+fn get_owned(mm: &ModelManager, ctx: &Ctx, upload_id: &Uuid) -> Result<Upload, Error> {
+    let upload = UploadBmc::get(mm, upload_id).map_err(|_| Error::UploadNotFound)?;
+
+    if upload.user_id != ctx.user_id || upload.expires_at < chrono::Utc::now() {
+        return Err(Error::UploadNotFound);
+    }
+
+    Ok(upload)
+}
+
+async fn remove_upload(mm: &ModelManager, upload_id: &Uuid) {
+    if let Err(e) = fs::remove_file(upload_path(upload_id)).await {
+        debug!(
+            "{:<12} - failed to remove upload file {}: {}",
+            "UPLOAD", upload_id, e
+        );
+    }
+    if let Err(e) = UploadBmc::delete(mm, upload_id) {
+        debug!(
+            "{:<12} - failed to remove upload {}: {:?}",
+            "UPLOAD", upload_id, e
+        );
+    }
+}
+
+async fn remove_expired(mm: &ModelManager) {
+    match UploadBmc::list_expired(mm) {
+        Ok(uploads) => {
+            for upload in uploads {
+                remove_upload(mm, &upload.id).await;
+            }
+        }
+        Err(e) => debug!("{:<12} - failed to list expired uploads: {:?}", "UPLOAD", e),
+    }
+}
+
+fn upload_path(upload_id: &Uuid) -> PathBuf {
+    PathBuf::from(&config().UPLOAD_DIR).join(upload_id.to_string())
+}
+
+/// file of single PATCH request, unique so concurrent requests don't share it
+fn part_path(upload_id: &Uuid) -> PathBuf {
+    PathBuf::from(&config().UPLOAD_DIR).join(format!("{}.{}.part", upload_id, Uuid::new_v4()))
+}
+
+fn upload_headers(upload: &Upload) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(TUS_RESUMABLE, HeaderValue::from_static(TUS_VERSION));
+    headers.insert(UPLOAD_OFFSET, HeaderValue::from(upload.upload_offset));
+    headers.insert(UPLOAD_LENGTH, HeaderValue::from(upload.upload_length));
+    if let Ok(expires) = header_value(upload.expires_at.to_rfc2822()) {
+        headers.insert(UPLOAD_EXPIRES, expires);
+    }
+    headers
+}
+
+fn header_u64(headers: &HeaderMap, name: &HeaderName) -> Result<u64, Error> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok())
+        .ok_or_else(|| Error::BadRequestReturn(format!("Missing or invalid {} header", name)))
+}
+
+fn header_value(value: String) -> Result<HeaderValue, Error> {
+    HeaderValue::from_str(&value).map_err(|e| Error::BadRequest(e.to_string()))
+}
@@ -2,17 +2,26 @@ use crate::services::lust::LustError;
 use crate::{crypt, model, web};
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
+use axum::Json;
 use serde::Serialize;
 use tracing::debug;
 
+use crate::graphql::Scope;
+use crate::model::invite::InviteRejection;
+use crate::model::registration::RegistrationRejection;
+use crate::model::session::SessionRejection;
 use crate::services::error::Error as ServiceError;
+use crate::services::transform::TransformRejection;
+use crate::services::validate::Rejection;
 
 pub type Result<T> = core::result::Result<T, Error>;
 
 #[derive(Debug, Serialize, strum_macros::AsRefStr)]
 #[serde(tag = "type", content = "data")]
 pub enum Error {
-    LoginFailPwdNotMatching { user_id: String },
+    LoginFailPwdNotMatching {
+        user_id: String,
+    },
 
     CtxExt(web::middleware::CtxExtError),
 
@@ -29,11 +38,74 @@ pub enum Error {
 
     BadUuidFormat,
     AuthError,
+    TokenScopeMissing {
+        scope: Scope,
+    },
+
+    ImageNotFound,
+
+    UploadNotFound,
+    UploadOffsetMismatch {
+        expected: i64,
+    },
+    UploadTooLarge {
+        max_size: u64,
+    },
+    UploadUnsupportedType {
+        mime_type: Option<String>,
+    },
+    UploadDimensionsTooLarge {
+        width: u32,
+        height: u32,
+        max_width: u32,
+        max_height: u32,
+    },
+    UploadUnreadableImage,
+
+    TransformNotAllowed(TransformRejection),
+
+    StorageQuotaExceeded {
+        used: i64,
+        requested: i64,
+        quota: i64,
+    },
+    ImageQuotaExceeded {
+        used: i32,
+        requested: i32,
+        quota: i32,
+    },
+
+    RegistrationRejected(RegistrationRejection),
+    InviteRejected(InviteRejection),
+    SessionRejected(SessionRejection),
 }
 
 impl From<model::Error> for Error {
     fn from(val: model::Error) -> Self {
-        Error::Model(val)
+        match val {
+            model::Error::StorageQuotaExceeded {
+                used,
+                requested,
+                quota,
+            } => Error::StorageQuotaExceeded {
+                used,
+                requested,
+                quota,
+            },
+            model::Error::ImageQuotaExceeded {
+                used,
+                requested,
+                quota,
+            } => Error::ImageQuotaExceeded {
+                used,
+                requested,
+                quota,
+            },
+            model::Error::RegistrationRejected(rejection) => Error::RegistrationRejected(rejection),
+            model::Error::InviteRejected(rejection) => Error::InviteRejected(rejection),
+            model::Error::SessionRejected(rejection) => Error::SessionRejected(rejection),
+            _ => Error::Model(val),
+        }
     }
 }
 
@@ -64,6 +136,33 @@ impl From<crate::services::error::Error> for Error {
     }
 }
 
+impl From<Rejection> for Error {
+    fn from(val: Rejection) -> Self {
+        match val {
+            Rejection::UnsupportedType { mime_type } => Self::UploadUnsupportedType { mime_type },
+            Rejection::DimensionsTooLarge {
+                width,
+                height,
+                max_width,
+                max_height,
+            } => Self::UploadDimensionsTooLarge {
+                width,
+                height,
+                max_width,
+                max_height,
+            },
+            Rejection::UnreadableImage => Self::UploadUnreadableImage,
+            Rejection::TooLarge { max_size } => Self::UploadTooLarge { max_size },
+        }
+    }
+}
+
+impl From<TransformRejection> for Error {
+    fn from(val: TransformRejection) -> Self {
+        Self::TransformNotAllowed(val)
+    }
+}
+
 impl IntoResponse for Error {
     fn into_response(self) -> Response {
         debug!("{:<12} - model::Error {self:?}", "INTO_RES");
@@ -85,7 +184,54 @@ impl IntoResponse for Error {
             Error::LustError(LustError::BadRequest(_, _)) => {
                 StatusCode::BAD_REQUEST.into_response()
             }
+            Error::ServiceError(ServiceError::ImageNotFound(_, _)) => {
+                StatusCode::NOT_FOUND.into_response()
+            }
+            Error::ServiceError(ServiceError::BadImageId(_))
+            | Error::ServiceError(ServiceError::UnknownPreset(_))
+            | Error::ServiceError(ServiceError::ImageProcessing(_)) => {
+                StatusCode::BAD_REQUEST.into_response()
+            }
+            Error::ImageNotFound | Error::UploadNotFound => StatusCode::NOT_FOUND.into_response(),
+            Error::UploadOffsetMismatch { .. } => StatusCode::CONFLICT.into_response(),
+            // rejected uploads carry reason and limits, so client can act on them
+            Error::UploadTooLarge { .. } => {
+                (StatusCode::PAYLOAD_TOO_LARGE, Json(&self)).into_response()
+            }
+            Error::UploadUnsupportedType { .. } => {
+                (StatusCode::UNSUPPORTED_MEDIA_TYPE, Json(&self)).into_response()
+            }
+            Error::UploadDimensionsTooLarge { .. } | Error::UploadUnreadableImage => {
+                (StatusCode::UNPROCESSABLE_ENTITY, Json(&self)).into_response()
+            }
+            Error::TransformNotAllowed(_) => (StatusCode::BAD_REQUEST, Json(&self)).into_response(),
+            Error::StorageQuotaExceeded { .. } | Error::ImageQuotaExceeded { .. } => {
+                (StatusCode::FORBIDDEN, Json(&self)).into_response()
+            }
+            Error::RegistrationRejected(ref rejection) => {
+                let status = match rejection {
+                    RegistrationRejection::RegistrationClosed
+                    | RegistrationRejection::InviteRequired => StatusCode::FORBIDDEN,
+                    RegistrationRejection::EmailTaken | RegistrationRejection::NickTaken => {
+                        StatusCode::CONFLICT
+                    }
+                    _ => StatusCode::BAD_REQUEST,
+                };
+                (status, Json(&self)).into_response()
+            }
+            Error::InviteRejected(ref rejection) => {
+                let status = match rejection {
+                    InviteRejection::AlreadyReferred => StatusCode::CONFLICT,
+                    _ => StatusCode::FORBIDDEN,
+                };
+                (status, Json(&self)).into_response()
+            }
+            Error::SessionRejected(_) => (StatusCode::UNAUTHORIZED, Json(&self)).into_response(),
+            Error::TokenScopeMissing { .. } => (StatusCode::FORBIDDEN, Json(&self)).into_response(),
             Error::CtxExt(_) => StatusCode::UNAUTHORIZED.into_response(),
+            Error::Crypt(crypt::Error::SignatureInvalid)
+            | Error::Crypt(crypt::Error::SignatureExpired)
+            | Error::Crypt(crypt::Error::FailToB64uDecode) => StatusCode::FORBIDDEN.into_response(),
             Error::BadRequestReturn(ref e) => (StatusCode::BAD_REQUEST, e.clone()).into_response(),
             _ => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
         };
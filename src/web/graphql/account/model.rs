@@ -1,10 +1,12 @@
-use async_graphql::{ComplexObject, Context, InputObject, SimpleObject};
+use async_graphql::{ComplexObject, Context, InputObject, MaybeUndefined, SimpleObject};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::graphql::guard::{Role, RoleGuard};
 use crate::graphql::scalars::{DateTime, Id, PublicLvl};
+use crate::graphql::ExifPolicy;
 use crate::model::account::AccountBmc;
+use crate::model::quota::QuotaBmc;
 use crate::model::user::UserBmc;
 use crate::model::ModelManager;
 use crate::web::graphql::error::Error as GraphQLError;
@@ -22,6 +24,12 @@ pub struct Account {
     pub is_banned: bool,
     pub created_at: DateTime,
     pub updated_at: DateTime,
+    /// empty when sys config default is used
+    pub exif_policy: Option<ExifPolicy>,
+    pub exif_retain_private: Option<bool>,
+    /// empty when sys config default is used
+    pub quota_bytes: Option<i64>,
+    pub quota_images: Option<i32>,
 }
 
 #[ComplexObject]
@@ -47,6 +55,16 @@ impl Account {
             AccountBmc::get_referrals(mm, &self.id.into()).map_err(GraphQLError::ModelError)?;
         Ok(users.into_iter().map(|r| r.into()).collect())
     }
+
+    pub async fn storage_usage(&self, ctx: &Context<'_>) -> async_graphql::Result<StorageUsage> {
+        let mm = ctx.data_opt::<ModelManager>();
+        let mm = match mm {
+            Some(mm) => mm,
+            None => return Err(GraphQLError::ModalManagerNotInContext.into()),
+        };
+        let usage = QuotaBmc::usage(mm, &self.id.into()).map_err(GraphQLError::ModelError)?;
+        Ok(usage.into())
+    }
 }
 
 impl From<crate::model::account::Account> for Account {
@@ -61,6 +79,10 @@ impl From<crate::model::account::Account> for Account {
             is_banned: account.is_banned,
             created_at: account.created_at.into(),
             updated_at: account.updated_at.into(),
+            exif_policy: account.exif_policy.map(|policy| policy.into()),
+            exif_retain_private: account.exif_retain_private,
+            quota_bytes: account.quota_bytes,
+            quota_images: account.quota_images,
         }
     }
 }
@@ -93,18 +115,70 @@ pub struct AccountForUpdate {
     pub is_admin: Option<bool>,
     pub public_lvl: Option<PublicLvl>,
     pub is_banned: Option<bool>,
+    /// `null` resets to sys config default
+    pub exif_policy: MaybeUndefined<ExifPolicy>,
+    pub exif_retain_private: MaybeUndefined<bool>,
+    /// `null` resets to sys config default
+    pub quota_bytes: MaybeUndefined<i64>,
+    pub quota_images: MaybeUndefined<i32>,
 }
 
-impl Into<crate::model::account::AccountForUpdate> for AccountForUpdate {
-    fn into(self) -> crate::model::account::AccountForUpdate {
+impl From<AccountForUpdate> for crate::model::account::AccountForUpdate {
+    fn from(value: AccountForUpdate) -> Self {
         crate::model::account::AccountForUpdate {
-            fullname: self.fullname,
-            email: self.email,
-            kind: self.kind.map(|r| r.into()),
-            is_admin: self.is_admin,
-            public_lvl: self.public_lvl.map(|pl| pl.into()),
-            is_banned: self.is_banned,
+            fullname: value.fullname,
+            email: value.email,
+            kind: value.kind.map(|r| r.into()),
+            is_admin: value.is_admin,
+            public_lvl: value.public_lvl.map(|pl| pl.into()),
+            is_banned: value.is_banned,
+            exif_policy: value
+                .exif_policy
+                .map_value(|policy| policy.to_string())
+                .into(),
+            exif_retain_private: value.exif_retain_private.into(),
+            quota_bytes: value.quota_bytes.into(),
+            quota_images: value.quota_images.into(),
             updated_at: chrono::Utc::now(),
         }
     }
 }
+
+/// ### EXIF settings
+/// EXIF handling of account uploads, with sys config defaults applied.
+#[derive(Debug, Clone, Serialize, SimpleObject)]
+pub struct ExifSettings {
+    pub policy: ExifPolicy,
+    /// stripped fields are kept in image metadata, visible only to the owner
+    pub retain_private: bool,
+}
+
+impl From<crate::model::sys_config::ExifSettings> for ExifSettings {
+    fn from(settings: crate::model::sys_config::ExifSettings) -> Self {
+        Self {
+            policy: settings.policy,
+            retain_private: settings.retain_private,
+        }
+    }
+}
+
+/// ### Storage usage
+/// Stored bytes and images of account, quota is empty when unlimited.
+#[derive(Debug, Clone, Serialize, SimpleObject)]
+pub struct StorageUsage {
+    pub bytes: i64,
+    pub images: i32,
+    pub quota_bytes: Option<i64>,
+    pub quota_images: Option<i32>,
+}
+
+impl From<crate::model::quota::StorageUsage> for StorageUsage {
+    fn from(usage: crate::model::quota::StorageUsage) -> Self {
+        Self {
+            bytes: usage.bytes,
+            images: usage.images,
+            quota_bytes: usage.quota.bytes,
+            quota_images: usage.quota.images,
+        }
+    }
+}
@@ -1,21 +1,25 @@
-use async_graphql::{Context, Object, Result};
+use async_graphql::{Context, MaybeUndefined, Object, Result};
 
-use crate::graphql::guard::{Role, RoleGuard};
+use crate::ctx::Ctx;
+use crate::graphql::guard::{Role, RoleGuard, ScopeGuard};
+use crate::graphql::ExifPolicy;
+use crate::graphql::Scope;
 use crate::model::account::AccountBmc;
+use crate::model::sys_config::SysConfigBmc;
 use crate::web::graphql::error::Error as GraphQLError;
 use crate::{
     graphql::scalars::{DateTime, Id},
     model::ModelManager,
 };
 
-use super::model::{Account, AccountForCreate, AccountForUpdate};
+use super::model::{Account, AccountForCreate, AccountForUpdate, ExifSettings};
 
 #[derive(Default)]
 pub struct AccountMutation;
 
-#[Object]
+#[Object(guard = "ScopeGuard::new(Scope::AccountWrite)")]
 impl AccountMutation {
-    #[graphql(guard = "RoleGuard::new(Role::Admin)")]
+    #[graphql(guard = "RoleGuard::new(Role::Admin).and(ScopeGuard::new(Scope::AccountWrite))")]
     async fn create_account(&self, ctx: &Context<'_>, input: AccountForCreate) -> Result<Account> {
         let mm = ctx.data_opt::<ModelManager>();
         let mm = match mm {
@@ -26,7 +30,7 @@ impl AccountMutation {
         Ok(account.into())
     }
 
-    #[graphql(guard = "RoleGuard::new(Role::Admin)")]
+    #[graphql(guard = "RoleGuard::new(Role::Admin).and(ScopeGuard::new(Scope::AccountWrite))")]
     async fn create_account_with_referral(
         &self,
         ctx: &Context<'_>,
@@ -38,13 +42,23 @@ impl AccountMutation {
             Some(mm) => mm,
             None => return Err(GraphQLError::ModalManagerNotInContext.into()),
         };
+        let admin_id = match ctx.data_opt::<Ctx>() {
+            Some(ctx) => ctx.account_id,
+            None => return Err(GraphQLError::AccessError("No user logged in".to_string()).into()),
+        };
 
-        let account = AccountBmc::create_with_referral(mm, &referrer_id.into(), None, input.into())
-            .map_err(GraphQLError::ModelError)?;
+        let account = AccountBmc::create_with_referral(
+            mm,
+            &referrer_id.into(),
+            None,
+            input.into(),
+            &admin_id,
+        )
+        .map_err(GraphQLError::ModelError)?;
         Ok(account.into())
     }
 
-    #[graphql(guard = "RoleGuard::new(Role::Admin)")]
+    #[graphql(guard = "RoleGuard::new(Role::Admin).and(ScopeGuard::new(Scope::AccountWrite))")]
     async fn update_account(
         &self,
         ctx: &Context<'_>,
@@ -61,7 +75,7 @@ impl AccountMutation {
         Ok(account.into())
     }
 
-    #[graphql(guard = "RoleGuard::new(Role::Admin)")]
+    #[graphql(guard = "RoleGuard::new(Role::Admin).and(ScopeGuard::new(Scope::AccountWrite))")]
     async fn delete_account(&self, ctx: &Context<'_>, id: Id) -> Result<String> {
         let mm = ctx.data_opt::<ModelManager>();
         let mm = match mm {
@@ -72,7 +86,7 @@ impl AccountMutation {
         Ok("Account deleted".to_string())
     }
 
-    #[graphql(guard = "RoleGuard::new(Role::Admin)")]
+    #[graphql(guard = "RoleGuard::new(Role::Admin).and(ScopeGuard::new(Scope::AccountWrite))")]
     async fn add_referral(
         &self,
         ctx: &Context<'_>,
@@ -85,13 +99,49 @@ impl AccountMutation {
             Some(mm) => mm,
             None => return Err(GraphQLError::ModalManagerNotInContext.into()),
         };
+        let admin_id = match ctx.data_opt::<Ctx>() {
+            Some(ctx) => ctx.account_id,
+            None => return Err(GraphQLError::AccessError("No user logged in".to_string()).into()),
+        };
         AccountBmc::add_referral(
             mm,
             &referrer_id.into(),
             &user_id.into(),
             exp.map(|x| x.into()),
+            &admin_id,
         )
         .map_err(GraphQLError::ModelError)?;
         Ok("Referral added".to_string())
     }
+
+    /// Sets EXIF handling of logged creator uploads, `null` resets to sys config default.
+    #[graphql(guard = "RoleGuard::new(Role::Creator).and(ScopeGuard::new(Scope::AccountWrite))")]
+    async fn update_own_exif_settings(
+        &self,
+        ctx: &Context<'_>,
+        policy: MaybeUndefined<ExifPolicy>,
+        retain_private: MaybeUndefined<bool>,
+    ) -> Result<ExifSettings> {
+        let mm = ctx.data_opt::<ModelManager>();
+        let mm = match mm {
+            Some(mm) => mm,
+            None => return Err(GraphQLError::ModalManagerNotInContext.into()),
+        };
+        let account_id = match ctx.data_opt::<Ctx>() {
+            Some(ctx) => ctx.account_id,
+            None => return Err(GraphQLError::AccessError("No user logged in".to_string()).into()),
+        };
+
+        let update = crate::model::account::AccountForUpdate {
+            exif_policy: policy.map_value(|policy| policy.to_string()).into(),
+            exif_retain_private: retain_private.into(),
+            updated_at: chrono::Utc::now(),
+            ..Default::default()
+        };
+        let account =
+            AccountBmc::update(mm, &account_id, update).map_err(GraphQLError::ModelError)?;
+        let settings =
+            SysConfigBmc::exif_settings(mm, &account).map_err(GraphQLError::ModelError)?;
+        Ok(settings.into())
+    }
 }
@@ -1,18 +1,21 @@
 use async_graphql::{Context, Object, Result};
 
-use crate::graphql::guard::{Role, RoleGuard};
+use crate::ctx::Ctx;
+use crate::graphql::guard::{Role, RoleGuard, ScopeGuard};
+use crate::graphql::Scope;
 use crate::model::account::AccountBmc;
+use crate::model::sys_config::SysConfigBmc;
 use crate::web::graphql::error::Error as GraphQLError;
 use crate::{graphql::scalars::Id, model::ModelManager};
 
-use super::model::Account;
+use super::model::{Account, ExifSettings};
 
 #[derive(Default)]
 pub struct AccountQuery;
 
-#[Object]
+#[Object(guard = "ScopeGuard::new(Scope::AccountRead)")]
 impl AccountQuery {
-    #[graphql(guard = "RoleGuard::new(Role::Admin)")]
+    #[graphql(guard = "RoleGuard::new(Role::Admin).and(ScopeGuard::new(Scope::AccountRead))")]
     async fn account(&self, ctx: &Context<'_>, id: Id) -> Result<Account> {
         let mm = ctx.data_opt::<ModelManager>();
         let mm = match mm {
@@ -23,7 +26,7 @@ impl AccountQuery {
         Ok(account.into())
     }
 
-    #[graphql(guard = "RoleGuard::new(Role::Admin)")]
+    #[graphql(guard = "RoleGuard::new(Role::Admin).and(ScopeGuard::new(Scope::AccountRead))")]
     async fn accounts(&self, ctx: &Context<'_>) -> Result<Vec<Account>> {
         let mm = ctx.data_opt::<ModelManager>();
         let mm = match mm {
@@ -33,4 +36,23 @@ impl AccountQuery {
         let accounts = AccountBmc::list(mm).map_err(GraphQLError::ModelError)?;
         Ok(accounts.into_iter().map(|r| r.into()).collect())
     }
+
+    /// EXIF handling of logged creator uploads
+    #[graphql(guard = "RoleGuard::new(Role::Creator).and(ScopeGuard::new(Scope::AccountRead))")]
+    async fn own_exif_settings(&self, ctx: &Context<'_>) -> Result<ExifSettings> {
+        let mm = ctx.data_opt::<ModelManager>();
+        let mm = match mm {
+            Some(mm) => mm,
+            None => return Err(GraphQLError::ModalManagerNotInContext.into()),
+        };
+        let account_id = match ctx.data_opt::<Ctx>() {
+            Some(ctx) => ctx.account_id,
+            None => return Err(GraphQLError::AccessError("No user logged in".to_string()).into()),
+        };
+
+        let account = AccountBmc::get(mm, &account_id).map_err(GraphQLError::ModelError)?;
+        let settings =
+            SysConfigBmc::exif_settings(mm, &account).map_err(GraphQLError::ModelError)?;
+        Ok(settings.into())
+    }
 }
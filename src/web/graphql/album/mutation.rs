@@ -1,5 +1,7 @@
 use async_graphql::{Context, Object, Result};
 
+use crate::graphql::guard::ScopeGuard;
+use crate::graphql::Scope;
 use crate::model::album::AlbumBmc;
 use crate::web::graphql::error::Error as GraphQLError;
 use crate::{graphql::scalars::Id, model::ModelManager};
@@ -9,7 +11,7 @@ use super::model::{Album, AlbumForCreate, AlbumForUpdate, AlbumPostForCreate};
 #[derive(Default)]
 pub struct AlbumMutation;
 
-#[Object]
+#[Object(guard = "ScopeGuard::new(Scope::AlbumsWrite)")]
 impl AlbumMutation {
     pub async fn create_album(&self, ctx: &Context<'_>, input: AlbumForCreate) -> Result<Album> {
         let mm = ctx.data_opt::<ModelManager>();
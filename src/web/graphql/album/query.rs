@@ -1,6 +1,9 @@
 use async_graphql::{Context, Object, Result};
 
+use crate::graphql::guard::ScopeGuard;
+use crate::graphql::Scope;
 use crate::model::album::AlbumBmc;
+use crate::web::graphql::connection::{connection, page_request, PageConnection};
 use crate::web::graphql::error::Error as GraphQLError;
 use crate::{graphql::scalars::Id, model::ModelManager};
 
@@ -9,7 +12,7 @@ use super::model::Album;
 #[derive(Default)]
 pub struct AlbumQuery;
 
-#[Object]
+#[Object(guard = "ScopeGuard::new(Scope::AlbumsRead)")]
 impl AlbumQuery {
     async fn album(&self, ctx: &Context<'_>, id: Id) -> Result<Album> {
         let mm = ctx.data_opt::<ModelManager>();
@@ -22,14 +25,20 @@ impl AlbumQuery {
         Ok(album.into())
     }
 
-    async fn albums(&self, ctx: &Context<'_>) -> Result<Vec<Album>> {
+    async fn albums(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+    ) -> Result<PageConnection<Album>> {
         let mm = ctx.data_opt::<ModelManager>();
         let mm = match mm {
             Some(mm) => mm,
             None => return Err(GraphQLError::ModalManagerNotInContext.into()),
         };
 
-        let albums = AlbumBmc::list(mm).map_err(GraphQLError::ModelError)?;
-        Ok(albums.into_iter().map(|a| a.into()).collect())
+        let page = page_request(first, after)?;
+        let albums = AlbumBmc::list(mm, &page).map_err(GraphQLError::ModelError)?;
+        Ok(connection(albums, &page))
     }
 }
@@ -0,0 +1,49 @@
+use async_graphql::{InputObject, SimpleObject};
+use serde::{Deserialize, Serialize};
+
+use crate::graphql::scalars::{DateTime, Id};
+use crate::graphql::Scope;
+
+/// ### API token
+/// Personal token sent as `Authorization: Bearer`, limited to its scopes.
+#[derive(SimpleObject, Debug, Clone, Serialize)]
+pub struct ApiToken {
+    pub id: Id,
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    pub expires_at: Option<DateTime>,
+    pub last_used_at: Option<DateTime>,
+    pub created_at: DateTime,
+}
+
+impl From<crate::model::api_token::ApiToken> for ApiToken {
+    fn from(api_token: crate::model::api_token::ApiToken) -> Self {
+        Self {
+            id: api_token.id.into(),
+            scopes: api_token
+                .scopes()
+                .filter_map(|scope| scope.parse().ok())
+                .collect(),
+            name: api_token.name,
+            expires_at: api_token.expires_at.map(|expires_at| expires_at.into()),
+            last_used_at: api_token
+                .last_used_at
+                .map(|last_used_at| last_used_at.into()),
+            created_at: api_token.created_at.into(),
+        }
+    }
+}
+
+/// Created token, `token` is shown only once.
+#[derive(SimpleObject, Debug, Clone, Serialize)]
+pub struct CreatedApiToken {
+    pub token: String,
+    pub api_token: ApiToken,
+}
+
+#[derive(Debug, Clone, Deserialize, InputObject)]
+pub struct ApiTokenForCreate {
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    pub expires_at: Option<DateTime>,
+}
@@ -0,0 +1,89 @@
+use async_graphql::{Context, Object, Result};
+
+use crate::ctx::Ctx;
+use crate::graphql::guard::SessionGuard;
+use crate::graphql::scalars::Id;
+use crate::model::api_token::ApiTokenBmc;
+use crate::model::ModelManager;
+use crate::web::graphql::error::Error as GraphQLError;
+
+use super::model::{ApiTokenForCreate, CreatedApiToken};
+
+const MAX_NAME_LENGTH: usize = 128;
+
+#[derive(Default)]
+pub struct ApiTokenMutation;
+
+#[Object(guard = "SessionGuard")]
+impl ApiTokenMutation {
+    /// creates API token of logged user, returned `token` can't be shown again
+    async fn create_api_token(
+        &self,
+        ctx: &Context<'_>,
+        input: ApiTokenForCreate,
+    ) -> Result<CreatedApiToken> {
+        let mm = ctx.data_opt::<ModelManager>();
+        let mm = match mm {
+            Some(mm) => mm,
+            None => return Err(GraphQLError::ModalManagerNotInContext.into()),
+        };
+
+        let user_id = match ctx.data_opt::<Ctx>() {
+            Some(ctx) => ctx.user_id,
+            None => return Err(GraphQLError::AccessError("No user logged in".to_string()).into()),
+        };
+
+        let name = input.name.trim();
+        if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+            return Err(GraphQLError::InvalidInput(format!(
+                "name must have 1 to {MAX_NAME_LENGTH} characters"
+            ))
+            .into());
+        }
+        if input.scopes.is_empty() {
+            return Err(GraphQLError::InvalidInput("scopes can't be empty".to_string()).into());
+        }
+
+        let expires_at: Option<chrono::DateTime<chrono::Utc>> =
+            input.expires_at.map(|expires_at| expires_at.into());
+        if expires_at.is_some_and(|expires_at| expires_at <= chrono::Utc::now()) {
+            return Err(
+                GraphQLError::InvalidInput("expires_at must be in future".to_string()).into(),
+            );
+        }
+
+        let mut scopes: Vec<String> = input.scopes.iter().map(|scope| scope.to_string()).collect();
+        scopes.sort();
+        scopes.dedup();
+
+        let (api_token, token) = ApiTokenBmc::create(mm, &user_id, name, scopes, expires_at)
+            .map_err(GraphQLError::ModelError)?;
+
+        Ok(CreatedApiToken {
+            token: token.to_string(),
+            api_token: api_token.into(),
+        })
+    }
+
+    /// deletes API token of logged user, it stops working immediately
+    async fn revoke_api_token(&self, ctx: &Context<'_>, api_token_id: Id) -> Result<String> {
+        let mm = ctx.data_opt::<ModelManager>();
+        let mm = match mm {
+            Some(mm) => mm,
+            None => return Err(GraphQLError::ModalManagerNotInContext.into()),
+        };
+
+        let user_id = match ctx.data_opt::<Ctx>() {
+            Some(ctx) => ctx.user_id,
+            None => return Err(GraphQLError::AccessError("No user logged in".to_string()).into()),
+        };
+
+        let deleted = ApiTokenBmc::delete(mm, &user_id, &api_token_id.into())
+            .map_err(GraphQLError::ModelError)?;
+        if deleted == 0 {
+            return Err(GraphQLError::NotFound.into());
+        }
+
+        Ok("API token revoked".to_string())
+    }
+}
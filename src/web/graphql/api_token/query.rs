@@ -0,0 +1,34 @@
+use async_graphql::{Context, Object, Result};
+
+use crate::ctx::Ctx;
+use crate::graphql::guard::SessionGuard;
+use crate::model::api_token::ApiTokenBmc;
+use crate::model::ModelManager;
+use crate::web::graphql::error::Error as GraphQLError;
+
+use super::model::ApiToken;
+
+#[derive(Default)]
+pub struct ApiTokenQuery;
+
+#[Object(guard = "SessionGuard")]
+impl ApiTokenQuery {
+    /// API tokens of logged user, newest first
+    async fn my_api_tokens(&self, ctx: &Context<'_>) -> Result<Vec<ApiToken>> {
+        let mm = ctx.data_opt::<ModelManager>();
+        let mm = match mm {
+            Some(mm) => mm,
+            None => return Err(GraphQLError::ModalManagerNotInContext.into()),
+        };
+
+        let user_id = match ctx.data_opt::<Ctx>() {
+            Some(ctx) => ctx.user_id,
+            None => return Err(GraphQLError::AccessError("No user logged in".to_string()).into()),
+        };
+
+        let api_tokens =
+            ApiTokenBmc::list_by_user(mm, &user_id).map_err(GraphQLError::ModelError)?;
+
+        Ok(api_tokens.into_iter().map(|r| r.into()).collect())
+    }
+}
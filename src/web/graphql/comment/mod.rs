@@ -0,0 +1,39 @@
+pub mod model;
+pub mod mutation;
+pub mod query;
+
+pub use mutation::CommentMutation;
+pub use query::CommentQuery;
+
+use async_graphql::Context;
+use uuid::Uuid;
+
+use crate::ctx::Ctx;
+use crate::graphql::guard::Accessship;
+use crate::model::post::{Post, PostBmc};
+use crate::model::ModelManager;
+use crate::web::graphql::error::Error as GraphQLError;
+
+/// Comments are reachable only through post logged user may see,
+/// returns the post with user access to it.
+fn post_access(
+    ctx: &Context<'_>,
+    mm: &ModelManager,
+    post_id: &Uuid,
+) -> async_graphql::Result<(Post, Accessship)> {
+    let user_account_id = match ctx.data_opt::<Ctx>() {
+        Some(ctx) => ctx.account_id,
+        None => return Err(GraphQLError::AccessError("No user logged in".to_string()).into()),
+    };
+
+    let post = PostBmc::get(mm, post_id).map_err(GraphQLError::ModelError)?;
+    let access = post
+        .user_access(mm, user_account_id)
+        .map_err(GraphQLError::ModelError)?;
+
+    if !access.allows(post.public_lvl) {
+        return Err(GraphQLError::AccessError(user_account_id.to_string()).into());
+    }
+
+    Ok((post, access))
+}
@@ -0,0 +1,62 @@
+use async_graphql::{InputObject, SimpleObject};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::graphql::scalars::{DateTime, Id};
+
+/// ### Post comment
+/// Comment hidden by moderator (post creator or admin) is visible only to moderators.
+#[derive(SimpleObject, Debug, Clone, Serialize)]
+pub struct Comment {
+    pub id: Id,
+    pub user_id: Id,
+    pub post_id: Id,
+    pub body: String,
+    pub hidden_at: Option<DateTime>,
+    pub created_at: DateTime,
+    pub updated_at: DateTime,
+}
+
+impl From<crate::model::comment::Comment> for Comment {
+    fn from(comment: crate::model::comment::Comment) -> Self {
+        Self {
+            id: comment.id.into(),
+            user_id: comment.user_id.into(),
+            post_id: comment.post_id.into(),
+            body: comment.body,
+            hidden_at: comment.hidden_at.map(|hidden_at| hidden_at.into()),
+            created_at: comment.created_at.into(),
+            updated_at: comment.updated_at.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, InputObject)]
+pub struct CommentForCreate {
+    pub body: String,
+}
+
+impl CommentForCreate {
+    pub fn into_db(self, user_id: Uuid, post_id: Uuid) -> crate::model::comment::CommentForCreate {
+        crate::model::comment::CommentForCreate {
+            id: Uuid::new_v4(),
+            user_id,
+            post_id,
+            body: self.body,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, InputObject)]
+pub struct CommentForUpdate {
+    pub body: Option<String>,
+}
+
+impl From<CommentForUpdate> for crate::model::comment::CommentForUpdate {
+    fn from(comment: CommentForUpdate) -> Self {
+        Self {
+            body: comment.body,
+            updated_at: chrono::Utc::now(),
+        }
+    }
+}
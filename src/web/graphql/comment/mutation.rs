@@ -0,0 +1,137 @@
+use async_graphql::{Context, Object, Result};
+
+use crate::ctx::Ctx;
+use crate::graphql::guard::{Role, RoleGuard, ScopeGuard};
+use crate::graphql::scalars::Id;
+use crate::graphql::Scope;
+use crate::model::comment::CommentBmc;
+use crate::model::ModelManager;
+use crate::web::graphql::error::Error as GraphQLError;
+
+use super::model::{Comment, CommentForCreate, CommentForUpdate};
+use super::post_access;
+
+#[derive(Default)]
+pub struct CommentMutation;
+
+#[Object(guard = "ScopeGuard::new(Scope::CommentsWrite)")]
+impl CommentMutation {
+    #[graphql(guard = "RoleGuard::new(Role::Commenter).and(ScopeGuard::new(Scope::CommentsWrite))")]
+    async fn create_comment(
+        &self,
+        ctx: &Context<'_>,
+        post_id: Id,
+        comment: CommentForCreate,
+    ) -> Result<Comment> {
+        let mm = ctx.data_opt::<ModelManager>();
+        let mm = match mm {
+            Some(mm) => mm,
+            None => return Err(GraphQLError::ModalManagerNotInContext.into()),
+        };
+
+        let user_id = match ctx.data_opt::<Ctx>() {
+            Some(ctx) => ctx.user_id,
+            None => return Err(GraphQLError::AccessError("No user logged in".to_string()).into()),
+        };
+
+        let (post, _) = post_access(ctx, mm, &post_id.into())?;
+        if post.disable_comments {
+            return Err(GraphQLError::CommentsDisabled.into());
+        }
+
+        let comment = CommentBmc::create(mm, comment.into_db(user_id, post.id))
+            .map_err(GraphQLError::ModelError)?;
+
+        Ok(comment.into())
+    }
+
+    /// only author can edit comment, hidden comment can't be edited
+    #[graphql(guard = "RoleGuard::new(Role::Commenter).and(ScopeGuard::new(Scope::CommentsWrite))")]
+    async fn update_comment(
+        &self,
+        ctx: &Context<'_>,
+        comment_id: Id,
+        comment: CommentForUpdate,
+    ) -> Result<Comment> {
+        let mm = ctx.data_opt::<ModelManager>();
+        let mm = match mm {
+            Some(mm) => mm,
+            None => return Err(GraphQLError::ModalManagerNotInContext.into()),
+        };
+
+        let user_id = match ctx.data_opt::<Ctx>() {
+            Some(ctx) => ctx.user_id,
+            None => return Err(GraphQLError::AccessError("No user logged in".to_string()).into()),
+        };
+
+        let stored = CommentBmc::get(mm, &comment_id.into()).map_err(GraphQLError::ModelError)?;
+        if stored.user_id != user_id || stored.hidden_at.is_some() {
+            return Err(GraphQLError::AccessError(user_id.to_string()).into());
+        }
+
+        let (post, _) = post_access(ctx, mm, &stored.post_id)?;
+        if post.disable_comments {
+            return Err(GraphQLError::CommentsDisabled.into());
+        }
+
+        let comment =
+            CommentBmc::update(mm, &stored.id, comment.into()).map_err(GraphQLError::ModelError)?;
+
+        Ok(comment.into())
+    }
+
+    /// comment can be deleted by its author or moderator of post
+    async fn delete_comment(&self, ctx: &Context<'_>, comment_id: Id) -> Result<String> {
+        let mm = ctx.data_opt::<ModelManager>();
+        let mm = match mm {
+            Some(mm) => mm,
+            None => return Err(GraphQLError::ModalManagerNotInContext.into()),
+        };
+
+        let user_id = match ctx.data_opt::<Ctx>() {
+            Some(ctx) => ctx.user_id,
+            None => return Err(GraphQLError::AccessError("No user logged in".to_string()).into()),
+        };
+
+        let comment = CommentBmc::get(mm, &comment_id.into()).map_err(GraphQLError::ModelError)?;
+        let (_, access) = post_access(ctx, mm, &comment.post_id)?;
+
+        if comment.user_id != user_id && !access.moderates() {
+            return Err(GraphQLError::AccessError(user_id.to_string()).into());
+        }
+
+        CommentBmc::delete(mm, &comment.id).map_err(GraphQLError::ModelError)?;
+
+        Ok("Comment deleted".to_string())
+    }
+
+    /// hides comment from everybody but moderators of post
+    async fn hide_comment(&self, ctx: &Context<'_>, comment_id: Id) -> Result<Comment> {
+        set_hidden(ctx, comment_id, true)
+    }
+
+    async fn restore_comment(&self, ctx: &Context<'_>, comment_id: Id) -> Result<Comment> {
+        set_hidden(ctx, comment_id, false)
+    }
+}
+
+/// only post creator and admin moderate comments
+fn set_hidden(ctx: &Context<'_>, comment_id: Id, hidden: bool) -> Result<Comment> {
+    let mm = ctx.data_opt::<ModelManager>();
+    let mm = match mm {
+        Some(mm) => mm,
+        None => return Err(GraphQLError::ModalManagerNotInContext.into()),
+    };
+
+    let comment = CommentBmc::get(mm, &comment_id.into()).map_err(GraphQLError::ModelError)?;
+    let (post, access) = post_access(ctx, mm, &comment.post_id)?;
+
+    if !access.moderates() {
+        return Err(GraphQLError::AccessError(post.id.to_string()).into());
+    }
+
+    let comment =
+        CommentBmc::set_hidden(mm, &comment.id, hidden).map_err(GraphQLError::ModelError)?;
+
+    Ok(comment.into())
+}
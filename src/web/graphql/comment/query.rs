@@ -0,0 +1,67 @@
+use async_graphql::{Context, Object, Result};
+use uuid::Uuid;
+
+use crate::graphql::guard::ScopeGuard;
+use crate::graphql::Scope;
+use crate::model::comment::CommentBmc;
+use crate::web::graphql::connection::{connection, page_request, PageConnection};
+use crate::web::graphql::error::Error as GraphQLError;
+use crate::{graphql::scalars::Id, model::ModelManager};
+
+use super::model::Comment;
+use super::post_access;
+
+#[derive(Default)]
+pub struct CommentQuery;
+
+#[Object(guard = "ScopeGuard::new(Scope::CommentsRead)")]
+impl CommentQuery {
+    async fn comment(&self, ctx: &Context<'_>, comment_id: Id) -> Result<Comment> {
+        let mm = ctx.data_opt::<ModelManager>();
+        let mm = match mm {
+            Some(mm) => mm,
+            None => return Err(GraphQLError::ModalManagerNotInContext.into()),
+        };
+
+        let comment = CommentBmc::get(mm, &comment_id.into()).map_err(GraphQLError::ModelError)?;
+        let (_, access) = post_access(ctx, mm, &comment.post_id)?;
+
+        if comment.hidden_at.is_some() && !access.moderates() {
+            return Err(GraphQLError::NotFound.into());
+        }
+
+        Ok(comment.into())
+    }
+
+    /// comments of post, hidden comments are listed only for moderators
+    async fn comments(
+        &self,
+        ctx: &Context<'_>,
+        post_id: Id,
+        first: Option<i32>,
+        after: Option<String>,
+    ) -> Result<PageConnection<Comment>> {
+        post_comments(ctx, &post_id.into(), first, after)
+    }
+}
+
+pub fn post_comments(
+    ctx: &Context<'_>,
+    post_id: &Uuid,
+    first: Option<i32>,
+    after: Option<String>,
+) -> Result<PageConnection<Comment>> {
+    let mm = ctx.data_opt::<ModelManager>();
+    let mm = match mm {
+        Some(mm) => mm,
+        None => return Err(GraphQLError::ModalManagerNotInContext.into()),
+    };
+
+    let page = page_request(first, after)?;
+    let (_, access) = post_access(ctx, mm, post_id)?;
+
+    let comments = CommentBmc::list(mm, post_id, access.moderates(), &page)
+        .map_err(GraphQLError::ModelError)?;
+
+    Ok(connection(comments, &page))
+}
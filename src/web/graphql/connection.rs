@@ -0,0 +1,132 @@
+use async_graphql::connection::{Connection, CursorType, Edge, EmptyFields};
+use async_graphql::{OutputType, SimpleObject};
+use chrono::{TimeZone, Utc};
+
+use crate::crypt::{b64u_decode, b64u_encode};
+use crate::model::page::{Cursor, HasCursor, Page, PageRequest};
+
+#[derive(SimpleObject)]
+pub struct ConnectionFields {
+    /// count of all items in the list
+    pub total_count: i64,
+}
+
+/// ### Relay connection
+/// Lists are ordered from the newest, next page is requested by `first` and `after` end cursor
+/// of previous page. Cursors are opaque, they stay valid when items are added or removed.
+pub type PageConnection<T> = Connection<PageCursor, T, ConnectionFields, EmptyFields>;
+
+pub struct PageCursor(pub Cursor);
+
+impl CursorType for PageCursor {
+    type Error = String;
+
+    /// b64u of `{created_at micros}:{id}`
+    fn decode_cursor(s: &str) -> Result<Self, Self::Error> {
+        let invalid = || "Invalid cursor".to_string();
+        let decoded =
+            String::from_utf8(b64u_decode(s).map_err(|_| invalid())?).map_err(|_| invalid())?;
+        let (micros, id) = decoded.split_once(':').ok_or_else(invalid)?;
+
+        let micros = micros.parse::<i64>().map_err(|_| invalid())?;
+        Ok(PageCursor(Cursor {
+            created_at: Utc.timestamp_micros(micros).single().ok_or_else(invalid)?,
+            id: id.parse().map_err(|_| invalid())?,
+        }))
+    }
+
+    fn encode_cursor(&self) -> String {
+        b64u_encode(format!(
+            "{}:{}",
+            self.0.created_at.timestamp_micros(),
+            self.0.id
+        ))
+    }
+}
+
+/// page of `first` items after `after` cursor
+pub fn page_request(
+    first: Option<i32>,
+    after: Option<String>,
+) -> async_graphql::Result<PageRequest> {
+    let after = match after {
+        Some(after) => Some(PageCursor::decode_cursor(&after)?.0),
+        None => None,
+    };
+
+    Ok(PageRequest::new(first.map(i64::from), after))
+}
+
+pub fn connection<M, T>(page: Page<M>, request: &PageRequest) -> PageConnection<T>
+where
+    M: HasCursor,
+    T: From<M> + OutputType,
+{
+    let mut connection = Connection::with_additional_fields(
+        request.after.is_some(),
+        page.has_next_page,
+        ConnectionFields {
+            total_count: page.total_count,
+        },
+    );
+    connection.edges.extend(
+        page.items
+            .into_iter()
+            .map(|item| Edge::new(PageCursor(item.cursor()), item.into())),
+    );
+
+    connection
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+
+    #[test]
+    fn cursor_round_trip() {
+        let cursor = Cursor {
+            created_at: Utc.timestamp_micros(1_700_000_000_123_456).unwrap(),
+            id: Uuid::new_v4(),
+        };
+        let encoded = PageCursor(cursor).encode_cursor();
+
+        assert_eq!(PageCursor::decode_cursor(&encoded).unwrap().0, cursor);
+    }
+
+    #[test]
+    fn cursor_is_opaque() {
+        let cursor = Cursor {
+            created_at: Utc.timestamp_micros(0).unwrap(),
+            id: Uuid::nil(),
+        };
+        let encoded = PageCursor(cursor).encode_cursor();
+
+        assert!(!encoded.contains(':'));
+        assert_eq!(
+            b64u_decode(&encoded).unwrap(),
+            format!("0:{}", Uuid::nil()).into_bytes()
+        );
+    }
+
+    #[test]
+    fn invalid_cursors_are_rejected() {
+        let id = Uuid::new_v4();
+        for decoded in [
+            String::new(),
+            "1700000000".to_string(),
+            format!("now:{}", id),
+            "1700000000:not-uuid".to_string(),
+            format!("{}:{}", i64::MAX, id),
+        ] {
+            assert!(PageCursor::decode_cursor(&b64u_encode(decoded)).is_err());
+        }
+        assert!(PageCursor::decode_cursor("not base64!").is_err());
+    }
+
+    #[test]
+    fn page_request_rejects_invalid_cursor() {
+        assert!(page_request(Some(10), Some("not base64!".to_string())).is_err());
+    }
+}
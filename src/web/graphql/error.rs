@@ -7,7 +7,8 @@ use crate::model::error::Error as ModelError;
 #[derive(Debug)]
 pub enum Error {
     ModalManagerNotInContext,
-    ClientNotInContext,
+    StoreNotInContext,
+    MailNotInContext,
 
     ServerError(crate::services::error::Error),
 
@@ -20,7 +21,10 @@ pub enum Error {
 
     FailedToEncryptPassword,
 
-    NotFound(String),
+    NotFound,
+
+    CommentsDisabled,
+    InvalidInput(String),
 }
 
 impl Display for Error {
@@ -30,10 +34,19 @@ impl Display for Error {
             Error::AuthError => write!(f, "User not logged in"),
             Error::ModelError(ModelError::DbEntityNotFound)
             | Error::AccessError(_)
-            | Error::NotFound(_) => write!(f, "Not found"),
+            | Error::NotFound => write!(f, "Not found"),
+            Error::CommentsDisabled => write!(f, "Comments are disabled"),
+            Error::InvalidInput(message) => write!(f, "{message}"),
+            Error::ModelError(ModelError::RegistrationRejected(rejection)) => {
+                write!(f, "{rejection}")
+            }
+            Error::ModelError(ModelError::InviteRejected(rejection)) => write!(f, "{rejection}"),
+            Error::ModelError(ModelError::SessionRejected(rejection)) => write!(f, "{rejection}"),
+            Error::ModelError(ModelError::PasswordRejected(rejection)) => write!(f, "{rejection}"),
             Error::ServerError(_)
             | Error::ModelError(_)
-            | Error::ClientNotInContext
+            | Error::StoreNotInContext
+            | Error::MailNotInContext
             | Error::FailedToReadFile
             | Error::FailedToEncryptPassword
             | Error::ModalManagerNotInContext => write!(f, "Internal server error"),
@@ -54,7 +67,7 @@ impl Display for Error {
 //                 async_graphql::Error::new("Already exists")
 //             }
 //             Error::ServerError(_)
-//             | Error::ClientNotInContext
+//             | Error::StoreNotInContext
 //             | Error::ModelError(_)
 //             | Error::FailedToReadFile
 //             | Error::FailedToEncryptPassword
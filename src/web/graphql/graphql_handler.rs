@@ -4,11 +4,12 @@ use axum::extract::State;
 use axum::response::{Html, IntoResponse};
 use axum::routing::get;
 use axum::Router;
-use reqwest::Client;
 
 use crate::ctx::Ctx;
 
 use crate::model::ModelManager;
+use crate::services::mail::SharedMailTransport;
+use crate::services::store::SharedImageStore;
 use crate::web::Result;
 
 use super::{create_schema, ImagerySchema};
@@ -16,19 +17,15 @@ use super::{create_schema, ImagerySchema};
 #[derive(Clone)]
 pub struct GraphQlState {
     schema: ImagerySchema,
-    reqwest_client: Client,
+    store: SharedImageStore,
 }
 
-pub fn routes(mm: ModelManager) -> Router {
-    let reqwest_client = Client::new();
-    let schema = create_schema(mm.clone(), reqwest_client.clone());
+pub fn routes(mm: ModelManager, store: SharedImageStore, mail: SharedMailTransport) -> Router {
+    let schema = create_schema(mm.clone(), store.clone(), mail);
     Router::new()
         .route("/", get(graphql_playground).post(graphql_handler))
         .route_service("/ws", GraphQLSubscription::new(schema.clone()))
-        .with_state(GraphQlState {
-            schema,
-            reqwest_client,
-        })
+        .with_state(GraphQlState { schema, store })
 }
 
 pub async fn graphql_playground() -> impl IntoResponse {
@@ -45,8 +42,8 @@ pub async fn graphql_handler(
 ) -> impl IntoResponse {
     let state = graph_ql_state;
     let builer_schema = match ctx {
-        Ok(ctx) => request.0.data(ctx).data(state.reqwest_client),
-        Err(_) => request.0.data(state.reqwest_client),
+        Ok(ctx) => request.0.data(ctx).data(state.store),
+        Err(_) => request.0.data(state.store),
     };
     let builder = state.schema.execute(builer_schema).await;
     let response = GraphQLResponse(builder.into());
@@ -1,17 +1,23 @@
 use async_graphql::{Context, EmptySubscription, MergedObject, Schema};
-use reqwest::Client;
 
 use super::{
     account::{AccountMutation, AccountQuery},
+    comment::{CommentMutation, CommentQuery},
     error::Error,
     post::{PostMutation, PostQuery},
+    sys_config::{SysConfigMutation, SysConfigQuery},
     user::{UserMutation, UserQuery},
 };
+use crate::services::{mail::SharedMailTransport, store::SharedImageStore};
 use crate::{ctx::Ctx, model::ModelManager};
 
 use super::{
     album::{AlbumMutation, AlbumQuery},
+    api_token::{ApiTokenMutation, ApiTokenQuery},
     image::{ImageMutation, ImageQuery},
+    invite::{InviteMutation, InviteQuery},
+    referral::{ReferralMutation, ReferralQuery},
+    session::{SessionMutation, SessionQuery},
     tag::{TagMutation, TagQuery},
 };
 
@@ -48,6 +54,12 @@ pub struct QueryRoot(
     ImageQuery,
     AlbumQuery,
     PostQuery,
+    CommentQuery,
+    InviteQuery,
+    ReferralQuery,
+    SessionQuery,
+    ApiTokenQuery,
+    SysConfigQuery,
 );
 
 #[derive(MergedObject, Default)]
@@ -59,17 +71,28 @@ pub struct MutationRoot(
     ImageMutation,
     AlbumMutation,
     PostMutation,
+    CommentMutation,
+    InviteMutation,
+    ReferralMutation,
+    SessionMutation,
+    ApiTokenMutation,
+    SysConfigMutation,
 );
 
 pub type ImagerySchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
 
-pub fn create_schema(mm: ModelManager, req_client: Client) -> ImagerySchema {
+pub fn create_schema(
+    mm: ModelManager,
+    store: SharedImageStore,
+    mail: SharedMailTransport,
+) -> ImagerySchema {
     Schema::build(
         QueryRoot::default(),
         MutationRoot::default(),
         EmptySubscription::default(),
     )
     .data(mm)
-    .data(req_client)
+    .data(store)
+    .data(mail)
     .finish()
 }
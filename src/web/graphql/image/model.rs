@@ -1,10 +1,17 @@
-use async_graphql::{InputObject, SimpleObject};
+use async_graphql::{ComplexObject, Context, InputObject, SimpleObject};
 use serde::{Deserialize, Serialize};
 
+use crate::config;
+use crate::crypt::signed_url::SignedImage;
+use crate::ctx::Ctx;
 use crate::graphql::{
     scalars::{DateTime, Id},
     ImageKind,
 };
+use crate::model::image_metadata::ImageMetadataBmc;
+use crate::model::ModelManager;
+use crate::services::store::SharedImageStore;
+use crate::web::graphql::error::Error as GraphQLError;
 
 /// ### Image
 /// Image is structure to hold path to image in lust server.\
@@ -12,7 +19,9 @@ use crate::graphql::{
 /// ```
 /// GET /api/image/{image_path}
 /// ```
+/// or without auth cookie by signed `url`.
 #[derive(Debug, Clone, Serialize, SimpleObject)]
+#[graphql(complex)]
 pub struct Image {
     pub id: Id,
     pub user_id: Id,
@@ -23,6 +32,60 @@ pub struct Image {
     pub updated_at: DateTime,
 }
 
+#[ComplexObject]
+impl Image {
+    /// Details read from image original on upload,
+    /// empty for images uploaded before metadata was extracted.\
+    /// Fields stripped from original by EXIF policy are visible only to the owner.
+    pub async fn metadata(
+        &self,
+        ctx: &Context<'_>,
+    ) -> async_graphql::Result<Option<ImageMetadata>> {
+        let mm = ctx.data_opt::<ModelManager>();
+        let mm = match mm {
+            Some(mm) => mm,
+            None => return Err(GraphQLError::ModalManagerNotInContext.into()),
+        };
+        let mut metadata =
+            ImageMetadataBmc::get(mm, &self.id.into()).map_err(GraphQLError::ModelError)?;
+
+        let is_owner = ctx
+            .data_opt::<Ctx>()
+            .map(|ctx| ctx.user_id == self.user_id.0)
+            .unwrap_or(false);
+        if !is_owner {
+            if let Some(metadata) = metadata.as_mut() {
+                metadata.hide_private();
+            }
+        }
+
+        Ok(metadata.map(|metadata| metadata.into()))
+    }
+
+    /// Signed url of image or its `size` preset, usable without auth cookie until it expires.\
+    /// `size` has to be preset configured for image bucket.
+    pub async fn url(
+        &self,
+        ctx: &Context<'_>,
+        size: Option<String>,
+    ) -> async_graphql::Result<String> {
+        if let Some(size) = &size {
+            let store = ctx
+                .data::<SharedImageStore>()
+                .map_err(|_| GraphQLError::StoreNotInContext)?;
+            if !store.has_preset(&config().LUST_IMAGE_BUCKET, size) {
+                return Err(
+                    GraphQLError::InvalidInput(format!("Unknown size preset: {}", size)).into(),
+                );
+            }
+        }
+
+        SignedImage::new(self.path.into(), size)
+            .url()
+            .map_err(|e| async_graphql::Error::new(format!("{:?}", e)))
+    }
+}
+
 impl From<crate::model::image::Image> for Image {
     fn from(image: crate::model::image::Image) -> Self {
         Self {
@@ -37,15 +100,90 @@ impl From<crate::model::image::Image> for Image {
     }
 }
 
+#[derive(Debug, Clone, Serialize, SimpleObject)]
+pub struct GpsPosition {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub altitude: Option<f64>,
+}
+
+/// ### Image metadata
+/// Dimensions, format and EXIF details of image original.\
+/// Fields which couldn't be read from the file are empty.
+#[derive(Debug, Clone, Serialize, SimpleObject)]
+pub struct ImageMetadata {
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub mime_type: Option<String>,
+    pub byte_size: i64,
+    pub captured_at: Option<DateTime>,
+    pub camera_model: Option<String>,
+    /// EXIF orientation 1-8
+    pub orientation: Option<i32>,
+    pub gps: Option<GpsPosition>,
+}
+
+impl From<crate::model::image_metadata::ImageMetadata> for ImageMetadata {
+    fn from(metadata: crate::model::image_metadata::ImageMetadata) -> Self {
+        let gps = match (metadata.gps_latitude, metadata.gps_longitude) {
+            (Some(latitude), Some(longitude)) => Some(GpsPosition {
+                latitude,
+                longitude,
+                altitude: metadata.gps_altitude,
+            }),
+            _ => None,
+        };
+
+        Self {
+            width: metadata.width,
+            height: metadata.height,
+            mime_type: metadata.mime_type,
+            byte_size: metadata.byte_size,
+            captured_at: metadata.captured_at.map(|captured_at| captured_at.into()),
+            camera_model: metadata.camera_model,
+            orientation: metadata.orientation,
+            gps,
+        }
+    }
+}
+
+/// ### Image filter
+/// Search images by metadata, all provided fields have to match.
+#[derive(Debug, Clone, Deserialize, InputObject)]
+pub struct ImageFilter {
+    pub mime_type: Option<String>,
+    /// case insensitive part of camera model
+    pub camera_model: Option<String>,
+    pub captured_after: Option<DateTime>,
+    pub captured_before: Option<DateTime>,
+    pub min_width: Option<i32>,
+    pub min_height: Option<i32>,
+    pub has_gps: Option<bool>,
+}
+
+impl From<ImageFilter> for crate::model::image_metadata::ImageMetadataFilter {
+    fn from(filter: ImageFilter) -> Self {
+        Self {
+            mime_type: filter.mime_type,
+            camera_model: filter.camera_model,
+            captured_after: filter.captured_after.map(|dt| dt.into()),
+            captured_before: filter.captured_before.map(|dt| dt.into()),
+            min_width: filter.min_width,
+            min_height: filter.min_height,
+            has_gps: filter.has_gps,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, InputObject)]
 pub struct ImageForUpdate {
     pub name: Option<String>,
 }
 
-impl Into<crate::model::image::ImageForUpdate> for ImageForUpdate {
-    fn into(self) -> crate::model::image::ImageForUpdate {
+impl From<ImageForUpdate> for crate::model::image::ImageForUpdate {
+    fn from(value: ImageForUpdate) -> Self {
         crate::model::image::ImageForUpdate {
-            name: self.name,
+            name: value.name,
             updated_at: chrono::Utc::now(),
         }
     }
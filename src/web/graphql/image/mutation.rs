@@ -1,12 +1,12 @@
 use async_graphql::{Context, Error, Object, Result};
-use reqwest::Client;
 
 use crate::config;
 use crate::ctx::Ctx;
-use crate::graphql::guard::ResourceGuard;
+use crate::graphql::guard::{ResourceGuard, ScopeGuard};
+use crate::graphql::Scope;
 use crate::model::account::AccountBmc;
 use crate::model::image::ImageBmc;
-use crate::services::lust::Lust;
+use crate::services::store::SharedImageStore;
 use crate::web::graphql::error::Error as GraphQLError;
 use crate::{graphql::scalars::Id, model::ModelManager};
 
@@ -15,9 +15,11 @@ use super::model::{Image, ImageDeleteResult, ImageForUpdate};
 #[derive(Default)]
 pub struct ImageMutation;
 
-#[Object]
+#[Object(guard = "ScopeGuard::new(Scope::ImagesWrite)")]
 impl ImageMutation {
-    #[graphql(guard = "ResourceGuard::new_image(id, false)")]
+    #[graphql(
+        guard = "ResourceGuard::new_image(id, false).and(ScopeGuard::new(Scope::ImagesWrite))"
+    )]
     async fn update_image(
         &self,
         ctx: &Context<'_>,
@@ -33,26 +35,27 @@ impl ImageMutation {
         Ok(image.into())
     }
 
-    #[graphql(guard = "ResourceGuard::new_image(id, true)")]
+    #[graphql(
+        guard = "ResourceGuard::new_image(id, true).and(ScopeGuard::new(Scope::ImagesWrite))"
+    )]
     async fn delete_image(&self, ctx: &Context<'_>, id: Id) -> Result<ImageDeleteResult> {
-        let client = ctx
-            .data::<Client>()
-            .map_err(|_| -> Error { GraphQLError::ClientNotInContext.into() })?;
+        let store = ctx
+            .data::<SharedImageStore>()
+            .map_err(|_| -> Error { GraphQLError::StoreNotInContext.into() })?;
         let mm = ctx.data_opt::<ModelManager>();
         let mm = match mm {
             Some(mm) => mm,
             None => return Err(GraphQLError::ModalManagerNotInContext.into()),
         };
         let image = ImageBmc::get(mm, &id.into()).map_err(GraphQLError::ModelError)?;
-        let lust_del_res = Lust::delete_file(client, &image.kind, image.path.to_string()).await;
-        match lust_del_res {
-            Ok(_) => {
-                let image = ImageBmc::delete(mm, id.0).map_err(GraphQLError::ModelError);
-                match image {
-                    Ok(_) => Ok(ImageDeleteResult::delete_success(id)),
-                    Err(e) => Ok(ImageDeleteResult::delete_failure(id, e.to_string())),
-                }
-            }
+        // blob is shared by images with the same content, it's removed with its last image
+        let unreferenced = ImageBmc::delete(mm, id.0).map_err(GraphQLError::ModelError);
+        match unreferenced {
+            Ok(Some(path)) => match store.delete(&image.kind, &path.to_string()).await {
+                Ok(_) => Ok(ImageDeleteResult::delete_success(id)),
+                Err(e) => Ok(ImageDeleteResult::delete_failure(id, e.to_string())),
+            },
+            Ok(None) => Ok(ImageDeleteResult::delete_success(id)),
             Err(e) => Ok(ImageDeleteResult::delete_failure(id, e.to_string())),
         }
     }
@@ -68,9 +71,9 @@ impl ImageMutation {
             None => return Err(GraphQLError::ModalManagerNotInContext.into()),
         };
 
-        let client = ctx
-            .data::<Client>()
-            .map_err(|_| -> Error { GraphQLError::ClientNotInContext.into() })?;
+        let store = ctx
+            .data::<SharedImageStore>()
+            .map_err(|_| -> Error { GraphQLError::StoreNotInContext.into() })?;
 
         let (user_id, account_id) = ctx
             .data::<Ctx>()
@@ -99,29 +102,29 @@ impl ImageMutation {
             };
 
             if image.user_id == user_id || is_admin {
-                let file_delete_result =
-                    Lust::delete_file(&client, &config().LUST_IMAGE_BUCKET, image.path.to_string())
-                        .await;
+                let result = ImageBmc::delete(mm, id.into()).map_err(GraphQLError::ModelError);
 
-                match file_delete_result {
-                    Ok(_) => (),
+                let path = match result {
+                    Ok(path) => path,
                     Err(e) => {
                         img_result.push(ImageDeleteResult::delete_failure(id, e.to_string()));
                         continue;
                     }
-                }
+                };
 
-                let result = ImageBmc::delete(mm, id.into()).map_err(GraphQLError::ModelError);
+                // blob still referenced by other images is kept in store
+                if let Some(path) = path {
+                    let file_delete_result = store
+                        .delete(&config().LUST_IMAGE_BUCKET, &path.to_string())
+                        .await;
 
-                match result {
-                    Ok(_) => {
-                        img_result.push(ImageDeleteResult::delete_success(id));
-                    }
-                    Err(e) => {
+                    if let Err(e) = file_delete_result {
                         img_result.push(ImageDeleteResult::delete_failure(id, e.to_string()));
                         continue;
                     }
                 }
+
+                img_result.push(ImageDeleteResult::delete_success(id));
             } else {
                 img_result.push(ImageDeleteResult::delete_failure(
                     id,
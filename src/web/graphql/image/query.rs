@@ -1,19 +1,28 @@
 use async_graphql::{Context, Object, Result};
 
 use crate::ctx::Ctx;
+use crate::graphql::guard::ScopeGuard;
+use crate::graphql::Scope;
 use crate::model::account::{Account, AccountBmc};
 use crate::model::ModelManager;
+use crate::web::graphql::connection::{connection, page_request, PageConnection};
 use crate::web::graphql::error::Error as GraphQLError;
 
-use super::model::Image;
+use super::model::{Image, ImageFilter};
 
 #[derive(Default)]
 pub struct ImageQuery;
 
-#[Object]
+#[Object(guard = "ScopeGuard::new(Scope::ImagesRead)")]
 impl ImageQuery {
-    /// return logged user images
-    async fn images(&self, ctx: &Context<'_>) -> Result<Vec<Image>> {
+    /// return logged user images, optionally filtered by image metadata
+    async fn images(
+        &self,
+        ctx: &Context<'_>,
+        filter: Option<ImageFilter>,
+        first: Option<i32>,
+        after: Option<String>,
+    ) -> Result<PageConnection<Image>> {
         let mm = ctx.data_opt::<ModelManager>();
         let mm = match mm {
             Some(mm) => mm,
@@ -33,8 +42,14 @@ impl ImageQuery {
             return Err(GraphQLError::AccessError("User is banned".to_string()).into());
         }
 
-        let images = crate::model::image::ImageBmc::list_user(mm, &user_id)
-            .map_err(GraphQLError::ModelError)?;
-        Ok(images.into_iter().map(|r| r.into()).collect())
+        let page = page_request(first, after)?;
+        let images = match filter {
+            Some(filter) => {
+                crate::model::image::ImageBmc::search_user(mm, &user_id, filter.into(), &page)
+            }
+            None => crate::model::image::ImageBmc::list_user(mm, &user_id, &page),
+        }
+        .map_err(GraphQLError::ModelError)?;
+        Ok(connection(images, &page))
     }
 }
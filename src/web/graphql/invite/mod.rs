@@ -0,0 +1,6 @@
+pub mod model;
+pub mod mutation;
+pub mod query;
+
+pub use mutation::InviteMutation;
+pub use query::InviteQuery;
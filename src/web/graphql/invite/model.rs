@@ -0,0 +1,42 @@
+use async_graphql::{InputObject, SimpleObject};
+use serde::{Deserialize, Serialize};
+
+use crate::graphql::scalars::{DateTime, Id, PublicLvl};
+
+/// ### Invite
+/// Code minted by creator, redeeming it at registration or by logged user creates referral
+/// to the creator. Referral opens content of `public_lvl` and expires with the invite.
+#[derive(SimpleObject, Debug, Clone, Serialize)]
+pub struct Invite {
+    pub id: Id,
+    pub code: String,
+    pub referrer_id: Id,
+    pub public_lvl: PublicLvl,
+    pub max_uses: i32,
+    pub uses: i32,
+    pub expires_at: Option<DateTime>,
+    pub created_at: DateTime,
+}
+
+impl From<crate::model::invite::Invite> for Invite {
+    fn from(invite: crate::model::invite::Invite) -> Self {
+        Self {
+            id: invite.id.into(),
+            code: invite.code,
+            referrer_id: invite.referrer_id.into(),
+            public_lvl: invite.public_lvl.into(),
+            max_uses: invite.max_uses,
+            uses: invite.uses,
+            expires_at: invite.expires_at.map(|expires_at| expires_at.into()),
+            created_at: invite.created_at.into(),
+        }
+    }
+}
+
+/// `public_lvl` defaults to subscribers, `max_uses` to single use
+#[derive(Debug, Clone, Deserialize, InputObject)]
+pub struct InviteForCreate {
+    pub public_lvl: Option<PublicLvl>,
+    pub max_uses: Option<i32>,
+    pub expires_at: Option<DateTime>,
+}
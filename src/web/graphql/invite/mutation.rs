@@ -0,0 +1,98 @@
+use async_graphql::{Context, Object, Result};
+
+use crate::ctx::Ctx;
+use crate::graphql::guard::{Role, RoleGuard, ScopeGuard};
+use crate::graphql::scalars::{Id, PublicLvl};
+use crate::graphql::Scope;
+use crate::model::invite::InviteBmc;
+use crate::model::ModelManager;
+use crate::web::graphql::error::Error as GraphQLError;
+
+use super::model::{Invite, InviteForCreate};
+
+#[derive(Default)]
+pub struct InviteMutation;
+
+#[Object(guard = "ScopeGuard::new(Scope::AccountWrite)")]
+impl InviteMutation {
+    /// mints invite code of logged creator
+    #[graphql(guard = "RoleGuard::new(Role::Creator).and(ScopeGuard::new(Scope::AccountWrite))")]
+    async fn create_invite(&self, ctx: &Context<'_>, input: InviteForCreate) -> Result<Invite> {
+        let mm = ctx.data_opt::<ModelManager>();
+        let mm = match mm {
+            Some(mm) => mm,
+            None => return Err(GraphQLError::ModalManagerNotInContext.into()),
+        };
+
+        let account_id = match ctx.data_opt::<Ctx>() {
+            Some(ctx) => ctx.account_id,
+            None => return Err(GraphQLError::AccessError("No user logged in".to_string()).into()),
+        };
+
+        // referral never opens private content
+        let public_lvl = input.public_lvl.unwrap_or(PublicLvl::Subscribers);
+        if public_lvl == PublicLvl::Private {
+            return Err(
+                GraphQLError::InvalidInput("public_lvl can't be private".to_string()).into(),
+            );
+        }
+        let max_uses = input.max_uses.unwrap_or(1);
+        if max_uses < 1 {
+            return Err(GraphQLError::InvalidInput("max_uses must be positive".to_string()).into());
+        }
+
+        let invite = InviteBmc::create(
+            mm,
+            crate::model::invite::InviteForCreate::new(
+                &account_id,
+                public_lvl.into(),
+                max_uses,
+                input.expires_at.map(|expires_at| expires_at.into()),
+            ),
+        )
+        .map_err(GraphQLError::ModelError)?;
+
+        Ok(invite.into())
+    }
+
+    /// deletes invite of logged creator, referrals created by it are kept
+    #[graphql(guard = "RoleGuard::new(Role::Creator).and(ScopeGuard::new(Scope::AccountWrite))")]
+    async fn revoke_invite(&self, ctx: &Context<'_>, invite_id: Id) -> Result<String> {
+        let mm = ctx.data_opt::<ModelManager>();
+        let mm = match mm {
+            Some(mm) => mm,
+            None => return Err(GraphQLError::ModalManagerNotInContext.into()),
+        };
+
+        let account_id = match ctx.data_opt::<Ctx>() {
+            Some(ctx) => ctx.account_id,
+            None => return Err(GraphQLError::AccessError("No user logged in".to_string()).into()),
+        };
+
+        let deleted = InviteBmc::delete(mm, &account_id, &invite_id.into())
+            .map_err(GraphQLError::ModelError)?;
+        if deleted == 0 {
+            return Err(GraphQLError::NotFound.into());
+        }
+
+        Ok("Invite revoked".to_string())
+    }
+
+    /// redeems invite code by logged user, creates referral to the invite creator
+    async fn redeem_invite(&self, ctx: &Context<'_>, code: String) -> Result<String> {
+        let mm = ctx.data_opt::<ModelManager>();
+        let mm = match mm {
+            Some(mm) => mm,
+            None => return Err(GraphQLError::ModalManagerNotInContext.into()),
+        };
+
+        let account_id = match ctx.data_opt::<Ctx>() {
+            Some(ctx) => ctx.account_id,
+            None => return Err(GraphQLError::AccessError("No user logged in".to_string()).into()),
+        };
+
+        InviteBmc::redeem(mm, code.trim(), &account_id).map_err(GraphQLError::ModelError)?;
+
+        Ok("Invite redeemed".to_string())
+    }
+}
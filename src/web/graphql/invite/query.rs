@@ -0,0 +1,36 @@
+use async_graphql::{Context, Object, Result};
+
+use crate::ctx::Ctx;
+use crate::graphql::guard::{Role, RoleGuard, ScopeGuard};
+use crate::graphql::Scope;
+use crate::model::invite::InviteBmc;
+use crate::model::ModelManager;
+use crate::web::graphql::error::Error as GraphQLError;
+
+use super::model::Invite;
+
+#[derive(Default)]
+pub struct InviteQuery;
+
+#[Object(guard = "ScopeGuard::new(Scope::AccountRead)")]
+impl InviteQuery {
+    /// invites minted by logged creator, newest first
+    #[graphql(guard = "RoleGuard::new(Role::Creator).and(ScopeGuard::new(Scope::AccountRead))")]
+    async fn my_invites(&self, ctx: &Context<'_>) -> Result<Vec<Invite>> {
+        let mm = ctx.data_opt::<ModelManager>();
+        let mm = match mm {
+            Some(mm) => mm,
+            None => return Err(GraphQLError::ModalManagerNotInContext.into()),
+        };
+
+        let account_id = match ctx.data_opt::<Ctx>() {
+            Some(ctx) => ctx.account_id,
+            None => return Err(GraphQLError::AccessError("No user logged in".to_string()).into()),
+        };
+
+        let invites =
+            InviteBmc::list_by_referrer(mm, &account_id).map_err(GraphQLError::ModelError)?;
+
+        Ok(invites.into_iter().map(|r| r.into()).collect())
+    }
+}
@@ -1,10 +1,17 @@
 pub mod account;
 pub mod album;
+pub mod api_token;
+pub mod comment;
+pub mod connection;
 pub mod error;
 pub mod graphql_handler;
 pub mod graphql_root;
 pub mod image;
+pub mod invite;
 pub mod post;
+pub mod referral;
+pub mod session;
+pub mod sys_config;
 pub mod tag;
 pub mod user;
 
@@ -1,11 +1,13 @@
-use async_graphql::{ComplexObject, Context, InputObject, SimpleObject};
+use async_graphql::{ComplexObject, Context, InputObject, SimpleObject, Union};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::web::graphql::comment::{model::Comment, query::post_comments};
+use crate::web::graphql::connection::PageConnection;
 use crate::web::graphql::error::Error as GraphQLError;
 use crate::{
     graphql::scalars::{DateTime, Id, PublicLvl},
-    model::{image::ImageBmc, ModelManager},
+    model::{favorite_image::FavBmc, image::ImageBmc, ModelManager},
     web::graphql::image::model::Image,
 };
 
@@ -40,6 +42,49 @@ impl Post {
 
         Ok(images.into_iter().map(|r| r.into()).collect())
     }
+
+    /// ### Post comments
+    /// Visible to logged users with access to post, hidden comments only to moderators.
+    pub async fn comments(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+    ) -> async_graphql::Result<PageConnection<Comment>> {
+        post_comments(ctx, &self.id.into(), first, after)
+    }
+
+    /// how many users favorited post
+    pub async fn favorite_count(&self, ctx: &Context<'_>) -> async_graphql::Result<i64> {
+        let mm = ctx.data_opt::<ModelManager>();
+        let mm = match mm {
+            Some(mm) => mm,
+            None => return Err(GraphQLError::ModalManagerNotInContext.into()),
+        };
+
+        let count = FavBmc::count_post(mm, &self.id.into()).map_err(GraphQLError::ModelError)?;
+
+        Ok(count)
+    }
+
+    /// whether logged user favorited post, always false without user
+    pub async fn is_favorited(&self, ctx: &Context<'_>) -> async_graphql::Result<bool> {
+        let mm = ctx.data_opt::<ModelManager>();
+        let mm = match mm {
+            Some(mm) => mm,
+            None => return Err(GraphQLError::ModalManagerNotInContext.into()),
+        };
+
+        let user_id = match ctx.data_opt::<crate::ctx::Ctx>() {
+            Some(ctx) => ctx.user_id,
+            None => return Ok(false),
+        };
+
+        let is_favorited = FavBmc::is_favorited(mm, &user_id, &self.id.into())
+            .map_err(GraphQLError::ModelError)?;
+
+        Ok(is_favorited)
+    }
 }
 
 impl From<crate::model::post::Post> for Post {
@@ -58,6 +103,49 @@ impl From<crate::model::post::Post> for Post {
     }
 }
 
+/// ### Locked post
+/// Post which user may not see yet, e.g. subscriber-only post for non subscriber.\
+/// UI can display placeholder from `blurhash` or `preview` (blurred data url) of its first image
+/// with "Content only for subscribers".
+#[derive(SimpleObject, Debug, Clone, Serialize)]
+pub struct LockedPost {
+    pub id: Id,
+    pub user_id: Id,
+    pub public_lvl: i32,
+    pub created_at: DateTime,
+    pub blurhash: Option<String>,
+    pub preview: Option<String>,
+}
+
+impl From<crate::model::post::LockedPost> for LockedPost {
+    fn from(post: crate::model::post::LockedPost) -> Self {
+        Self {
+            id: post.id.into(),
+            user_id: post.user_id.into(),
+            public_lvl: post.public_lvl,
+            created_at: post.created_at.into(),
+            blurhash: post.blurhash,
+            preview: post.preview,
+        }
+    }
+}
+
+/// Post in user posts list, either visible or locked
+#[derive(Union, Debug, Clone)]
+pub enum PostListItem {
+    Post(Post),
+    LockedPost(LockedPost),
+}
+
+impl From<crate::model::post::PostListItem> for PostListItem {
+    fn from(item: crate::model::post::PostListItem) -> Self {
+        match item {
+            crate::model::post::PostListItem::Post(post) => Self::Post(post.into()),
+            crate::model::post::PostListItem::Locked(post) => Self::LockedPost(post.into()),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, InputObject)]
 pub struct PostForCreate {
     pub title: String,
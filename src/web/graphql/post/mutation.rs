@@ -1,8 +1,10 @@
 use async_graphql::{Context, Object, Result};
 use uuid::Uuid;
 
-use crate::graphql::guard::{CreatorGuard, Role, RoleGuard};
+use crate::graphql::guard::{CreatorGuard, Role, RoleGuard, ScopeGuard};
 use crate::graphql::scalars::Id;
+use crate::graphql::Scope;
+use crate::model::favorite_image::FavBmc;
 use crate::model::post::PostBmc;
 use crate::model::ModelManager;
 use crate::web::graphql::error::Error as GraphQLError;
@@ -12,9 +14,9 @@ use super::model::{Post, PostForCreate, PostForUpdate};
 #[derive(Default)]
 pub struct PostMutation;
 
-#[Object]
+#[Object(guard = "ScopeGuard::new(Scope::PostsWrite)")]
 impl PostMutation {
-    #[graphql(guard = "RoleGuard::new(Role::Creator)")]
+    #[graphql(guard = "RoleGuard::new(Role::Creator).and(ScopeGuard::new(Scope::PostsWrite))")]
     async fn create_post(
         &self,
         ctx: &Context<'_>,
@@ -41,7 +43,7 @@ impl PostMutation {
         Ok(post.into())
     }
 
-    #[graphql(guard = "CreatorGuard::new(post_id, false)")]
+    #[graphql(guard = "CreatorGuard::new(post_id, false).and(ScopeGuard::new(Scope::PostsWrite))")]
     async fn update_post(
         &self,
         ctx: &Context<'_>,
@@ -60,7 +62,7 @@ impl PostMutation {
         Ok(post.into())
     }
 
-    #[graphql(guard = "CreatorGuard::new(post_id, true)")]
+    #[graphql(guard = "CreatorGuard::new(post_id, true).and(ScopeGuard::new(Scope::PostsWrite))")]
     async fn delete_post(&self, ctx: &Context<'_>, post_id: Id) -> Result<String> {
         let mm = ctx.data_opt::<ModelManager>();
         let mm = match mm {
@@ -72,4 +74,48 @@ impl PostMutation {
 
         Ok("Post deleted".to_string())
     }
+
+    /// favorites post user has access to, favoriting it again is no-op
+    async fn favorite_post(&self, ctx: &Context<'_>, post_id: Id) -> Result<Post> {
+        let mm = ctx.data_opt::<ModelManager>();
+        let mm = match mm {
+            Some(mm) => mm,
+            None => return Err(GraphQLError::ModalManagerNotInContext.into()),
+        };
+
+        let (user_id, user_account_id) = match ctx.data_opt::<crate::ctx::Ctx>() {
+            Some(ctx) => (ctx.user_id, ctx.account_id),
+            None => return Err(GraphQLError::AccessError("No user logged in".to_string()).into()),
+        };
+
+        let post = PostBmc::get(mm, &post_id.into()).map_err(GraphQLError::ModelError)?;
+        let access = post
+            .user_access(mm, user_account_id)
+            .map_err(GraphQLError::ModelError)?;
+        if !access.allows(post.public_lvl) {
+            return Err(GraphQLError::AccessError(user_account_id.to_string()).into());
+        }
+
+        FavBmc::favorite(mm, &user_id, &post.id).map_err(GraphQLError::ModelError)?;
+
+        Ok(post.into())
+    }
+
+    /// post can be unfavorited even when user lost access to it
+    async fn unfavorite_post(&self, ctx: &Context<'_>, post_id: Id) -> Result<String> {
+        let mm = ctx.data_opt::<ModelManager>();
+        let mm = match mm {
+            Some(mm) => mm,
+            None => return Err(GraphQLError::ModalManagerNotInContext.into()),
+        };
+
+        let user_id = match ctx.data_opt::<crate::ctx::Ctx>() {
+            Some(ctx) => ctx.user_id,
+            None => return Err(GraphQLError::AccessError("No user logged in".to_string()).into()),
+        };
+
+        FavBmc::unfavorite(mm, &user_id, &post_id.into()).map_err(GraphQLError::ModelError)?;
+
+        Ok("Post unfavorited".to_string())
+    }
 }
@@ -1,17 +1,20 @@
 use async_graphql::{Context, Object, Result};
 
 use crate::ctx::Ctx;
-use crate::graphql::guard::{Accessship, Role, RoleGuard};
-use crate::model::post::PostBmc;
+use crate::graphql::guard::{Accessship, Role, RoleGuard, ScopeGuard};
+use crate::graphql::Scope;
+use crate::model::page::Page;
+use crate::model::post::{PostBmc, PostListItem as PostListItemModel};
+use crate::web::graphql::connection::{connection, page_request, PageConnection};
 use crate::web::graphql::error::Error as GraphQLError;
 use crate::{graphql::scalars::Id, model::ModelManager};
 
-use super::model::Post;
+use super::model::{Post, PostListItem};
 
 #[derive(Default)]
 pub struct PostQuery;
 
-#[Object]
+#[Object(guard = "ScopeGuard::new(Scope::PostsRead)")]
 impl PostQuery {
     async fn post(&self, ctx: &Context<'_>, post_id: Id) -> Result<Post> {
         let mm = ctx.data_opt::<ModelManager>();
@@ -43,28 +46,88 @@ impl PostQuery {
         }
     }
 
-    async fn posts(&self, ctx: &Context<'_>, user_id: Option<Id>) -> Result<Vec<Option<Post>>> {
+    /// posts of user, posts user may not see yet are locked
+    async fn posts(
+        &self,
+        ctx: &Context<'_>,
+        user_id: Option<Id>,
+        first: Option<i32>,
+        after: Option<String>,
+    ) -> Result<PageConnection<PostListItem>> {
         let mm = ctx.data_opt::<ModelManager>();
         let mm = match mm {
             Some(mm) => mm,
             None => return Err(GraphQLError::ModalManagerNotInContext.into()),
         };
 
+        let page = page_request(first, after)?;
         let app_ctx = ctx.data_opt::<Ctx>();
         let (search_user_id, user_account_id) = match (user_id, app_ctx) {
             (Some(user_id), Some(user)) => (user_id.into(), Some(user.account_id)),
             (None, Some(user)) => (user.user_id.into(), Some(user.account_id)),
-            _ => return Ok(vec![]),
+            _ => {
+                return Ok(connection(
+                    Page::<PostListItemModel>::new(vec![], &page, 0),
+                    &page,
+                ))
+            }
         };
 
-        let posts = PostBmc::list(mm, user_account_id, &search_user_id)
+        let posts = PostBmc::list(mm, user_account_id, &search_user_id, &page)
             .map_err(GraphQLError::ModelError)?;
 
-        Ok(posts.into_iter().map(|r| r.map(|r| r.into())).collect())
+        Ok(connection(posts, &page))
+    }
+
+    /// home feed - posts added to feed by subscribed creators,
+    /// or by the single feed user when it's configured
+    async fn feed(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+    ) -> Result<PageConnection<Post>> {
+        let mm = ctx.data_opt::<ModelManager>();
+        let mm = match mm {
+            Some(mm) => mm,
+            None => return Err(GraphQLError::ModalManagerNotInContext.into()),
+        };
+
+        let page = page_request(first, after)?;
+        let user_account_id = ctx.data_opt::<Ctx>().map(|ctx| ctx.account_id);
+
+        let posts = PostBmc::feed(mm, user_account_id, &page).map_err(GraphQLError::ModelError)?;
+
+        Ok(connection(posts, &page))
+    }
+
+    /// posts favorited by logged user, posts user lost access to are left out
+    async fn my_favorites(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+    ) -> Result<PageConnection<Post>> {
+        let mm = ctx.data_opt::<ModelManager>();
+        let mm = match mm {
+            Some(mm) => mm,
+            None => return Err(GraphQLError::ModalManagerNotInContext.into()),
+        };
+
+        let (user_id, user_account_id) = match ctx.data_opt::<Ctx>() {
+            Some(ctx) => (ctx.user_id, ctx.account_id),
+            None => return Err(GraphQLError::AccessError("No user logged in".to_string()).into()),
+        };
+
+        let page = page_request(first, after)?;
+        let posts = PostBmc::list_favorites(mm, &user_id, &user_account_id, &page)
+            .map_err(GraphQLError::ModelError)?;
+
+        Ok(connection(posts, &page))
     }
 
     /// get all posts - allowed only for admin
-    #[graphql(guard = "RoleGuard::new(Role::Admin)")]
+    #[graphql(guard = "RoleGuard::new(Role::Admin).and(ScopeGuard::new(Scope::PostsRead))")]
     async fn posts_all(&self, ctx: &Context<'_>) -> Result<Vec<Post>> {
         let mm = ctx.data_opt::<ModelManager>();
         let mm = match mm {
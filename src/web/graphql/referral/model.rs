@@ -0,0 +1,82 @@
+use async_graphql::{ComplexObject, Context, Result, SimpleObject};
+use serde::Serialize;
+
+use crate::graphql::scalars::{DateTime, Id, PublicLvl};
+use crate::model::referral::ReferralBmc;
+use crate::model::user::UserBmc;
+use crate::model::ModelManager;
+use crate::web::graphql::error::Error as GraphQLError;
+use crate::web::graphql::user::model::User;
+
+/// ### Referral
+/// Subscription of `user_id` account to content of `referrer_id` creator account.\
+/// `granted_by` is admin who added it or creator whose invite was redeemed.
+#[derive(SimpleObject, Debug, Clone, Serialize)]
+#[graphql(complex)]
+pub struct Referral {
+    pub id: Id,
+    pub referrer_id: Id,
+    pub user_id: Id,
+    pub public_lvl: PublicLvl,
+    pub expires_at: Option<DateTime>,
+    pub granted_by: Option<Id>,
+    pub created_at: DateTime,
+    pub updated_at: DateTime,
+}
+
+#[ComplexObject]
+impl Referral {
+    /// user of creator account
+    async fn referrer(&self, ctx: &Context<'_>) -> Result<User> {
+        let mm = ctx.data_opt::<ModelManager>();
+        let mm = match mm {
+            Some(mm) => mm,
+            None => return Err(GraphQLError::ModalManagerNotInContext.into()),
+        };
+
+        let user = UserBmc::get_by_account_id(mm, &self.referrer_id.into())
+            .map_err(GraphQLError::ModelError)?;
+        Ok(user.into())
+    }
+
+    /// user of subscribed account
+    async fn subscriber(&self, ctx: &Context<'_>) -> Result<User> {
+        let mm = ctx.data_opt::<ModelManager>();
+        let mm = match mm {
+            Some(mm) => mm,
+            None => return Err(GraphQLError::ModalManagerNotInContext.into()),
+        };
+
+        let user = UserBmc::get_by_account_id(mm, &self.user_id.into())
+            .map_err(GraphQLError::ModelError)?;
+        Ok(user.into())
+    }
+
+    /// subscriber has no valid referral to the referrer anymore
+    async fn expired(&self, ctx: &Context<'_>) -> Result<bool> {
+        let mm = ctx.data_opt::<ModelManager>();
+        let mm = match mm {
+            Some(mm) => mm,
+            None => return Err(GraphQLError::ModalManagerNotInContext.into()),
+        };
+
+        let expired = ReferralBmc::is_expired(mm, &self.user_id.into(), &self.referrer_id.into())
+            .map_err(GraphQLError::ModelError)?;
+        Ok(expired)
+    }
+}
+
+impl From<crate::model::referral::Referral> for Referral {
+    fn from(referral: crate::model::referral::Referral) -> Self {
+        Self {
+            id: referral.id.into(),
+            referrer_id: referral.referrer_id.into(),
+            user_id: referral.user_id.into(),
+            public_lvl: referral.public_lvl.into(),
+            expires_at: referral.expires_at.map(|expires_at| expires_at.into()),
+            granted_by: referral.granted_by.map(|granted_by| granted_by.into()),
+            created_at: referral.created_at.into(),
+            updated_at: referral.updated_at.into(),
+        }
+    }
+}
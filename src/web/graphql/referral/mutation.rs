@@ -0,0 +1,115 @@
+use async_graphql::{Context, Object, Result};
+use uuid::Uuid;
+
+use crate::ctx::Ctx;
+use crate::graphql::guard::ScopeGuard;
+use crate::graphql::scalars::{DateTime, Id};
+use crate::graphql::Scope;
+use crate::model::account::AccountBmc;
+use crate::model::referral::{ReferralBmc, ReferralForUpdate};
+use crate::model::ModelManager;
+use crate::web::graphql::error::Error as GraphQLError;
+
+use super::model::Referral;
+
+#[derive(Default)]
+pub struct ReferralMutation;
+
+#[Object(guard = "ScopeGuard::new(Scope::AccountWrite)")]
+impl ReferralMutation {
+    /// moves expiration of referral, `null` never expires. Only referrer or admin.
+    async fn extend_referral(
+        &self,
+        ctx: &Context<'_>,
+        referral_id: Id,
+        expires_at: Option<DateTime>,
+    ) -> Result<Referral> {
+        let mm = ctx.data_opt::<ModelManager>();
+        let mm = match mm {
+            Some(mm) => mm,
+            None => return Err(GraphQLError::ModalManagerNotInContext.into()),
+        };
+
+        let referral = referral_access(ctx, mm, &referral_id.into(), false)?;
+
+        let expires_at: Option<chrono::DateTime<chrono::Utc>> =
+            expires_at.map(|expires_at| expires_at.into());
+        if expires_at.is_some_and(|expires_at| expires_at <= chrono::Utc::now()) {
+            return Err(
+                GraphQLError::InvalidInput("expires_at must be in future".to_string()).into(),
+            );
+        }
+
+        let update = ReferralForUpdate {
+            expires_at: Some(expires_at),
+            updated_at: chrono::Utc::now(),
+            ..Default::default()
+        };
+        let referral =
+            ReferralBmc::update(mm, &referral.id, update).map_err(GraphQLError::ModelError)?;
+
+        Ok(referral.into())
+    }
+
+    /// expires referral now, it stays listed. Referrer, admin or the subscriber.
+    async fn expire_referral(&self, ctx: &Context<'_>, referral_id: Id) -> Result<Referral> {
+        let mm = ctx.data_opt::<ModelManager>();
+        let mm = match mm {
+            Some(mm) => mm,
+            None => return Err(GraphQLError::ModalManagerNotInContext.into()),
+        };
+
+        let referral = referral_access(ctx, mm, &referral_id.into(), true)?;
+
+        let now = chrono::Utc::now();
+        let update = ReferralForUpdate {
+            expires_at: Some(Some(now)),
+            updated_at: now,
+            ..Default::default()
+        };
+        let referral =
+            ReferralBmc::update(mm, &referral.id, update).map_err(GraphQLError::ModelError)?;
+
+        Ok(referral.into())
+    }
+
+    /// deletes referral. Referrer, admin or the subscriber.
+    async fn revoke_referral(&self, ctx: &Context<'_>, referral_id: Id) -> Result<String> {
+        let mm = ctx.data_opt::<ModelManager>();
+        let mm = match mm {
+            Some(mm) => mm,
+            None => return Err(GraphQLError::ModalManagerNotInContext.into()),
+        };
+
+        let referral = referral_access(ctx, mm, &referral_id.into(), true)?;
+
+        ReferralBmc::delete(mm, &referral.id).map_err(GraphQLError::ModelError)?;
+
+        Ok("Referral revoked".to_string())
+    }
+}
+
+/// referral managed by logged referrer or admin, `subscriber` allows subscribed user too
+fn referral_access(
+    ctx: &Context<'_>,
+    mm: &ModelManager,
+    referral_id: &Uuid,
+    subscriber: bool,
+) -> Result<crate::model::referral::Referral> {
+    let account_id = match ctx.data_opt::<Ctx>() {
+        Some(ctx) => ctx.account_id,
+        None => return Err(GraphQLError::AccessError("No user logged in".to_string()).into()),
+    };
+
+    let referral = ReferralBmc::get(mm, referral_id).map_err(GraphQLError::ModelError)?;
+    if referral.referrer_id == account_id || (subscriber && referral.user_id == account_id) {
+        return Ok(referral);
+    }
+
+    let account = AccountBmc::get(mm, &account_id).map_err(GraphQLError::ModelError)?;
+    if !account.is_admin {
+        return Err(GraphQLError::AccessError(referral.id.to_string()).into());
+    }
+
+    Ok(referral)
+}
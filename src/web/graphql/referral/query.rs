@@ -0,0 +1,68 @@
+use async_graphql::{Context, Object, Result};
+
+use crate::ctx::Ctx;
+use crate::graphql::guard::{Role, RoleGuard, ScopeGuard};
+use crate::graphql::Scope;
+use crate::model::referral::ReferralBmc;
+use crate::model::ModelManager;
+use crate::web::graphql::connection::{connection, page_request, PageConnection};
+use crate::web::graphql::error::Error as GraphQLError;
+
+use super::model::Referral;
+
+#[derive(Default)]
+pub struct ReferralQuery;
+
+#[Object(guard = "ScopeGuard::new(Scope::AccountRead)")]
+impl ReferralQuery {
+    /// referrals to logged creator, expired ones included
+    #[graphql(guard = "RoleGuard::new(Role::Creator).and(ScopeGuard::new(Scope::AccountRead))")]
+    async fn my_subscribers(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+    ) -> Result<PageConnection<Referral>> {
+        let mm = ctx.data_opt::<ModelManager>();
+        let mm = match mm {
+            Some(mm) => mm,
+            None => return Err(GraphQLError::ModalManagerNotInContext.into()),
+        };
+
+        let account_id = match ctx.data_opt::<Ctx>() {
+            Some(ctx) => ctx.account_id,
+            None => return Err(GraphQLError::AccessError("No user logged in".to_string()).into()),
+        };
+
+        let page = page_request(first, after)?;
+        let referrals = ReferralBmc::list_by_referrer(mm, &account_id, &page)
+            .map_err(GraphQLError::ModelError)?;
+
+        Ok(connection(referrals, &page))
+    }
+
+    /// referrals of logged user, expired ones included
+    async fn my_subscriptions(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+    ) -> Result<PageConnection<Referral>> {
+        let mm = ctx.data_opt::<ModelManager>();
+        let mm = match mm {
+            Some(mm) => mm,
+            None => return Err(GraphQLError::ModalManagerNotInContext.into()),
+        };
+
+        let account_id = match ctx.data_opt::<Ctx>() {
+            Some(ctx) => ctx.account_id,
+            None => return Err(GraphQLError::AccessError("No user logged in".to_string()).into()),
+        };
+
+        let page = page_request(first, after)?;
+        let referrals =
+            ReferralBmc::list_by_user(mm, &account_id, &page).map_err(GraphQLError::ModelError)?;
+
+        Ok(connection(referrals, &page))
+    }
+}
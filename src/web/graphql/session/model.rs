@@ -0,0 +1,30 @@
+use async_graphql::SimpleObject;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::graphql::scalars::{DateTime, Id};
+
+/// ### Session
+/// Active login of user, `current` is the session of this request.
+#[derive(SimpleObject, Debug, Clone, Serialize)]
+pub struct Session {
+    pub id: Id,
+    pub user_agent: Option<String>,
+    pub current: bool,
+    pub last_used_at: DateTime,
+    pub expires_at: DateTime,
+    pub created_at: DateTime,
+}
+
+impl Session {
+    pub fn from_db(session: crate::model::session::Session, current_id: &Uuid) -> Self {
+        Self {
+            id: session.id.into(),
+            user_agent: session.user_agent,
+            current: session.id == *current_id,
+            last_used_at: session.last_used_at.into(),
+            expires_at: session.expires_at.into(),
+            created_at: session.created_at.into(),
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use async_graphql::{Context, Object, Result};
+
+use crate::ctx::Ctx;
+use crate::graphql::guard::SessionGuard;
+use crate::graphql::scalars::Id;
+use crate::model::session::SessionBmc;
+use crate::model::ModelManager;
+use crate::web::graphql::error::Error as GraphQLError;
+
+#[derive(Default)]
+pub struct SessionMutation;
+
+#[Object(guard = "SessionGuard")]
+impl SessionMutation {
+    /// revokes session of logged user, its tokens stop working immediately
+    async fn revoke_session(&self, ctx: &Context<'_>, session_id: Id) -> Result<String> {
+        let mm = ctx.data_opt::<ModelManager>();
+        let mm = match mm {
+            Some(mm) => mm,
+            None => return Err(GraphQLError::ModalManagerNotInContext.into()),
+        };
+
+        let user_id = match ctx.data_opt::<Ctx>() {
+            Some(ctx) => ctx.user_id,
+            None => return Err(GraphQLError::AccessError("No user logged in".to_string()).into()),
+        };
+
+        let revoked = SessionBmc::revoke(mm, &user_id, &session_id.into())
+            .map_err(GraphQLError::ModelError)?;
+        if revoked == 0 {
+            return Err(GraphQLError::NotFound.into());
+        }
+
+        Ok("Session revoked".to_string())
+    }
+
+    /// revokes all sessions of logged user but the current one, returns count of revoked
+    async fn revoke_other_sessions(&self, ctx: &Context<'_>) -> Result<i32> {
+        let mm = ctx.data_opt::<ModelManager>();
+        let mm = match mm {
+            Some(mm) => mm,
+            None => return Err(GraphQLError::ModalManagerNotInContext.into()),
+        };
+
+        let (user_id, session_id) = match ctx.data_opt::<Ctx>() {
+            Some(Ctx {
+                user_id,
+                session_id: Some(session_id),
+                ..
+            }) => (*user_id, *session_id),
+            _ => return Err(GraphQLError::AccessError("No user logged in".to_string()).into()),
+        };
+
+        let revoked = SessionBmc::revoke_others(mm, &user_id, &session_id)
+            .map_err(GraphQLError::ModelError)?;
+
+        Ok(revoked as i32)
+    }
+}
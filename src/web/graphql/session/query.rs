@@ -0,0 +1,40 @@
+use async_graphql::{Context, Object, Result};
+
+use crate::ctx::Ctx;
+use crate::graphql::guard::SessionGuard;
+use crate::model::session::SessionBmc;
+use crate::model::ModelManager;
+use crate::web::graphql::error::Error as GraphQLError;
+
+use super::model::Session;
+
+#[derive(Default)]
+pub struct SessionQuery;
+
+#[Object(guard = "SessionGuard")]
+impl SessionQuery {
+    /// active sessions of logged user, recently used first
+    async fn my_sessions(&self, ctx: &Context<'_>) -> Result<Vec<Session>> {
+        let mm = ctx.data_opt::<ModelManager>();
+        let mm = match mm {
+            Some(mm) => mm,
+            None => return Err(GraphQLError::ModalManagerNotInContext.into()),
+        };
+
+        let (user_id, session_id) = match ctx.data_opt::<Ctx>() {
+            Some(Ctx {
+                user_id,
+                session_id: Some(session_id),
+                ..
+            }) => (*user_id, *session_id),
+            _ => return Err(GraphQLError::AccessError("No user logged in".to_string()).into()),
+        };
+
+        let sessions = SessionBmc::list_active(mm, &user_id).map_err(GraphQLError::ModelError)?;
+
+        Ok(sessions
+            .into_iter()
+            .map(|session| Session::from_db(session, &session_id))
+            .collect())
+    }
+}
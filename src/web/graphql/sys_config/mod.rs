@@ -0,0 +1,6 @@
+pub mod model;
+pub mod mutation;
+pub mod query;
+
+pub use mutation::SysConfigMutation;
+pub use query::SysConfigQuery;
@@ -0,0 +1,105 @@
+use async_graphql::{ComplexObject, Context, InputObject, MaybeUndefined, SimpleObject};
+use serde::{Deserialize, Serialize};
+
+use crate::graphql::guard::Role;
+use crate::graphql::scalars::Id;
+use crate::graphql::ExifPolicy;
+use crate::model::sys_config::SysConfigBmc;
+use crate::model::ModelManager;
+use crate::web::graphql::error::Error as GraphQLError;
+
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+#[graphql(complex)]
+pub struct SysConfig {
+    pub allow_registration: bool,
+    pub single_user_feed: Option<Id>,
+    /// default for accounts without own policy
+    pub exif_policy: ExifPolicy,
+    pub exif_retain_private: bool,
+    /// sniffed types accepted on upload
+    pub upload_mime_types: Vec<String>,
+    pub upload_max_width: i32,
+    pub upload_max_height: i32,
+    /// default storage quota of accounts, empty is unlimited
+    pub quota_bytes: Option<i64>,
+    pub quota_images: Option<i32>,
+}
+
+#[ComplexObject]
+impl SysConfig {
+    /// max original size by account kind, other kinds are limited by server config
+    pub async fn upload_limits(
+        &self,
+        ctx: &Context<'_>,
+    ) -> async_graphql::Result<Vec<UploadLimit>> {
+        let mm = ctx.data_opt::<ModelManager>();
+        let mm = match mm {
+            Some(mm) => mm,
+            None => return Err(GraphQLError::ModalManagerNotInContext.into()),
+        };
+        let limits = SysConfigBmc::list_upload_limits(mm).map_err(GraphQLError::ModelError)?;
+        Ok(limits.into_iter().map(|limit| limit.into()).collect())
+    }
+}
+
+impl From<crate::model::sys_config::SysConfig> for SysConfig {
+    fn from(sys_config: crate::model::sys_config::SysConfig) -> Self {
+        Self {
+            allow_registration: sys_config.allow_registration,
+            single_user_feed: sys_config.single_user_feed.map(|id| id.into()),
+            exif_policy: sys_config.exif_policy.into(),
+            exif_retain_private: sys_config.exif_retain_private,
+            upload_mime_types: sys_config.upload_mime_types,
+            upload_max_width: sys_config.upload_max_width,
+            upload_max_height: sys_config.upload_max_height,
+            quota_bytes: sys_config.quota_bytes,
+            quota_images: sys_config.quota_images,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, InputObject)]
+pub struct SysConfigForUpdate {
+    pub allow_registration: Option<bool>,
+    pub single_user_feed: Option<Id>,
+    pub exif_policy: Option<ExifPolicy>,
+    pub exif_retain_private: Option<bool>,
+    pub upload_mime_types: Option<Vec<String>>,
+    pub upload_max_width: Option<i32>,
+    pub upload_max_height: Option<i32>,
+    /// `null` removes the quota
+    pub quota_bytes: MaybeUndefined<i64>,
+    pub quota_images: MaybeUndefined<i32>,
+}
+
+impl From<SysConfigForUpdate> for crate::model::sys_config::SysConfigForUpdate {
+    fn from(input: SysConfigForUpdate) -> Self {
+        Self {
+            allow_registration: input.allow_registration,
+            single_user_feed: input.single_user_feed.map(|id| id.into()),
+            exif_policy: input.exif_policy.map(|policy| policy.to_string()),
+            exif_retain_private: input.exif_retain_private,
+            upload_mime_types: input.upload_mime_types,
+            upload_max_width: input.upload_max_width,
+            upload_max_height: input.upload_max_height,
+            quota_bytes: input.quota_bytes.into(),
+            quota_images: input.quota_images.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct UploadLimit {
+    pub account_kind: Role,
+    /// bytes
+    pub max_size: i64,
+}
+
+impl From<crate::model::sys_config::UploadLimit> for UploadLimit {
+    fn from(limit: crate::model::sys_config::UploadLimit) -> Self {
+        Self {
+            account_kind: limit.account_kind.into(),
+            max_size: limit.max_size,
+        }
+    }
+}
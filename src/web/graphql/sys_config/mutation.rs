@@ -0,0 +1,49 @@
+use async_graphql::{Context, Object, Result};
+
+use crate::graphql::guard::{Role, RoleGuard};
+use crate::model::sys_config::SysConfigBmc;
+use crate::model::ModelManager;
+use crate::web::graphql::error::Error as GraphQLError;
+
+use super::model::{SysConfig, SysConfigForUpdate, UploadLimit};
+
+#[derive(Default)]
+pub struct SysConfigMutation;
+
+#[Object]
+impl SysConfigMutation {
+    #[graphql(guard = "RoleGuard::new(Role::Admin)")]
+    async fn update_sys_config(
+        &self,
+        ctx: &Context<'_>,
+        input: SysConfigForUpdate,
+    ) -> Result<SysConfig> {
+        let mm = ctx.data_opt::<ModelManager>();
+        let mm = match mm {
+            Some(mm) => mm,
+            None => return Err(GraphQLError::ModalManagerNotInContext.into()),
+        };
+        let sys_config =
+            SysConfigBmc::update(mm, input.into()).map_err(GraphQLError::ModelError)?;
+        Ok(sys_config.into())
+    }
+
+    /// Sets max original size for accounts of `account_kind`, `null` removes the limit.
+    #[graphql(guard = "RoleGuard::new(Role::Admin)")]
+    async fn set_upload_limit(
+        &self,
+        ctx: &Context<'_>,
+        account_kind: Role,
+        max_size: Option<i64>,
+    ) -> Result<Option<UploadLimit>> {
+        let mm = ctx.data_opt::<ModelManager>();
+        let mm = match mm {
+            Some(mm) => mm,
+            None => return Err(GraphQLError::ModalManagerNotInContext.into()),
+        };
+        let account_kind: String = account_kind.into();
+        let limit = SysConfigBmc::set_upload_limit(mm, &account_kind, max_size)
+            .map_err(GraphQLError::ModelError)?;
+        Ok(limit.map(|limit| limit.into()))
+    }
+}
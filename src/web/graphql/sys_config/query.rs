@@ -0,0 +1,25 @@
+use async_graphql::{Context, Object, Result};
+
+use crate::graphql::guard::{Role, RoleGuard};
+use crate::model::sys_config::SysConfigBmc;
+use crate::model::ModelManager;
+use crate::web::graphql::error::Error as GraphQLError;
+
+use super::model::SysConfig;
+
+#[derive(Default)]
+pub struct SysConfigQuery;
+
+#[Object]
+impl SysConfigQuery {
+    #[graphql(guard = "RoleGuard::new(Role::Admin)")]
+    async fn sys_config(&self, ctx: &Context<'_>) -> Result<SysConfig> {
+        let mm = ctx.data_opt::<ModelManager>();
+        let mm = match mm {
+            Some(mm) => mm,
+            None => return Err(GraphQLError::ModalManagerNotInContext.into()),
+        };
+        let sys_config = SysConfigBmc::get(mm).map_err(GraphQLError::ModelError)?;
+        Ok(sys_config.into())
+    }
+}
@@ -1,5 +1,7 @@
 use async_graphql::{Context, Object, Result};
 
+use crate::graphql::guard::ScopeGuard;
+use crate::graphql::Scope;
 use crate::model::ModelManager;
 use crate::web::graphql::error::Error as GraphQLError;
 use crate::{graphql::scalars::Id, model::tag::TagBmc};
@@ -9,7 +11,7 @@ use super::model::{Tag, TagForCreate, TagForUpdate};
 #[derive(Default)]
 pub struct TagMutation;
 
-#[Object]
+#[Object(guard = "ScopeGuard::new(Scope::PostsWrite)")]
 impl TagMutation {
     async fn create_tag(&self, ctx: &Context<'_>, tag_for_create: TagForCreate) -> Result<Tag> {
         let mm = ctx.data_opt::<ModelManager>();
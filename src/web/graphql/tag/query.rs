@@ -1,6 +1,9 @@
 use async_graphql::{Context, Object, Result};
 
+use crate::graphql::guard::ScopeGuard;
+use crate::graphql::Scope;
 use crate::model::tag::TagBmc;
+use crate::web::graphql::connection::{connection, page_request, PageConnection};
 use crate::web::graphql::error::Error as GraphQLError;
 use crate::{graphql::scalars::Id, model::ModelManager};
 
@@ -9,7 +12,7 @@ use super::model::Tag;
 #[derive(Default)]
 pub struct TagQuery;
 
-#[Object]
+#[Object(guard = "ScopeGuard::new(Scope::PostsRead)")]
 impl TagQuery {
     async fn tag(&self, ctx: &Context<'_>, id: Id) -> Result<Tag> {
         let mm = ctx.data_opt::<ModelManager>();
@@ -20,6 +23,22 @@ impl TagQuery {
         let tag = TagBmc::get(mm, id.0).map_err(GraphQLError::ModelError)?;
         Ok(tag.into())
     }
+
+    async fn tags(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+    ) -> Result<PageConnection<Tag>> {
+        let mm = ctx.data_opt::<ModelManager>();
+        let mm = match mm {
+            Some(mm) => mm,
+            None => return Err(GraphQLError::ModalManagerNotInContext.into()),
+        };
+        let page = page_request(first, after)?;
+        let tags = TagBmc::list(mm, &page).map_err(GraphQLError::ModelError)?;
+        Ok(connection(tags, &page))
+    }
     // async fn tags(
     //     &self,
     //     ctx: &Context<'_>,
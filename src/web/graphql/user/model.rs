@@ -10,7 +10,9 @@ use crate::model::account::AccountBmc;
 use crate::model::post::PostBmc;
 use crate::model::ModelManager;
 use crate::web::graphql::account::model::Account;
+use crate::web::graphql::connection::{connection, page_request, PageConnection};
 use crate::web::graphql::error::Error as GraphQLError;
+use crate::web::graphql::post::model::PostListItem;
 
 #[derive(SimpleObject, Debug, Clone, Serialize, Deserialize)]
 #[graphql(complex)]
@@ -48,20 +50,21 @@ impl User {
     pub async fn posts(
         &self,
         ctx: &Context<'_>,
-    ) -> Result<Vec<Option<crate::web::graphql::post::model::Post>>> {
+        first: Option<i32>,
+        after: Option<String>,
+    ) -> Result<PageConnection<PostListItem>> {
         let mm = ctx.data_opt::<ModelManager>();
         let mm = match mm {
             Some(mm) => mm,
             None => return Err(GraphQLError::ModalManagerNotInContext.into()),
         };
 
+        let page = page_request(first, after)?;
         let user_id = ctx.data_opt::<Ctx>().map(|r| r.user_id);
         let posts =
-            PostBmc::list(mm, user_id, &self.id.into()).map_err(GraphQLError::ModelError)?;
+            PostBmc::list(mm, user_id, &self.id.into(), &page).map_err(GraphQLError::ModelError)?;
 
-        let posts = posts.into_iter().map(|r| r.map(|r| r.into())).collect();
-
-        Ok(posts)
+        Ok(connection(posts, &page))
     }
 }
 
@@ -122,11 +125,33 @@ impl UserForCreate {
     }
 }
 
+/// Self-service registration, `invite_code` is required when registration is invite only
+#[derive(Debug, Clone, Serialize, Deserialize, InputObject)]
+pub struct UserRegister {
+    pub email: String,
+    pub nick: String,
+    pub password: String,
+    pub fullname: Option<String>,
+    pub invite_code: Option<String>,
+}
+
+impl From<UserRegister> for crate::model::registration::Registration {
+    fn from(input: UserRegister) -> Self {
+        Self {
+            email: input.email,
+            nick: input.nick,
+            password: input.password,
+            fullname: input.fullname,
+            invite_code: input.invite_code,
+        }
+    }
+}
+
+/// Password is changed with `changePassword` or `resetPassword` only
 #[derive(Debug, Clone, Serialize, Deserialize, InputObject)]
 pub struct UserForUpdate {
     pub email: Option<String>,
     pub nick: Option<String>,
-    pub hash: Option<String>,
 }
 
 impl Into<crate::model::user::UserForUpdate> for UserForUpdate {
@@ -134,7 +159,7 @@ impl Into<crate::model::user::UserForUpdate> for UserForUpdate {
         crate::model::user::UserForUpdate {
             email: self.email,
             nick: self.nick,
-            hash: self.hash,
+            hash: None,
             updated_at: chrono::Utc::now(),
         }
     }
@@ -1,18 +1,25 @@
 use async_graphql::{Context, Object, Result};
+use tracing::error;
 
-use crate::graphql::guard::{Role, RoleGuard};
+use crate::crypt::token::ResetToken;
+use crate::ctx::Ctx;
+use crate::graphql::guard::{Role, RoleGuard, ScopeGuard, SessionGuard};
+use crate::graphql::Scope;
+use crate::model::password::{PasswordBmc, PasswordRejection};
+use crate::model::registration::RegistrationBmc;
 use crate::model::user::UserBmc;
+use crate::services::mail::{Mail, SharedMailTransport};
 use crate::web::graphql::error::Error as GraphQLError;
 use crate::{graphql::scalars::Id, model::ModelManager};
 
-use super::model::{User, UserForCreate};
+use super::model::{User, UserForCreate, UserRegister};
 
 #[derive(Default)]
 pub struct UserMutation;
 
-#[Object]
+#[Object(guard = "ScopeGuard::new(Scope::AccountWrite)")]
 impl UserMutation {
-    #[graphql(guard = "RoleGuard::new(Role::Admin)")]
+    #[graphql(guard = "RoleGuard::new(Role::Admin).and(ScopeGuard::new(Scope::AccountWrite))")]
     async fn create_user(&self, ctx: &Context<'_>, input: UserForCreate) -> Result<User> {
         let mm = ctx.data_opt::<ModelManager>();
         let mm = match mm {
@@ -24,7 +31,91 @@ impl UserMutation {
         Ok(user.into())
     }
 
-    #[graphql(guard = "RoleGuard::new(Role::Admin)")]
+    /// self-service registration when it's allowed in sys config, user logs in with `/api/login`
+    async fn register(&self, ctx: &Context<'_>, input: UserRegister) -> Result<User> {
+        let mm = ctx.data_opt::<ModelManager>();
+        let mm = match mm {
+            Some(mm) => mm,
+            None => return Err(GraphQLError::ModalManagerNotInContext.into()),
+        };
+        let (_, user) =
+            RegistrationBmc::register(mm, input.into()).map_err(GraphQLError::ModelError)?;
+        Ok(user.into())
+    }
+
+    /// changes password of logged user, other sessions are revoked
+    #[graphql(guard = "SessionGuard")]
+    async fn change_password(
+        &self,
+        ctx: &Context<'_>,
+        old_password: String,
+        new_password: String,
+    ) -> Result<String> {
+        let mm = ctx.data_opt::<ModelManager>();
+        let mm = match mm {
+            Some(mm) => mm,
+            None => return Err(GraphQLError::ModalManagerNotInContext.into()),
+        };
+
+        let (user_id, session_id) = match ctx.data_opt::<Ctx>() {
+            Some(Ctx {
+                user_id,
+                session_id: Some(session_id),
+                ..
+            }) => (*user_id, *session_id),
+            _ => return Err(GraphQLError::AccessError("No user logged in".to_string()).into()),
+        };
+
+        PasswordBmc::change(mm, &user_id, &old_password, &new_password, &session_id)
+            .map_err(GraphQLError::ModelError)?;
+
+        Ok("Password changed".to_string())
+    }
+
+    /// mails reset token to user with `email`, answer is the same whether such user exists
+    async fn request_password_reset(&self, ctx: &Context<'_>, email: String) -> Result<String> {
+        let mm = ctx.data_opt::<ModelManager>();
+        let mm = match mm {
+            Some(mm) => mm,
+            None => return Err(GraphQLError::ModalManagerNotInContext.into()),
+        };
+        let mail = ctx
+            .data::<SharedMailTransport>()
+            .map_err(|_| -> async_graphql::Error { GraphQLError::MailNotInContext.into() })?;
+
+        let reset = PasswordBmc::request_reset(mm, &email).map_err(GraphQLError::ModelError)?;
+        if let Some((user, token)) = reset {
+            let message = Mail::password_reset(&user.email, &token.to_string());
+            if let Err(e) = mail.send(&message).await {
+                error!("{:<12} - password reset mail - {e:?}", "MAIL");
+            }
+        }
+
+        Ok("Password reset link was sent if the email is registered".to_string())
+    }
+
+    /// sets new password with token from reset mail, all sessions of user are revoked
+    async fn reset_password(
+        &self,
+        ctx: &Context<'_>,
+        token: String,
+        new_password: String,
+    ) -> Result<String> {
+        let mm = ctx.data_opt::<ModelManager>();
+        let mm = match mm {
+            Some(mm) => mm,
+            None => return Err(GraphQLError::ModalManagerNotInContext.into()),
+        };
+
+        let token: ResetToken = token
+            .parse()
+            .map_err(|_| GraphQLError::ModelError(PasswordRejection::ResetTokenInvalid.into()))?;
+        PasswordBmc::reset(mm, &token, &new_password).map_err(GraphQLError::ModelError)?;
+
+        Ok("Password reset, log in with the new password".to_string())
+    }
+
+    #[graphql(guard = "RoleGuard::new(Role::Admin).and(ScopeGuard::new(Scope::AccountWrite))")]
     async fn delete_user(&self, ctx: &Context<'_>, id: Id) -> Result<String> {
         let mm = ctx.data_opt::<ModelManager>();
         let mm = match mm {
@@ -1,8 +1,10 @@
 use async_graphql::{Context, Object, Result};
 
-use crate::graphql::guard::{Accessship, UserQueryGuard};
+use crate::graphql::guard::{Accessship, ScopeGuard, UserQueryGuard};
+use crate::graphql::Scope;
 use crate::model::account::AccountBmc;
 use crate::model::user::UserBmc;
+use crate::web::graphql::connection::{connection, page_request, PageConnection};
 use crate::web::graphql::error::Error as GraphQLError;
 use crate::{graphql::scalars::Id, model::ModelManager};
 
@@ -11,7 +13,7 @@ use super::model::{User, UserSelf};
 #[derive(Default)]
 pub struct UserQuery;
 
-#[Object]
+#[Object(guard = "ScopeGuard::new(Scope::AccountRead)")]
 impl UserQuery {
     async fn user(&self, ctx: &Context<'_>, id: Id) -> Result<User> {
         let mm = ctx.data_opt::<ModelManager>();
@@ -55,17 +57,23 @@ impl UserQuery {
         }
     }
 
-    async fn users(&self, ctx: &Context<'_>) -> Result<Vec<User>> {
+    async fn users(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+    ) -> Result<PageConnection<User>> {
         let mm = ctx.data_opt::<ModelManager>();
         let mm = match mm {
             Some(mm) => mm,
             None => return Err(GraphQLError::ModalManagerNotInContext.into()),
         };
 
+        let page = page_request(first, after)?;
         let user_account_id = ctx.data_opt::<crate::ctx::Ctx>().map(|r| r.account_id);
 
-        let users = UserBmc::list(mm, user_account_id).map_err(GraphQLError::ModelError)?;
+        let users = UserBmc::list(mm, user_account_id, &page).map_err(GraphQLError::ModelError)?;
 
-        Ok(users.into_iter().map(|r| r.into()).collect())
+        Ok(connection(users, &page))
     }
 }
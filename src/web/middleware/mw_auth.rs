@@ -1,14 +1,18 @@
-use crate::crypt::token::Token;
+use crate::crypt::token::{PersonalToken, Token};
 use crate::ctx::Ctx;
+use crate::graphql::Scope;
 use crate::model::account::AccountBmc;
+use crate::model::api_token::ApiTokenBmc;
+use crate::model::session::SessionBmc;
 use crate::model::user::UserBmc;
 use crate::model::ModelManager;
-use crate::web::{set_token_cookie, AUTH_TOKEN};
+use crate::web::AUTH_TOKEN;
 use crate::web::{Error, Result};
 use async_trait::async_trait;
 use axum::extract::{FromRequestParts, State};
 use axum::http::request::Parts;
 use axum::http::Request;
+use axum::http::{header, Method};
 use axum::middleware::Next;
 use axum::response::Response;
 use serde::Serialize;
@@ -28,6 +32,25 @@ pub async fn mw_ctx_require<B>(
     Ok(next.run(req).await)
 }
 
+/// Every `/api` route serves images, API token needs `images:read` to get and `images:write` otherwise.
+pub async fn mw_images_scope<B>(
+    ctx: Result<Ctx>,
+    req: Request<B>,
+    next: Next<B>,
+) -> Result<Response> {
+    if let Ok(ctx) = ctx {
+        let scope = match *req.method() {
+            Method::GET | Method::HEAD => Scope::ImagesRead,
+            _ => Scope::ImagesWrite,
+        };
+        if !ctx.allows(scope) {
+            return Err(Error::TokenScopeMissing { scope });
+        }
+    }
+
+    Ok(next.run(req).await)
+}
+
 pub async fn mw_ctx_resolve<B>(
     mm: State<ModelManager>,
     cookies: Cookies,
@@ -36,11 +59,27 @@ pub async fn mw_ctx_resolve<B>(
 ) -> Result<Response> {
     debug!("{:<12} - mw_ctx_resolve", "MIDDLEWARE");
 
-    let ctx_ext_result = _ctx_resolve(mm, &cookies).await;
+    let bearer = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|token| token.trim().to_string());
 
-    if ctx_ext_result.is_err() && !matches!(ctx_ext_result, Err(CtxExtError::TokenNotInCookie)) {
-        cookies.remove(Cookie::named(AUTH_TOKEN))
-    }
+    let ctx_ext_result = match bearer {
+        Some(token) => _ctx_resolve_bearer(mm, token).await,
+        None => {
+            let ctx_ext_result = _ctx_resolve(mm, &cookies).await;
+
+            if ctx_ext_result.is_err()
+                && !matches!(ctx_ext_result, Err(CtxExtError::TokenNotInCookie))
+            {
+                cookies.remove(Cookie::named(AUTH_TOKEN))
+            }
+
+            ctx_ext_result
+        }
+    };
 
     // Store the ctx_ext_result in the request extension
     // (for Ctx extractor).
@@ -56,28 +95,58 @@ async fn _ctx_resolve(mm: State<ModelManager>, cookies: &Cookies) -> CtxExtResul
         .map(|c| c.value().to_string())
         .ok_or(CtxExtError::TokenNotInCookie)?;
 
+    _ctx_resolve_token(&mm, token)
+}
+
+/// `Authorization: Bearer` is either access token or personal API token
+async fn _ctx_resolve_bearer(mm: State<ModelManager>, token: String) -> CtxExtResult {
+    if !token.starts_with(PersonalToken::PREFIX) {
+        return _ctx_resolve_token(&mm, token);
+    }
+
+    // -- Parse Personal Token
+    let token: PersonalToken = token.parse().map_err(|_| CtxExtError::TokenWrongFormat)?;
+    let api_token =
+        ApiTokenBmc::authenticate(&mm, &token).map_err(|_| CtxExtError::FailValidate)?;
+
+    let user = UserBmc::get(&mm, &api_token.user_id).map_err(|_| CtxExtError::UserNotFound)?;
+    let account = AccountBmc::get(&mm, &user.account_id).map_err(|_| CtxExtError::UserNotFound)?;
+    if account.is_banned {
+        return Err(CtxExtError::FailValidate);
+    }
+
+    // unknown scopes of older tokens are ignored
+    let scopes = api_token
+        .scopes()
+        .filter_map(|scope| scope.parse::<Scope>().ok())
+        .collect();
+
+    Ok(Ctx::new(user.id, account.id, &user.nick, &account.kind).with_scopes(scopes))
+}
+
+fn _ctx_resolve_token(mm: &ModelManager, token: String) -> CtxExtResult {
     // -- Parse Token
     let token: Token = token.parse().map_err(|_| CtxExtError::TokenWrongFormat)?;
     let token_claims = token
         .parse_claims()
         .map_err(|_| CtxExtError::FailValidate)?;
 
+    // -- Validate Token
+    token.validate().map_err(|_| CtxExtError::FailValidate)?;
+
+    // -- Check Session of Token
+    SessionBmc::get_active(mm, &token_claims.id).map_err(|_| CtxExtError::SessionInvalid)?;
+
     // -- Get UserForAuth
-    let user = UserBmc::get(&mm, &token_claims.user_id).map_err(|_| {
+    let user = UserBmc::get(mm, &token_claims.user_id).map_err(|_| {
         debug!("error!");
         CtxExtError::UserNotFound
     })?;
 
-    let account = AccountBmc::get(&mm, &user.account_id).map_err(|_| CtxExtError::UserNotFound)?;
-
-    // -- Validate Token
-    token.validate().map_err(|_| CtxExtError::FailValidate)?;
-
-    // -- Update Token
-    set_token_cookie(cookies, token);
+    let account = AccountBmc::get(mm, &user.account_id).map_err(|_| CtxExtError::UserNotFound)?;
 
     // -- Create CtxExtResult
-    Ok(Ctx::new(user.id, account.id, &user.nick, &account.kind))
+    Ok(Ctx::new(user.id, account.id, &user.nick, &account.kind).with_session(token_claims.id))
 }
 
 // region:    --- Ctx Extractor
@@ -109,6 +178,8 @@ pub enum CtxExtError {
     UserNotFound,
     // ModelAccessError(String),
     FailValidate,
+    /// session of token was revoked or has expired
+    SessionInvalid,
     // CannotSetTokenCookie,
     CtxNotInRequestExt,
     // CtxCreateFail(String),
@@ -5,12 +5,14 @@ pub mod middleware;
 pub mod routes_login;
 
 pub use self::error::{Error, Result};
-use crate::crypt::token::Token;
+use crate::crypt::token::{RefreshToken, Token};
 use tower_cookies::{Cookie, Cookies};
 
 // endregion: --- Modules
 
 pub const AUTH_TOKEN: &str = "auth-token";
+pub const REFRESH_TOKEN: &str = "refresh-token";
+const REFRESH_TOKEN_PATH: &str = "/api/session";
 
 fn set_token_cookie(cookies: &Cookies, token: Token) {
     let mut cookie = Cookie::new(AUTH_TOKEN, token.to_string());
@@ -20,9 +22,24 @@ fn set_token_cookie(cookies: &Cookies, token: Token) {
     cookies.add(cookie);
 }
 
+/// refresh token is sent only to `/api/session/refresh` and `/api/session/logout`
+fn set_refresh_token_cookie(cookies: &Cookies, refresh_token: RefreshToken) {
+    let mut cookie = Cookie::new(REFRESH_TOKEN, refresh_token.to_string());
+    cookie.set_http_only(true);
+    cookie.set_path(REFRESH_TOKEN_PATH);
+
+    cookies.add(cookie);
+}
+
+/// removes both access and refresh token
 fn remove_token_cookie(cookies: &Cookies) {
     let mut cookie = Cookie::named(AUTH_TOKEN);
     cookie.set_path("/");
 
     cookies.remove(cookie);
+
+    let mut cookie = Cookie::named(REFRESH_TOKEN);
+    cookie.set_path(REFRESH_TOKEN_PATH);
+
+    cookies.remove(cookie);
 }
\ No newline at end of file
@@ -1,25 +1,40 @@
 use axum::extract::State;
+use axum::http::{header, HeaderMap};
 use axum::routing::{post, put};
 use axum::{Form, Json, Router};
 use serde::Deserialize;
 use serde_json::{json, Value};
 use tower_cookies::Cookies;
+use uuid::Uuid;
 
+use crate::crypt::token::RefreshToken;
+use crate::ctx::Ctx;
 use crate::model::account::AccountBmc;
+use crate::model::registration::{Registration, RegistrationBmc};
+use crate::model::session::SessionBmc;
+use crate::model::user::User;
 use crate::model::{user::UserBmc, ModelManager};
 
-use super::{remove_token_cookie, set_token_cookie, Error, Result};
+use super::middleware::CtxExtError;
+use super::{
+    remove_token_cookie, set_refresh_token_cookie, set_token_cookie, Error, Result, REFRESH_TOKEN,
+};
 
 pub fn routes(mm: ModelManager) -> Router {
     Router::new()
         .route("/api/login", post(login_handler))
+        // logs out by access token, refresh token cookie isn't sent here
         .route("/api/logout", put(logout_handler))
+        .route("/api/session/logout", put(logout_handler))
+        .route("/api/session/refresh", post(refresh_handler))
+        .route("/api/register", post(register_handler))
         .with_state(mm)
 }
 
 async fn login_handler(
     State(mm): State<ModelManager>,
     cookies: Cookies,
+    headers: HeaderMap,
     Form(payload): Form<UserLogin>,
 ) -> Result<Json<Value>> {
     let user = UserBmc::get_by_email(&mm, &payload.email)?;
@@ -30,9 +45,8 @@ async fn login_handler(
             match account.is_banned {
                 true => return Err(Error::AuthError),
                 false => {
-                    let token = user.into_token()?;
-                    set_token_cookie(&cookies, token);
-                    Ok(Json(json!({ "success": true })))
+                    let body = start_session(&mm, &cookies, &headers, user)?;
+                    Ok(Json(body))
                 }
             }
         }
@@ -42,13 +56,141 @@ async fn login_handler(
     }
 }
 
-async fn logout_handler(cookies: Cookies) -> Result<Json<Value>> {
+/// self-service registration, registered user is logged in
+async fn register_handler(
+    State(mm): State<ModelManager>,
+    cookies: Cookies,
+    headers: HeaderMap,
+    Form(payload): Form<UserRegister>,
+) -> Result<Json<Value>> {
+    let (_, user) = RegistrationBmc::register(&mm, payload.into())?;
+    let user_id = user.id;
+
+    let mut body = start_session(&mm, &cookies, &headers, user)?;
+    body["user_id"] = json!(user_id);
+
+    Ok(Json(body))
+}
+
+/// rotates refresh token and issues new access token of its session
+async fn refresh_handler(
+    State(mm): State<ModelManager>,
+    cookies: Cookies,
+    headers: HeaderMap,
+) -> Result<Json<Value>> {
+    let refresh_token = refresh_token(&cookies, &headers)?;
+
+    let (session, refresh_token) = match SessionBmc::refresh(&mm, &refresh_token) {
+        Ok(refreshed) => refreshed,
+        Err(e) => {
+            remove_token_cookie(&cookies);
+            return Err(e.into());
+        }
+    };
+    let user = UserBmc::get(&mm, &session.user_id)?;
+
+    let body = issue_tokens(&cookies, user, &session.id, refresh_token)?;
+    Ok(Json(body))
+}
+
+/// revokes session of access token, or of refresh token when access token has expired
+async fn logout_handler(
+    State(mm): State<ModelManager>,
+    cookies: Cookies,
+    headers: HeaderMap,
+    ctx: Option<Ctx>,
+) -> Result<Json<Value>> {
+    let session = ctx
+        .and_then(|ctx| ctx.session_id.map(|session_id| (ctx.user_id, session_id)))
+        .or_else(|| {
+            refresh_token(&cookies, &headers)
+                .ok()
+                .and_then(|refresh_token| SessionBmc::get_by_refresh(&mm, &refresh_token).ok())
+                .map(|session| (session.user_id, session.id))
+        });
+    if let Some((user_id, session_id)) = session {
+        SessionBmc::revoke(&mm, &user_id, &session_id)?;
+    }
+
     remove_token_cookie(&cookies);
     Ok(Json(json!({ "success": true })))
 }
 
+/// new session of user with access and refresh token
+fn start_session(
+    mm: &ModelManager,
+    cookies: &Cookies,
+    headers: &HeaderMap,
+    user: User,
+) -> Result<Value> {
+    let user_agent = headers
+        .get(header::USER_AGENT)
+        .and_then(|user_agent| user_agent.to_str().ok());
+    let (session, refresh_token) = SessionBmc::create(mm, &user.id, user_agent)?;
+
+    issue_tokens(cookies, user, &session.id, refresh_token)
+}
+
+/// sets token cookies, tokens are returned too for clients using `Authorization: Bearer`
+fn issue_tokens(
+    cookies: &Cookies,
+    user: User,
+    session_id: &Uuid,
+    refresh_token: RefreshToken,
+) -> Result<Value> {
+    let token = user.into_token(session_id)?;
+    let body = json!({
+        "success": true,
+        "access_token": token.to_string(),
+        "refresh_token": refresh_token.to_string(),
+    });
+
+    set_token_cookie(cookies, token);
+    set_refresh_token_cookie(cookies, refresh_token);
+
+    Ok(body)
+}
+
+/// refresh token from cookie, or `Authorization: Bearer` of clients without cookies
+fn refresh_token(cookies: &Cookies, headers: &HeaderMap) -> Result<RefreshToken> {
+    let token = match cookies.get(REFRESH_TOKEN) {
+        Some(cookie) => cookie.value().to_string(),
+        None => headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(|token| token.trim().to_string())
+            .ok_or(Error::CtxExt(CtxExtError::TokenNotInCookie))?,
+    };
+
+    token
+        .parse()
+        .map_err(|_| Error::CtxExt(CtxExtError::TokenWrongFormat))
+}
+
 #[derive(Deserialize)]
 struct UserLogin {
     email: String,
     password: String,
 }
+
+#[derive(Deserialize)]
+struct UserRegister {
+    email: String,
+    nick: String,
+    password: String,
+    fullname: Option<String>,
+    invite_code: Option<String>,
+}
+
+impl From<UserRegister> for Registration {
+    fn from(payload: UserRegister) -> Self {
+        Self {
+            email: payload.email,
+            nick: payload.nick,
+            password: payload.password,
+            fullname: payload.fullname,
+            invite_code: payload.invite_code,
+        }
+    }
+}